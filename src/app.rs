@@ -1,24 +1,54 @@
-use crate::infra::password_store::{NoopPasswordStore, OsPasswordStore, PasswordStore};
+use crate::config::{Config, PasswordBackend};
+use crate::db;
+use crate::error::{AppError, AppResult};
+use crate::infra::password_store::{OsPasswordStore, PasswordStore};
+use crate::infra::vault_store::VaultPasswordStore;
+use anyhow::Result;
 use sea_orm::DatabaseConnection;
+use std::io::IsTerminal;
 use std::sync::Arc;
 
 pub struct AppContext {
     pub db: DatabaseConnection,
     pub password_store: Arc<dyn PasswordStore>,
+    pub config: Config,
 }
 
 impl AppContext {
-    pub fn new(db: DatabaseConnection) -> Self {
-        let store: Arc<dyn PasswordStore> = if cfg!(any(target_os = "macos", target_os = "windows"))
-        {
-            Arc::new(OsPasswordStore::new("com.jiyamira.jmssh"))
-        } else {
-            Arc::new(NoopPasswordStore)
+    pub fn new(db: DatabaseConnection, config: Config) -> Result<Self> {
+        let store: Arc<dyn PasswordStore> = match config.password_store.backend {
+            // macOS Keychain / Windows Credential Manager / Linux Secret Service + keyutils
+            PasswordBackend::Keyring => Arc::new(OsPasswordStore::new(
+                config.password_store.keyring_service.clone(),
+            )),
+            // 无头机器：口令加密的本地文件
+            PasswordBackend::Vault => Arc::new(open_vault()?),
         };
 
-        Self {
+        Ok(Self {
             db,
             password_store: store,
-        }
+            config,
+        })
     }
 }
+
+pub fn open_vault() -> Result<VaultPasswordStore> {
+    Ok(
+        VaultPasswordStore::new(db::vault_path()?, db::vault_key_cache_path()?)
+            .with_prompt(prompt_vault_passphrase),
+    )
+}
+
+/// 没有会话目录缓存 key 的平台上，每条用到 vault 的命令自己问一次口令
+fn prompt_vault_passphrase() -> AppResult<String> {
+    if !std::io::stdin().is_terminal() {
+        return Err(AppError::VaultError(
+            "vault is locked and the key can't be cached on this system; \
+             run jmssh from a terminal to enter the passphrase"
+                .to_string(),
+        ));
+    }
+    rpassword::prompt_password("vault passphrase: ")
+        .map_err(|e| AppError::IoError(format!("failed to read passphrase: {e}")))
+}
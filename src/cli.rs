@@ -1,3 +1,7 @@
+use crate::config::SortOrder;
+use crate::infra::ansible::InventoryFormat;
+use crate::usecase::ConflictPolicy;
+use crate::usecase::backup::BackupFormat;
 use clap::{Args, Parser, Subcommand};
 
 #[derive(Parser)]
@@ -31,10 +35,37 @@ pub enum Command {
     #[command(visible_alias = "c")]
     Connect(ConnectArgs),
 
-    /// Manage passwords in the OS keyring
+    /// Print the ssh command for a profile (same as `connect --print`)
+    Cmd(ConnectArgs),
+
+    /// Manage stored passwords (OS keyring or vault, see password_store.backend)
     #[command(visible_alias = "pwd")]
     Password(PasswordArgs),
 
+    /// Manage the encrypted password vault (password_store.backend = "vault")
+    Vault(VaultArgs),
+
+    /// Inspect and upgrade the local database schema
+    Db(DbArgs),
+
+    /// List tags with the number of profiles using each
+    Tags,
+
+    /// Show recent connections
+    History(HistoryArgs),
+
+    /// Reconnect to the most recently used profile
+    Last,
+
+    /// Read and change settings in config.toml
+    Config(ConfigArgs),
+
+    /// Import profiles from other tools
+    Import(ImportArgs),
+
+    /// Export profiles for other tools
+    Export(ExportArgs),
+
     /// Internal completion helper (hidden)
     #[command(hide = true)]
     _Complete(CompleteArgs),
@@ -64,7 +95,10 @@ pub enum ProfileCommand {
 
     /// List all profiles
     #[command(visible_alias = "ls")]
-    List,
+    List(ListArgs),
+
+    /// Search profiles, e.g. `host:10.0 port:2200-2299 -tag:legacy`
+    Find(FindArgs),
 }
 
 #[derive(Args)]
@@ -87,24 +121,52 @@ pub struct EditProfileArgs {
     /// Auth mode: auto | password | key
     #[arg(
         long,
-        help = "Auth mode: 'auto' (ssh agent/default), 'password' (keyring+sshpass), 'key' (local private key)"
+        help = "Auth mode: 'auto' (ssh agent/default), 'password' (stored password + autofill), 'key' (local private key)"
     )]
     pub mode: Option<String>,
 
-    /// Optional tags (comma-separated)
+    /// Tags (comma-separated), replaces the current set
     #[arg(
         long,
-        help = "Optional tags for future filtering (currently reserved / no behavior yet)"
+        value_name = "TAGS",
+        help = "Comma-separated tags; replaces all current tags (empty string clears them)"
     )]
     pub tags: Option<String>,
 
+    /// Add or remove a single tag, repeatable
+    ///
+    /// Example: --tag +prod --tag -old
+    #[arg(
+        long = "tag",
+        value_name = "[+|-]TAG",
+        allow_hyphen_values = true,
+        help = "Add (+tag or tag) or remove (-tag) one tag. Repeatable."
+    )]
+    pub tag_changes: Vec<String>,
+
     /// Optional note for human use
     #[arg(
         long,
-        help = "Optional free-form note (currently reserved / no behavior yet)"
+        help = "Free-form note, shown in the picker and searchable with note:"
     )]
     pub note: Option<String>,
 
+    /// Private key path for auth mode 'key'
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Private key file for --mode=key; must exist and not be group/world accessible"
+    )]
+    pub key: Option<String>,
+
+    /// Remove the stored private key path
+    #[arg(
+        long = "clear-key",
+        conflicts_with = "key",
+        help = "Forget the stored private key path"
+    )]
+    pub clear_key: bool,
+
     /// Jump chain labels. If present, replaces the entire chain.
     ///
     /// Example: --jump bastion --jump edge
@@ -113,17 +175,75 @@ pub struct EditProfileArgs {
         help = "Jump chain labels; pass multiple --jump foo --jump bar. Non-empty list replaces the whole chain."
     )]
     pub jumps: Vec<String>,
+
+    /// Extra ssh option for this profile, repeatable
+    ///
+    /// Example: --opt ServerAliveInterval=60 --opt KexAlgorithms=+diffie-hellman-group1-sha1
+    #[arg(
+        long = "opt",
+        value_name = "KEY=VALUE",
+        help = "ssh option for this profile (-o Key=Value); overrides config.toml defaults. Repeatable."
+    )]
+    pub options: Vec<String>,
+
+    /// Remove an ssh option from this profile, repeatable
+    #[arg(
+        long = "unset-opt",
+        value_name = "KEY",
+        help = "Remove a per-profile ssh option by name. Repeatable."
+    )]
+    pub unset_options: Vec<String>,
 }
 
-#[derive(Args)]
+#[derive(Args, Default)]
 pub struct ConnectArgs {
     /// Profile label to connect to, e.g. 'prod.web-1'
-    #[arg(help = "Profile label (recommended) or raw host. Leave empty for interactive mode.")]
+    #[arg(
+        help = "Profile label (recommended) or raw user@host[:port]. Leave empty for interactive mode."
+    )]
     pub target: Option<String>,
 
     /// Optional numeric profile id; when set, overrides the label
     #[arg(long, help = "Profile numeric id; overrides label matching when set")]
     pub id: Option<u32>,
+
+    /// Jump hosts for a raw destination: profile labels or [user@]host[:port]
+    #[arg(long = "jump", value_name = "JUMP")]
+    pub jumps: Vec<String>,
+
+    /// Auth mode for a raw destination: 'auto' | 'password' | 'key'
+    #[arg(long)]
+    pub mode: Option<String>,
+
+    /// Save a raw destination as a profile once ssh has connected to it
+    #[arg(long, value_name = "LABEL")]
+    pub save_as: Option<String>,
+
+    /// Resolve the connection and print the ssh command without running it
+    #[arg(long, conflicts_with = "print")]
+    pub dry_run: bool,
+
+    /// Print only a shell-quoted ssh command (for eval / copy-paste)
+    #[arg(long)]
+    pub print: bool,
+
+    /// Print the resolved plan as JSON instead (hops, argv; never secrets)
+    #[arg(long)]
+    pub json: bool,
+    /// Extra ssh options, repeatable; each value is split into words like a shell would
+    // 不用 `connect <label> [ssh 参数] -- cmd`：裸参数会把写在后面的 jmssh 参数一起吞给 ssh，
+    // 而 `--` 已经用来分隔远端命令
+    #[arg(
+        long = "ssh-opt",
+        value_name = "OPT",
+        allow_hyphen_values = true,
+        help = "Extra ssh option(s) passed through as-is, e.g. --ssh-opt -v --ssh-opt '-L 8080:localhost:80'. Repeatable."
+    )]
+    pub ssh_opts: Vec<String>,
+
+    /// Remote command after `--`; runs non-interactively and forwards its exit code
+    #[arg(value_name = "COMMAND", allow_hyphen_values = true, last = true)]
+    pub remote_command: Vec<String>,
 }
 
 #[derive(Args)]
@@ -132,6 +252,45 @@ pub struct RmArgs {
     /// If NOT provided, opens interactive TUI to pick a profile to delete.
     #[arg(help = "Profile label. Leave empty to select interactively.")]
     pub label: String,
+
+    /// Also drop the jump entries of profiles that go through this one
+    #[arg(long, conflicts_with = "cascade")]
+    pub force: bool,
+
+    /// Also remove every profile that (directly or indirectly) jumps through this one
+    #[arg(long)]
+    pub cascade: bool,
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Only profiles with this tag; prefix with ! to exclude. Repeatable.
+    ///
+    /// Example: --tag prod --tag '!legacy'
+    #[arg(long = "tag", value_name = "[!]TAG")]
+    pub tags: Vec<String>,
+
+    /// Sort order: name, recent (last connected first) or frecency (recent and frequent first)
+    #[arg(long, value_enum, default_value_t = SortOrder::Name)]
+    pub sort: SortOrder,
+}
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// Only connections to this profile
+    pub label: Option<String>,
+
+    /// How many entries to show
+    #[arg(long, short = 'n', default_value_t = 20)]
+    pub limit: u32,
+}
+
+#[derive(Args)]
+pub struct FindArgs {
+    /// Query terms: plain words, field:value (host user label port mode tag note),
+    /// "quoted phrases", port ranges (port:2200-2299); prefix - or ! to negate
+    #[arg(required = true, allow_hyphen_values = true, trailing_var_arg = true)]
+    pub query: Vec<String>,
 }
 
 // Show 不做交互式，因为看详情通常是脚本行为，或者既然都交互了直接看 Preview 就行
@@ -158,13 +317,13 @@ pub struct PasswordArgs {
 
 #[derive(Subcommand)]
 pub enum PasswordCommand {
-    /// Prompt and store password for a profile in OS keyring
+    /// Prompt and store password for a profile in the password store
     Set(PasswordLabelArgs),
 
     /// Print stored password for a profile to stdout
     Show(PasswordLabelArgs),
 
-    /// Delete stored password for a profile from OS keyring
+    /// Delete stored password for a profile from the password store
     Clear(PasswordLabelArgs),
 }
 
@@ -175,6 +334,203 @@ pub struct PasswordLabelArgs {
     pub label: String,
 }
 
+#[derive(Args)]
+pub struct VaultArgs {
+    /// Vault-related subcommands
+    #[command(subcommand)]
+    pub cmd: VaultCommand,
+}
+
+#[derive(Subcommand)]
+pub enum VaultCommand {
+    /// Unlock the vault for this session (creates it on first use)
+    Unlock,
+
+    /// Forget the cached vault key for this session
+    Lock,
+
+    /// Re-encrypt the vault under a new passphrase
+    Rekey,
+
+    /// Show vault location and lock state
+    Status,
+}
+
+#[derive(Args)]
+pub struct DbArgs {
+    /// Database-related subcommands
+    #[command(subcommand)]
+    pub cmd: DbCommand,
+}
+
+#[derive(Subcommand)]
+pub enum DbCommand {
+    /// Show database location and applied / pending migrations
+    Status,
+
+    /// Apply pending migrations
+    Migrate,
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    /// Config-related subcommands
+    #[command(subcommand)]
+    pub cmd: ConfigCommand,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective value of a setting (e.g. defaults.user)
+    Get {
+        /// Dotted key, e.g. ssh.binary or ssh.options.Compression
+        key: String,
+    },
+
+    /// Write a setting to config.toml
+    Set {
+        /// Dotted key, e.g. defaults.port
+        key: String,
+
+        /// New value
+        value: String,
+    },
+
+    /// List every setting with its effective value and where it comes from
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Open config.toml in $VISUAL / $EDITOR and validate it before saving
+    Edit,
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct ImportArgs {
+    /// Import sources other than jmssh's own backup files
+    #[command(subcommand)]
+    pub cmd: Option<ImportCommand>,
+
+    /// Backup file written by `jmssh export --format json|toml`
+    #[arg(required = true, value_name = "FILE")]
+    pub file: Option<String>,
+
+    /// Make the database match the file: profiles missing from it are removed
+    #[arg(long, conflicts_with = "on_conflict")]
+    pub replace: bool,
+
+    #[command(flatten)]
+    pub opts: ImportOptions,
+}
+
+#[derive(Subcommand)]
+pub enum ImportCommand {
+    /// Import Host blocks from an OpenSSH client config (follows Include)
+    SshConfig(ImportSshConfigArgs),
+
+    /// Import Xshell session files (.xsh)
+    Xshell(ImportSessionFilesArgs),
+
+    /// Import a MobaXterm session export (.mxtsessions)
+    Mobaxterm(ImportSessionFilesArgs),
+
+    /// Import PuTTY sessions from a registry export (.reg)
+    Putty(ImportSessionFilesArgs),
+
+    /// Import SecureCRT sessions (.ini session files or an XML export)
+    Securecrt(ImportSessionFilesArgs),
+
+    /// Import hosts from an Ansible inventory (INI or YAML); groups become tags
+    Ansible(ImportAnsibleArgs),
+}
+
+#[derive(Args)]
+pub struct ImportAnsibleArgs {
+    /// Inventory file, e.g. inventory.ini or hosts.yml
+    #[arg(value_name = "INVENTORY")]
+    pub path: String,
+
+    #[command(flatten)]
+    pub opts: ImportOptions,
+}
+
+#[derive(Args)]
+pub struct ImportSessionFilesArgs {
+    /// Files or folders to read; folders are searched recursively and sub-folder names become tags
+    #[arg(required = true, value_name = "PATH")]
+    pub paths: Vec<String>,
+
+    #[command(flatten)]
+    pub opts: ImportOptions,
+}
+
+#[derive(Args)]
+pub struct ImportSshConfigArgs {
+    /// Config file to read (default: ~/.ssh/config)
+    pub path: Option<String>,
+
+    #[command(flatten)]
+    pub opts: ImportOptions,
+}
+
+#[derive(Args)]
+pub struct ImportOptions {
+    /// Show what would be imported without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// What to do when a label already exists: skip, overwrite or rename (web -> web-2)
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = ConflictPolicy::Skip)]
+    pub on_conflict: ConflictPolicy,
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct ExportArgs {
+    /// Export targets other than a full backup
+    #[command(subcommand)]
+    pub cmd: Option<ExportCommand>,
+
+    /// Backup format for the whole profile database
+    #[arg(long, value_enum, default_value_t = BackupFormat::Json)]
+    pub format: BackupFormat,
+
+    /// Also export stored passwords (keep the file somewhere safe)
+    #[arg(long)]
+    pub include_secrets: bool,
+
+    /// Write to this file (mode 0600) instead of stdout
+    #[arg(long, short = 'o', value_name = "FILE")]
+    pub output: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum ExportCommand {
+    /// Render every profile as a `Host <label>` block for scp, rsync, git and IDE plugins
+    SshConfig(ExportSshConfigArgs),
+
+    /// Render every profile as an Ansible inventory host; tags become groups
+    Ansible(ExportAnsibleArgs),
+}
+
+#[derive(Args)]
+pub struct ExportAnsibleArgs {
+    /// Inventory format
+    #[arg(long, value_enum, default_value_t = InventoryFormat::Yaml)]
+    pub format: InventoryFormat,
+
+    /// Write to this file instead of stdout
+    #[arg(long, short = 'o', value_name = "FILE")]
+    pub output: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ExportSshConfigArgs {
+    /// Update the managed file in the config dir instead of printing to stdout
+    #[arg(long)]
+    pub write: bool,
+}
+
 #[derive(Args)]
 pub struct CompleteArgs {
     #[command(subcommand)]
@@ -219,3 +575,42 @@ pub struct GuiArgs {
     )]
     pub endpoint: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn connect_args(argv: &[&str]) -> Result<ConnectArgs, clap::Error> {
+        let cli = Cli::try_parse_from(["jmssh", "connect"].iter().chain(argv))?;
+        match cli.command {
+            Some(Command::Connect(args)) => Ok(args),
+            _ => unreachable!("connect subcommand"),
+        }
+    }
+
+    #[test]
+    fn jmssh_flags_after_ssh_opts_stay_jmssh_flags() {
+        let args = connect_args(&[
+            "web",
+            "--ssh-opt",
+            "-v",
+            "--ssh-opt",
+            "-L 8080:localhost:80",
+            "--dry-run",
+            "--",
+            "uptime",
+        ])
+        .unwrap();
+        assert!(args.dry_run);
+        assert_eq!(args.ssh_opts, ["-v", "-L 8080:localhost:80"]);
+        assert_eq!(args.remote_command, ["uptime"]);
+    }
+
+    #[test]
+    fn unknown_flags_and_bare_words_are_rejected() {
+        assert!(connect_args(&["web", "--dry-rn"]).is_err());
+        assert!(connect_args(&["web", "-v"]).is_err());
+        assert!(connect_args(&["web", "uptime"]).is_err());
+    }
+}
@@ -0,0 +1,615 @@
+//! 全局配置 `config.toml`（放在 ProjectDirs 的 config 目录下）。
+//!
+//! 生效值分三层：内置默认 < 配置文件 < `JMSSH_*` 环境变量。
+//! 文件不存在时全部取默认值；`jmssh config get/set/list/edit` 都走这里的校验。
+
+use crate::db;
+use crate::error::{AppError, AppResult};
+use crate::infra::files::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub defaults: DefaultsSection,
+    pub ssh: SshSection,
+    pub password_store: PasswordStoreSection,
+    pub ui: UiSection,
+}
+
+/// `profile add` 没给时用的值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DefaultsSection {
+    pub user: String,
+    pub port: u16,
+    pub host: String,
+}
+
+impl Default for DefaultsSection {
+    fn default() -> Self {
+        Self {
+            user: "root".to_string(),
+            port: 22,
+            host: "127.0.0.1".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SshSection {
+    /// ssh 可执行文件：PATH 里的名字或绝对路径
+    pub binary: String,
+    pub autofill: AutofillMode,
+    /// 每次连接都带上的 `-o Key=Value`；profile 自己的同名选项优先。
+    /// 写了 `[ssh.options]` 就整体替换默认值（所以也可以借此去掉 Compression 之类）
+    pub options: BTreeMap<String, String>,
+}
+
+impl Default for SshSection {
+    fn default() -> Self {
+        Self {
+            binary: "ssh".to_string(),
+            autofill: AutofillMode::default(),
+            options: [
+                ("Compression", "yes"),
+                ("ServerAliveInterval", "30"),
+                ("ServerAliveCountMax", "3"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        }
+    }
+}
+
+/// 密码自动填充方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutofillMode {
+    /// 内置 PTY 驱动
+    #[default]
+    Builtin,
+    /// 外部 sshpass（只能喂一个密码）
+    Sshpass,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordStoreSection {
+    pub backend: PasswordBackend,
+    pub keyring_service: String,
+}
+
+impl Default for PasswordStoreSection {
+    fn default() -> Self {
+        Self {
+            backend: PasswordBackend::default(),
+            keyring_service: "com.jiyamira.jmssh".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordBackend {
+    /// macOS Keychain / Windows Credential Manager / Linux Secret Service + keyutils
+    #[default]
+    Keyring,
+    /// 无头机器：口令加密的本地文件
+    Vault,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiSection {
+    pub color: ColorMode,
+    pub picker_layout: PickerLayout,
+    /// picker 里没有搜索词（或模糊匹配同分）时的顺序
+    pub picker_sort: SortOrder,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// stderr 是终端且没有 NO_COLOR 时上色
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PickerLayout {
+    /// 列表 + 右侧详情
+    #[default]
+    Split,
+    /// 列表 + 下方详情
+    Stacked,
+    /// 只有列表
+    List,
+}
+
+/// profile 列表的排序方式，`profile list --sort` 和 `ui.picker_sort` 共用
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// By label
+    Name,
+    /// Most recently connected first
+    #[default]
+    Recent,
+    /// Frequently and recently used first
+    Frecency,
+}
+
+/// 环境变量覆盖层：配置项 -> 变量名（`JMSSH_PASSWORD_STORE` / `JMSSH_AUTOFILL` 沿用早先的名字）
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("defaults.user", "JMSSH_DEFAULT_USER"),
+    ("defaults.port", "JMSSH_DEFAULT_PORT"),
+    ("defaults.host", "JMSSH_DEFAULT_HOST"),
+    ("ssh.binary", "JMSSH_SSH_BINARY"),
+    ("ssh.autofill", "JMSSH_AUTOFILL"),
+    ("password_store.backend", "JMSSH_PASSWORD_STORE"),
+    ("password_store.keyring_service", "JMSSH_KEYRING_SERVICE"),
+    ("ui.color", "JMSSH_COLOR"),
+    ("ui.picker_layout", "JMSSH_PICKER_LAYOUT"),
+    ("ui.picker_sort", "JMSSH_PICKER_SORT"),
+];
+
+const OPTIONS_PREFIX: &str = "ssh.options.";
+
+/// 某个配置项的生效值和来源
+#[derive(Debug, Clone)]
+pub struct SettingView {
+    pub key: String,
+    pub value: String,
+    pub source: SettingSource,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingSource {
+    Default,
+    File,
+    Env(&'static str),
+}
+
+impl Config {
+    /// 默认值 < config.toml < 环境变量，合并后校验
+    pub fn load() -> AppResult<Self> {
+        let (config, _) = load_layers()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> AppResult<()> {
+        let non_empty = [
+            ("defaults.user", &self.defaults.user),
+            ("defaults.host", &self.defaults.host),
+            ("ssh.binary", &self.ssh.binary),
+            (
+                "password_store.keyring_service",
+                &self.password_store.keyring_service,
+            ),
+        ];
+        if let Some((key, _)) = non_empty.iter().find(|(_, v)| v.trim().is_empty()) {
+            return Err(AppError::ConfigError(format!("{key} must not be empty")));
+        }
+
+        if self.defaults.port == 0 {
+            return Err(AppError::ConfigError(
+                "defaults.port must be 1-65535".to_string(),
+            ));
+        }
+
+        for (key, value) in &self.ssh.options {
+            validate_option_name(key)?;
+            if value.is_empty() || value.chars().any(char::is_control) {
+                return Err(AppError::ConfigError(format!(
+                    "{OPTIONS_PREFIX}{key} must be a non-empty single-line value"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 列出所有配置项（含 ssh.options 下的每一条）的生效值
+pub fn list_settings() -> AppResult<Vec<SettingView>> {
+    let (config, file) = load_layers()?;
+    let effective = to_table(&config)?;
+
+    let mut out = Vec::new();
+    flatten("", &effective, &mut out);
+
+    Ok(out
+        .into_iter()
+        .map(|(key, value)| {
+            let source = setting_source(&key, &file);
+            SettingView {
+                value: display_value(&value),
+                key,
+                source,
+            }
+        })
+        .collect())
+}
+
+pub fn get_setting(key: &str) -> AppResult<SettingView> {
+    // 先做 key 校验，未知 key 给出明确错误
+    value_kind(key)?;
+    list_settings()?
+        .into_iter()
+        .find(|s| s.key == key)
+        .ok_or_else(|| AppError::ConfigError(format!("{key} is not set")))
+}
+
+/// 写入 config.toml（保留文件里已有的注释和排版），写之前整体校验一遍
+pub fn set_setting(key: &str, raw: &str) -> AppResult<()> {
+    let path = db::config_path().map_err(|e| AppError::IoError(e.to_string()))?;
+    let text = read_optional(&path)?.unwrap_or_default();
+    let new_text = edit_text(&text, key, raw, &path)?;
+    write_atomic(&path, &new_text)
+}
+
+/// 在已有的配置文本上改一项，返回校验过的新文本
+fn edit_text(text: &str, key: &str, raw: &str, path: &Path) -> AppResult<String> {
+    let value = parse_typed(key, raw)?;
+    let mut doc = text
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| AppError::ConfigError(format!("{}: {e}", path.display())))?;
+
+    // 第一次写 ssh.options 时先把默认值带上，免得只写一条就把其余默认全丢了
+    if key.starts_with(OPTIONS_PREFIX) && doc.get("ssh").and_then(|s| s.get("options")).is_none() {
+        for (k, v) in &SshSection::default().options {
+            set_path(
+                &mut doc,
+                &["ssh", "options", k],
+                toml::Value::String(v.clone()),
+            )?;
+        }
+    }
+
+    let segments = key.split('.').collect::<Vec<_>>();
+    set_path(&mut doc, &segments, value)?;
+
+    let new_text = doc.to_string();
+    validate_text(&new_text)?;
+    Ok(new_text)
+}
+
+/// 校验一份完整的配置文本（`config edit` 保存后用）
+pub fn validate_text(text: &str) -> AppResult<()> {
+    let config: Config = toml::from_str(text).map_err(|e| AppError::ConfigError(e.to_string()))?;
+    config.validate()
+}
+
+/// 首次 `config edit` 时写入的模板：默认值全部写出来，方便照着改
+pub fn default_template() -> AppResult<String> {
+    let body = toml::to_string_pretty(&Config::default())
+        .map_err(|e| AppError::ConfigError(e.to_string()))?;
+    Ok(format!(
+        "# jmssh configuration\n\
+         # every key can also be overridden by a JMSSH_* environment variable, see `jmssh config list`\n\n\
+         {body}"
+    ))
+}
+
+fn load_layers() -> AppResult<(Config, toml::Table)> {
+    let path = db::config_path().map_err(|e| AppError::IoError(e.to_string()))?;
+    let file = match read_optional(&path)? {
+        Some(raw) => raw
+            .parse::<toml::Table>()
+            .map_err(|e| AppError::ConfigError(format!("{}: {e}", path.display())))?,
+        None => toml::Table::new(),
+    };
+    let config = merge_layers(&file, &path, |name| std::env::var(name).ok())?;
+    Ok((config, file))
+}
+
+/// 文件内容叠上环境变量（`env` 按变量名取值），得到校验过的生效配置
+fn merge_layers(
+    file: &toml::Table,
+    path: &Path,
+    env: impl Fn(&str) -> Option<String>,
+) -> AppResult<Config> {
+    let mut merged = file.clone();
+    for (key, name) in ENV_OVERRIDES {
+        if let Some(raw) = env(name) {
+            let value = parse_typed(key, &raw).map_err(|e| match e {
+                AppError::ConfigError(msg) => AppError::ConfigError(format!("{name}: {msg}")),
+                other => other,
+            })?;
+            let segments = key.split('.').collect::<Vec<_>>();
+            insert_path(&mut merged, &segments, value);
+        }
+    }
+
+    let config: Config = toml::Value::Table(merged).try_into().map_err(|e| {
+        // 文件本身没问题的话，错的就是某个 JMSSH_* 变量
+        let origin = match Config::deserialize(toml::Value::Table(file.clone())) {
+            Ok(_) => "JMSSH_* environment".to_string(),
+            Err(_) => path.display().to_string(),
+        };
+        AppError::ConfigError(format!("{origin}: {e}"))
+    })?;
+    config.validate()?;
+    Ok(config)
+}
+
+fn read_optional(path: &Path) -> AppResult<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(raw) => Ok(Some(raw)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn to_table(config: &Config) -> AppResult<toml::Table> {
+    toml::Table::try_from(config).map_err(|e| AppError::ConfigError(e.to_string()))
+}
+
+fn flatten(prefix: &str, table: &toml::Table, out: &mut Vec<(String, toml::Value)>) {
+    for (k, v) in table {
+        let key = if prefix.is_empty() {
+            k.clone()
+        } else {
+            format!("{prefix}.{k}")
+        };
+        match v {
+            toml::Value::Table(t) => flatten(&key, t, out),
+            other => out.push((key, other.clone())),
+        }
+    }
+}
+
+fn setting_source(key: &str, file: &toml::Table) -> SettingSource {
+    if let Some((_, env)) = ENV_OVERRIDES
+        .iter()
+        .find(|(k, env)| *k == key && std::env::var_os(env).is_some())
+    {
+        return SettingSource::Env(env);
+    }
+
+    // ssh.options 是整体替换的：文件里有这张表，里面的每一条都来自文件
+    let lookup = match key.strip_prefix(OPTIONS_PREFIX) {
+        Some(_) => "ssh.options",
+        None => key,
+    };
+    let in_file = lookup
+        .split('.')
+        .try_fold(file, |t, seg| match t.get(seg) {
+            Some(toml::Value::Table(inner)) => Some(inner),
+            Some(_) => Some(t),
+            None => None,
+        })
+        .is_some();
+
+    if in_file {
+        SettingSource::File
+    } else {
+        SettingSource::Default
+    }
+}
+
+fn display_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ValueKind {
+    String,
+    Integer,
+}
+
+/// 按默认配置里同名项的类型判断 key 是否合法、值该怎么解析
+fn value_kind(key: &str) -> AppResult<ValueKind> {
+    if let Some(name) = key.strip_prefix(OPTIONS_PREFIX) {
+        validate_option_name(name)?;
+        return Ok(ValueKind::String);
+    }
+
+    let mut known = Vec::new();
+    flatten("", &to_table(&Config::default())?, &mut known);
+
+    match known.iter().find(|(k, _)| k == key) {
+        Some((_, toml::Value::Integer(_))) => Ok(ValueKind::Integer),
+        Some(_) => Ok(ValueKind::String),
+        None => Err(AppError::ConfigError(format!(
+            "unknown config key {key:?}; see `jmssh config list`"
+        ))),
+    }
+}
+
+fn parse_typed(key: &str, raw: &str) -> AppResult<toml::Value> {
+    match value_kind(key)? {
+        ValueKind::String => Ok(toml::Value::String(raw.to_string())),
+        ValueKind::Integer => raw
+            .trim()
+            .parse::<i64>()
+            .map(toml::Value::Integer)
+            .map_err(|_| AppError::ConfigError(format!("{key} expects a number, got {raw:?}"))),
+    }
+}
+
+/// 每个 profile 自己决定的选项，放进全局默认会盖掉 profile 的设置
+const MANAGED_OPTIONS: &[&str] = &["HostName", "User", "Port", "ProxyJump", "IdentityFile"];
+
+fn validate_option_name(name: &str) -> AppResult<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AppError::ConfigError(format!(
+            "{OPTIONS_PREFIX}{name}: ssh option names are letters and digits only"
+        )));
+    }
+    if let Some(managed) = MANAGED_OPTIONS
+        .iter()
+        .find(|m| m.eq_ignore_ascii_case(name))
+    {
+        return Err(AppError::ConfigError(format!(
+            "{OPTIONS_PREFIX}{name}: {managed} is set per profile by jmssh and can't be a global default"
+        )));
+    }
+    Ok(())
+}
+
+fn insert_path(table: &mut toml::Table, segments: &[&str], value: toml::Value) {
+    match segments {
+        [] => {}
+        [last] => {
+            table.insert(last.to_string(), value);
+        }
+        [first, rest @ ..] => {
+            let entry = table
+                .entry(first.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if !entry.is_table() {
+                *entry = toml::Value::Table(toml::Table::new());
+            }
+            if let toml::Value::Table(inner) = entry {
+                insert_path(inner, rest, value);
+            }
+        }
+    }
+}
+
+fn set_path(
+    doc: &mut toml_edit::DocumentMut,
+    segments: &[&str],
+    value: toml::Value,
+) -> AppResult<()> {
+    let Some((last, parents)) = segments.split_last() else {
+        return Ok(());
+    };
+
+    let mut table = doc.as_table_mut();
+    for seg in parents {
+        let item = table.entry(seg).or_insert_with(|| {
+            // 只写 [ssh.options] 时不要多出一个空的 [ssh]
+            let mut t = toml_edit::Table::new();
+            t.set_implicit(true);
+            toml_edit::Item::Table(t)
+        });
+        table = item
+            .as_table_mut()
+            .ok_or_else(|| AppError::ConfigError(format!("{seg} in config.toml is not a table")))?;
+    }
+
+    table[*last] = match value {
+        toml::Value::Integer(i) => toml_edit::value(i),
+        toml::Value::Boolean(b) => toml_edit::value(b),
+        other => toml_edit::value(display_value(&other)),
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(file: &str, env: &[(&str, &str)]) -> AppResult<Config> {
+        let file = file.parse::<toml::Table>().unwrap();
+        merge_layers(&file, Path::new("config.toml"), |name| {
+            env.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        })
+    }
+
+    fn config_error(result: AppResult<impl std::fmt::Debug>) -> String {
+        match result {
+            Err(AppError::ConfigError(msg)) => msg,
+            other => panic!("expected a config error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn env_overrides_file_overrides_defaults() {
+        let file = "[defaults]\nuser = \"deploy\"\nport = 2200\n";
+
+        let config = merge(file, &[]).unwrap();
+        assert_eq!(config.defaults.user, "deploy");
+        assert_eq!(config.defaults.port, 2200);
+        assert_eq!(config.defaults.host, "127.0.0.1");
+
+        let config = merge(
+            file,
+            &[
+                ("JMSSH_DEFAULT_PORT", "2222"),
+                ("JMSSH_AUTOFILL", "sshpass"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.defaults.user, "deploy");
+        assert_eq!(config.defaults.port, 2222);
+        assert_eq!(config.ssh.autofill, AutofillMode::Sshpass);
+    }
+
+    #[test]
+    fn invalid_values_name_their_source() {
+        let msg = config_error(merge("", &[("JMSSH_DEFAULT_PORT", "twenty")]));
+        assert!(msg.starts_with("JMSSH_DEFAULT_PORT:"), "{msg}");
+
+        let msg = config_error(merge("", &[("JMSSH_AUTOFILL", "expect")]));
+        assert!(msg.starts_with("JMSSH_* environment:"), "{msg}");
+
+        let msg = config_error(merge("[ui]\ncolor = \"rainbow\"\n", &[]));
+        assert!(msg.starts_with("config.toml:"), "{msg}");
+
+        assert!(config_error(merge("[defaults]\nport = 0\n", &[])).contains("defaults.port"));
+        assert!(config_error(merge("[ssh]\nbinary = \" \"\n", &[])).contains("ssh.binary"));
+        assert!(
+            config_error(merge("[ssh.options]\n\"Bad-Name\" = \"x\"\n", &[])).contains("Bad-Name")
+        );
+    }
+
+    #[test]
+    fn managed_options_are_not_global_defaults() {
+        for name in ["HostName", "user", "Port", "ProxyJump", "IdentityFile"] {
+            let msg = config_error(merge(&format!("[ssh.options]\n{name} = \"x\"\n"), &[]));
+            assert!(msg.contains("set per profile"), "{msg}");
+        }
+        assert!(
+            edit_text(
+                "",
+                "ssh.options.ProxyJump",
+                "bastion",
+                Path::new("config.toml")
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn set_keeps_comments_and_round_trips() {
+        let path = Path::new("config.toml");
+        let text = "# my settings\n[defaults]\nuser = \"deploy\" # who I am\n";
+
+        let text = edit_text(text, "defaults.port", "2200", path).unwrap();
+        let text = edit_text(&text, "ssh.options.ServerAliveInterval", "15", path).unwrap();
+        let text = edit_text(&text, "ui.picker_sort", "name", path).unwrap();
+        assert!(text.starts_with("# my settings\n"));
+        assert!(text.contains("user = \"deploy\" # who I am"));
+        assert!(!text.contains("[ssh]\n"), "{text}");
+
+        let config: Config = toml::from_str(&text).unwrap();
+        assert_eq!(config.defaults.user, "deploy");
+        assert_eq!(config.defaults.port, 2200);
+        assert_eq!(config.ui.picker_sort, SortOrder::Name);
+        // 第一次写 ssh.options 带上了其余默认值
+        assert_eq!(config.ssh.options["ServerAliveInterval"], "15");
+        assert_eq!(config.ssh.options["Compression"], "yes");
+
+        assert!(edit_text(&text, "defaults.port", "70000", path).is_err());
+        assert!(edit_text(&text, "ui.colour", "never", path).is_err());
+        assert!(edit_text(&text, "ui.color", "rainbow", path).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! 自带的 schema 迁移：`schema_version` 表记录已应用的版本，启动时自动补齐。
+//!
+//! 每个迁移的 SQL 写死在这里，不从 entity 推导：entity 以后加字段时，
+//! 老库靠新的迁移升级，而不是靠 `create_table_from_entity` 碰运气。
+//! 已发布的迁移不要再改，有变更就往后追加新版本。
+
+use crate::error::{AppError, AppResult};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement, TransactionTrait};
+
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    statements: &'static [&'static str],
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        // 和早期 create_table_from_entity 生成的表结构一致；IF NOT EXISTS 让老库直接认领 v1
+        statements: &[
+            r#"CREATE TABLE IF NOT EXISTS "profiles" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "label" varchar, "hostname" varchar NOT NULL, "username" varchar NOT NULL, "port" smallint, "auth_mode" tinyint NOT NULL, "tags" varchar, "note" varchar )"#,
+            r#"CREATE TABLE IF NOT EXISTS "routes" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "profile_id" integer NOT NULL, "seq" integer NOT NULL, "via_profile_id" integer NOT NULL )"#,
+            r#"CREATE TABLE IF NOT EXISTS "local_auth" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "profile_id" integer NOT NULL, "key_path_local" varchar )"#,
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "profile_options",
+        statements: &[
+            r#"CREATE TABLE "profile_options" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "profile_id" integer NOT NULL, "key" varchar NOT NULL, "value" varchar NOT NULL )"#,
+            // ssh 的选项名不区分大小写，同一个 profile 里一个选项只留一条
+            r#"CREATE UNIQUE INDEX "idx_profile_options_profile_key" ON "profile_options" ("profile_id", "key" COLLATE NOCASE)"#,
+        ],
+    },
+    Migration {
+        version: 3,
+        name: "profile_tags",
+        statements: &[
+            r#"CREATE TABLE "tags" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "name" varchar NOT NULL )"#,
+            r#"CREATE UNIQUE INDEX "idx_tags_name" ON "tags" ("name")"#,
+            r#"CREATE TABLE "profile_tags" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "profile_id" integer NOT NULL, "tag_id" integer NOT NULL )"#,
+            r#"CREATE UNIQUE INDEX "idx_profile_tags_profile_tag" ON "profile_tags" ("profile_id", "tag_id")"#,
+            // 旧的 profiles.tags 是逗号分隔的字符串：拆开、小写、空格换成 '-'，搬进新表
+            r#"WITH RECURSIVE "split" ("profile_id", "tag", "rest") AS (
+                SELECT "id", '', "tags" || ',' FROM "profiles" WHERE "tags" IS NOT NULL
+                UNION ALL
+                SELECT "profile_id",
+                       replace(lower(trim(substr("rest", 1, instr("rest", ',') - 1))), ' ', '-'),
+                       substr("rest", instr("rest", ',') + 1)
+                FROM "split" WHERE "rest" <> ''
+            )
+            INSERT OR IGNORE INTO "tags" ("name")
+            SELECT DISTINCT "tag" FROM "split" WHERE "tag" <> ''"#,
+            r#"WITH RECURSIVE "split" ("profile_id", "tag", "rest") AS (
+                SELECT "id", '', "tags" || ',' FROM "profiles" WHERE "tags" IS NOT NULL
+                UNION ALL
+                SELECT "profile_id",
+                       replace(lower(trim(substr("rest", 1, instr("rest", ',') - 1))), ' ', '-'),
+                       substr("rest", instr("rest", ',') + 1)
+                FROM "split" WHERE "rest" <> ''
+            )
+            INSERT OR IGNORE INTO "profile_tags" ("profile_id", "tag_id")
+            SELECT "split"."profile_id", "tags"."id"
+            FROM "split" JOIN "tags" ON "tags"."name" = "split"."tag"
+            WHERE "split"."tag" <> ''"#,
+            // 旧列保留（SQLite 删列不方便），但清空，以后只认 profile_tags
+            r#"UPDATE "profiles" SET "tags" = NULL"#,
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "profile_last_connected",
+        statements: &[r#"ALTER TABLE "profiles" ADD COLUMN "last_connected_at" varchar"#],
+    },
+    Migration {
+        version: 5,
+        name: "connections",
+        statements: &[
+            // profile_id 为空：临时目标，或 profile 之后被删了（target 里还留着当时的名字）
+            r#"CREATE TABLE "connections" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "profile_id" integer, "target" varchar NOT NULL, "hops" varchar NOT NULL, "started_at" varchar NOT NULL DEFAULT (datetime('now')), "ended_at" varchar, "exit_code" integer )"#,
+            r#"CREATE INDEX "idx_connections_started_at" ON "connections" ("started_at")"#,
+            r#"CREATE INDEX "idx_connections_profile_id" ON "connections" ("profile_id")"#,
+        ],
+    },
+];
+
+const CREATE_VERSION_TABLE: &str = r#"CREATE TABLE IF NOT EXISTS "schema_version" ( "version" integer NOT NULL PRIMARY KEY, "name" varchar NOT NULL, "applied_at" varchar NOT NULL DEFAULT (datetime('now')) )"#;
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: u32,
+    pub name: &'static str,
+    /// None 表示尚未应用
+    pub applied_at: Option<String>,
+}
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// 已应用的版本（version, applied_at），按版本升序
+async fn applied_versions(db: &DatabaseConnection) -> AppResult<Vec<(u32, String)>> {
+    db.execute_unprepared(CREATE_VERSION_TABLE).await?;
+
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            r#"SELECT "version", "applied_at" FROM "schema_version" ORDER BY "version""#,
+        ))
+        .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            let version: u32 = row.try_get("", "version")?;
+            let applied_at: String = row.try_get("", "applied_at")?;
+            Ok((version, applied_at))
+        })
+        .collect()
+}
+
+pub async fn status(db: &DatabaseConnection) -> AppResult<Vec<MigrationStatus>> {
+    let applied = applied_versions(db).await?;
+    check_not_newer(&applied)?;
+
+    Ok(MIGRATIONS
+        .iter()
+        .map(|m| MigrationStatus {
+            version: m.version,
+            name: m.name,
+            applied_at: applied
+                .iter()
+                .find(|(v, _)| *v == m.version)
+                .map(|(_, at)| at.clone()),
+        })
+        .collect())
+}
+
+/// 依次应用所有未应用的迁移，每个迁移一个事务；返回本次应用的迁移
+pub async fn migrate(db: &DatabaseConnection) -> AppResult<Vec<&'static Migration>> {
+    let applied = applied_versions(db).await?;
+    check_not_newer(&applied)?;
+
+    let mut done = Vec::new();
+    for m in MIGRATIONS
+        .iter()
+        .filter(|m| !applied.iter().any(|(v, _)| *v == m.version))
+    {
+        let txn = db.begin().await?;
+        for sql in m.statements {
+            txn.execute_unprepared(sql).await?;
+        }
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            r#"INSERT INTO "schema_version" ("version", "name") VALUES (?, ?)"#,
+            [m.version.into(), m.name.into()],
+        ))
+        .await?;
+        txn.commit().await?;
+        done.push(m);
+    }
+
+    Ok(done)
+}
+
+/// 库是更新版本的 jmssh 建的：不认识的表结构，拒绝继续以免写坏
+fn check_not_newer(applied: &[(u32, String)]) -> AppResult<()> {
+    match applied.last() {
+        Some((v, _)) if *v > latest_version() => Err(AppError::SchemaError(format!(
+            "database is at schema v{v}, but this jmssh only knows up to v{}; please upgrade jmssh",
+            latest_version()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::Database;
+
+    async fn memory_db() -> DatabaseConnection {
+        Database::connect("sqlite::memory:").await.unwrap()
+    }
+
+    async fn query_strings(db: &DatabaseConnection, sql: &str) -> Vec<String> {
+        db.query_all(Statement::from_string(DbBackend::Sqlite, sql))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.try_get_by_index::<String>(0).unwrap())
+            .collect()
+    }
+
+    /// 有 schema_version 之前的老库：只有 v1 的三张表，tags 还是逗号分隔的字符串
+    async fn baseline_db() -> DatabaseConnection {
+        let db = memory_db().await;
+        for sql in MIGRATIONS[0].statements {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+        db.execute_unprepared(
+            r#"INSERT INTO "profiles" ("id", "label", "hostname", "username", "port", "auth_mode", "tags") VALUES
+                (1, 'web', 'w', 'root', 22, 0, 'Prod, Web Tier,,prod'),
+                (2, 'db', 'd', 'root', 22, 0, ''),
+                (3, 'cache', 'c', 'root', 22, 0, ' , '),
+                (4, 'plain', 'p', 'root', 22, 0, NULL),
+                (5, 'edge', 'e', 'root', 22, 0, 'edge')"#,
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn baseline_database_upgrades_in_place() {
+        let db = baseline_db().await;
+
+        let done = migrate(&db).await.unwrap();
+        assert_eq!(done.len(), MIGRATIONS.len());
+        assert!(
+            status(&db)
+                .await
+                .unwrap()
+                .iter()
+                .all(|m| m.applied_at.is_some())
+        );
+
+        let labels = query_strings(&db, r#"SELECT "label" FROM "profiles" ORDER BY "id""#).await;
+        assert_eq!(labels, ["web", "db", "cache", "plain", "edge"]);
+
+        // 再跑一次什么都不做
+        assert!(migrate(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn comma_separated_tags_move_to_profile_tags() {
+        let db = baseline_db().await;
+        migrate(&db).await.unwrap();
+
+        let rows = query_strings(
+            &db,
+            r#"SELECT "profiles"."label" || ':' || "tags"."name"
+               FROM "profile_tags"
+               JOIN "profiles" ON "profiles"."id" = "profile_tags"."profile_id"
+               JOIN "tags" ON "tags"."id" = "profile_tags"."tag_id"
+               ORDER BY 1"#,
+        )
+        .await;
+        assert_eq!(rows, ["edge:edge", "web:prod", "web:web-tier"]);
+
+        let tags = query_strings(&db, r#"SELECT "name" FROM "tags" ORDER BY 1"#).await;
+        assert_eq!(tags, ["edge", "prod", "web-tier"]);
+
+        let leftover = query_strings(
+            &db,
+            r#"SELECT "label" FROM "profiles" WHERE "tags" IS NOT NULL"#,
+        )
+        .await;
+        assert!(leftover.is_empty());
+    }
+
+    #[tokio::test]
+    async fn newer_schema_is_refused_untouched() {
+        let db = memory_db().await;
+        db.execute_unprepared(CREATE_VERSION_TABLE).await.unwrap();
+        db.execute_unprepared(
+            r#"INSERT INTO "schema_version" ("version", "name") VALUES (1, 'initial_schema'), (99, 'from_the_future')"#,
+        )
+        .await
+        .unwrap();
+        let tables = r#"SELECT "name" FROM "sqlite_master" ORDER BY 1"#;
+        let before = query_strings(&db, tables).await;
+
+        assert!(matches!(migrate(&db).await, Err(AppError::SchemaError(_))));
+        assert!(matches!(status(&db).await, Err(AppError::SchemaError(_))));
+        assert_eq!(query_strings(&db, tables).await, before);
+    }
+}
@@ -0,0 +1,78 @@
+pub mod migrations;
+
+use anyhow::{Result, anyhow};
+use directories::ProjectDirs;
+use sea_orm::{Database, DatabaseConnection};
+use std::fs;
+use std::path::PathBuf;
+
+fn project_dirs() -> Result<ProjectDirs> {
+    ProjectDirs::from("com", "jiyamira", "jmssh")
+        .ok_or_else(|| anyhow!("Could not find jmssh directory"))
+}
+
+pub fn data_dir() -> Result<PathBuf> {
+    let proj = project_dirs()?;
+    let data_dir = proj.data_dir();
+    fs::create_dir_all(data_dir)?;
+    Ok(data_dir.to_path_buf())
+}
+
+pub fn config_dir() -> Result<PathBuf> {
+    let proj = project_dirs()?;
+    let config_dir = proj.config_dir();
+    fs::create_dir_all(config_dir)?;
+    Ok(config_dir.to_path_buf())
+}
+
+pub fn config_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("config.toml"))
+}
+
+/// `jmssh export ssh-config --write` 维护的文件，用户在 `~/.ssh/config` 里 Include 它
+pub fn managed_ssh_config_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("ssh_config"))
+}
+
+/// 会话级运行时目录（Linux 上是 `$XDG_RUNTIME_DIR`，登出即清空）；
+/// macOS / Windows / 没有 `$XDG_RUNTIME_DIR` 的 Linux 上没有，返回 None。
+pub fn runtime_dir() -> Result<Option<PathBuf>> {
+    let proj = project_dirs()?;
+    let Some(dir) = proj.runtime_dir() else {
+        return Ok(None);
+    };
+    fs::create_dir_all(dir)?;
+    Ok(Some(dir.to_path_buf()))
+}
+
+/// 放不含秘密的临时文件（跳板用的 ssh_config）：优先会话目录，没有就用 cache 目录
+pub fn scratch_dir() -> Result<PathBuf> {
+    if let Some(dir) = runtime_dir()? {
+        return Ok(dir);
+    }
+    let proj = project_dirs()?;
+    let dir = proj.cache_dir();
+    fs::create_dir_all(dir)?;
+    Ok(dir.to_path_buf())
+}
+
+pub fn db_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("jmssh.sqlite"))
+}
+
+pub fn vault_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join("jmssh.vault"))
+}
+
+/// 没有会话目录就不缓存解锁后的 key：放到 cache 目录的话登出也不会清掉
+pub fn vault_key_cache_path() -> Result<Option<PathBuf>> {
+    Ok(runtime_dir()?.map(|dir| dir.join("vault.key")))
+}
+
+pub async fn connect_db() -> Result<DatabaseConnection> {
+    let path = db_path()?;
+    let path_str = path.to_string_lossy();
+    let url = format!("sqlite:{}?mode=rwc", path_str);
+    let db = Database::connect(&url).await?;
+    Ok(db)
+}
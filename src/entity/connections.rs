@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, EnumIter};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, DeriveEntityModel)]
+#[sea_orm(table_name = "connections")]
+pub struct Model {
+    #[sea_orm(primary_key, column_name = "id")]
+    pub id: u32,
+    #[sea_orm(column_name = "profile_id")]
+    pub profile_id: Option<u32>,
+    #[sea_orm(column_name = "target")]
+    pub target: String,
+    /// 解析后的整条链，`user@host:port -> user@host:port`
+    #[sea_orm(column_name = "hops")]
+    pub hops: String,
+    #[sea_orm(column_name = "started_at")]
+    pub started_at: String,
+    #[sea_orm(column_name = "ended_at")]
+    pub ended_at: Option<String>,
+    #[sea_orm(column_name = "exit_code")]
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
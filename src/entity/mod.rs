@@ -1,3 +1,7 @@
+pub mod connections;
 pub mod local_auth;
+pub mod profile_options;
+pub mod profile_tags;
 pub mod profiles;
 pub mod routes;
+pub mod tags;
@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, EnumIter};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, DeriveEntityModel)]
+#[sea_orm(table_name = "profile_options")]
+pub struct Model {
+    #[sea_orm(primary_key, column_name = "id")]
+    pub id: u32,
+    #[sea_orm(column_name = "profile_id")]
+    pub profile_id: u32,
+    #[sea_orm(column_name = "key")]
+    pub key: String,
+    #[sea_orm(column_name = "value")]
+    pub value: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
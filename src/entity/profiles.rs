@@ -51,10 +51,11 @@ pub struct Model {
     pub port: Option<u16>,
     #[sea_orm(column_name = "auth_mode")]
     pub auth_mode: AuthMode,
-    #[sea_orm(column_name = "tags")]
-    pub tags: Option<String>,
+    // 旧的 "tags" 列还在表里，v3 起标签都在 profile_tags，这里不再映射
     #[sea_orm(column_name = "note")]
     pub note: Option<String>,
+    #[sea_orm(column_name = "last_connected_at")]
+    pub last_connected_at: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -9,6 +9,12 @@ pub enum AppError {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("database schema error: {0}")]
+    SchemaError(String),
+
+    #[error("config error: {0}")]
+    ConfigError(String),
+
     // --- 业务错误（profile 相关） ---
     #[error("profile not found: {0}")]
     ProfileNotFound(String),
@@ -21,17 +27,105 @@ pub enum AppError {
 
     #[error("route target profile not found for label: {0}")]
     ProfileRouteTargetNotFound(String),
+
+    #[error("profile {0} is used as a jump host by: {1} (use --force or --cascade)")]
+    ProfileInUse(String, String),
+
+    #[error("invalid jump chain: {0}")]
+    InvalidJumpChain(String),
+
+    #[error("invalid destination: {0}")]
+    InvalidDestination(String),
+
+    #[error("invalid ssh option: {0}")]
+    InvalidSshOption(String),
+
+    #[error("invalid tag: {0}")]
+    InvalidTag(String),
+
+    #[error("invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("invalid import file: {0}")]
+    InvalidImport(String),
+
     #[error("invalid auth mode: {0}")]
     InvalidAuthMode(String),
 
+    #[error("invalid private key: {0}")]
+    InvalidKeyPath(String),
+
     #[error("password store error: {0}")]
     PasswordStoreError(String),
 
+    #[error("vault is locked; run `jmssh vault unlock` first")]
+    VaultLocked,
+
+    #[error("vault error: {0}")]
+    VaultError(String),
+
     #[error("io error: {0}")]
     IoError(String),
 
+    #[error("ssh binary not found")]
+    SshNotFound,
+
     #[error("internal server error: {0}")]
     _InternalError(String),
 }
 
 pub type AppResult<T> = anyhow::Result<T, AppError>;
+
+/// jmssh 自身失败时的退出码，取值参考 sysexits.h；
+/// `connect` 成功跑起 ssh 时则原样透传 ssh 的退出码（信号为 128+N）。
+pub mod exit_code {
+    /// 其他未归类的失败
+    pub const FAILURE: u8 = 1;
+    /// 命令行用法错误（缺参数、非 TTY 下需要交互等）
+    pub const USAGE: u8 = 64;
+    /// 输入数据不合法（auth mode、key 路径、jump 链、schema 等）
+    pub const DATA: u8 = 65;
+    /// 找不到 profile
+    pub const NOT_FOUND: u8 = 66;
+    /// 本地数据库等内部错误
+    pub const SOFTWARE: u8 = 70;
+    /// profile 已存在
+    pub const EXISTS: u8 = 73;
+    /// 本地 IO 错误
+    pub const IO: u8 = 74;
+    /// 密码库（keyring / vault）不可用或被锁
+    pub const NO_PERM: u8 = 77;
+    /// config.toml 写错了
+    pub const CONFIG: u8 = 78;
+    /// 系统里没有 ssh
+    pub const SSH_NOT_FOUND: u8 = 127;
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            AppError::Db(_) | AppError::_InternalError(_) => exit_code::SOFTWARE,
+            AppError::Io(_) | AppError::IoError(_) => exit_code::IO,
+            AppError::SchemaError(_)
+            | AppError::_ProfileNothingToUpdate(_)
+            | AppError::ProfileInUse(..)
+            | AppError::InvalidJumpChain(_)
+            | AppError::InvalidDestination(_)
+            | AppError::InvalidSshOption(_)
+            | AppError::InvalidTag(_)
+            | AppError::InvalidQuery(_)
+            | AppError::InvalidImport(_)
+            | AppError::InvalidAuthMode(_)
+            | AppError::InvalidKeyPath(_) => exit_code::DATA,
+            AppError::ProfileNotFound(_) | AppError::ProfileRouteTargetNotFound(_) => {
+                exit_code::NOT_FOUND
+            }
+            AppError::ProfileAlreadyExists(_) => exit_code::EXISTS,
+            AppError::PasswordStoreError(_) | AppError::VaultLocked | AppError::VaultError(_) => {
+                exit_code::NO_PERM
+            }
+            AppError::ConfigError(_) => exit_code::CONFIG,
+            AppError::SshNotFound => exit_code::SSH_NOT_FOUND,
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::cli::{ConfigArgs, ConfigCommand};
+use crate::config::{self, SettingSource};
+use crate::db;
+use crate::error::{AppError, AppResult};
+use crate::infra::files::write_atomic;
+use crate::term::{c_accent, c_error, log_error, log_info};
+use std::fs;
+use std::io::{self, ErrorKind, IsTerminal, Write};
+use std::process::Command;
+use tabwriter::TabWriter;
+
+pub fn handle_config(args: ConfigArgs) -> AppResult<()> {
+    match args.cmd {
+        ConfigCommand::Get { key } => {
+            // 只输出值本身，方便 $(jmssh config get ssh.binary)
+            println!("{}", config::get_setting(&key)?.value);
+            Ok(())
+        }
+        ConfigCommand::Set { key, value } => {
+            config::set_setting(&key, &value)?;
+            log_info(format!("{key} = {value}"));
+            Ok(())
+        }
+        ConfigCommand::List => handle_config_list(),
+        ConfigCommand::Edit => handle_config_edit(),
+    }
+}
+
+fn handle_config_list() -> AppResult<()> {
+    let path = db::config_path().map_err(|e| AppError::IoError(e.to_string()))?;
+    log_info(format!("config {}", c_accent(&path.display().to_string())));
+
+    let mut tw = TabWriter::new(io::stdout()).padding(2);
+    writeln!(tw, "KEY\tVALUE\tSOURCE")?;
+    for s in config::list_settings()? {
+        let source = match s.source {
+            SettingSource::Default => "default".to_string(),
+            SettingSource::File => "file".to_string(),
+            SettingSource::Env(name) => format!("env {name}"),
+        };
+        writeln!(tw, "{}\t{}\t{}", s.key, s.value, source)?;
+    }
+    tw.flush()?;
+    Ok(())
+}
+
+/// 在临时副本上编辑，校验通过才替换 config.toml；原文件坏了也能进来修
+fn handle_config_edit() -> AppResult<()> {
+    let path = db::config_path().map_err(|e| AppError::IoError(e.to_string()))?;
+    let original = match fs::read_to_string(&path) {
+        Ok(raw) => Some(raw),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let draft = path.with_extension("edit.toml");
+    let seed = match &original {
+        Some(raw) => raw.clone(),
+        None => config::default_template()?,
+    };
+    fs::write(&draft, &seed)?;
+
+    loop {
+        run_editor(&draft)?;
+        let edited = fs::read_to_string(&draft)?;
+
+        if original.as_deref() == Some(edited.as_str()) {
+            fs::remove_file(&draft)?;
+            log_info("config unchanged");
+            return Ok(());
+        }
+
+        match config::validate_text(&edited) {
+            Ok(()) => {
+                write_atomic(&path, &edited)?;
+                fs::remove_file(&draft)?;
+                log_info(format!(
+                    "config saved to {}",
+                    c_accent(&path.display().to_string())
+                ));
+                return Ok(());
+            }
+            Err(e) => {
+                if !ask_edit_again(&e)? {
+                    log_info(format!(
+                        "config.toml left untouched; your draft is kept at {}",
+                        c_accent(&draft.display().to_string())
+                    ));
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+fn run_editor(file: &std::path::Path) -> AppResult<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|k| std::env::var(k).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+
+    // EDITOR 里可能带参数，比如 "code -w"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(file)
+        .status()
+        .map_err(|e| AppError::IoError(format!("failed to start editor {program}: {e}")))?;
+
+    if !status.success() {
+        return Err(AppError::IoError(format!(
+            "editor {program} exited with {status}"
+        )));
+    }
+    Ok(())
+}
+
+fn ask_edit_again(err: &AppError) -> AppResult<bool> {
+    if !io::stdin().is_terminal() {
+        return Ok(false);
+    }
+
+    log_error(format!("{} {err}", c_error("invalid config:")));
+    eprint!("edit again? [Y/n] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(!answer.trim().eq_ignore_ascii_case("n"))
+}
@@ -1,62 +1,317 @@
 use crate::app::AppContext;
 use crate::cli::ConnectArgs;
+use crate::config::{AutofillMode, SshSection};
+use crate::db;
 use crate::entity::profiles::AuthMode;
-use crate::error::AppResult;
-use crate::term::{c_accent, c_error, log_error, log_info};
+use crate::error::{AppError, AppResult, exit_code};
+use crate::infra::shell;
+use crate::infra::ssh_config::{self, TempSshConfig};
+use crate::term::{c_accent, c_error, log_error, log_info, log_warn};
 use crate::ui::tui::connect::pick_profile_for_connect;
-use crate::usecase::{ConnectInput, connect};
+use crate::usecase::{ConnectInput, ConnectPlan, HopSecret, connect, history};
 use itertools::Itertools;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::io::ErrorKind;
+use std::path::Path;
 use std::process::{Command, ExitStatus};
 
-pub async fn handle_connect(ctx: &AppContext, args: ConnectArgs) -> AppResult<()> {
+/// 返回值是 jmssh 应该使用的退出码：ssh 跑起来之后就是 ssh 自己的退出码
+pub async fn handle_connect(ctx: &AppContext, args: ConnectArgs) -> AppResult<u8> {
+    // 额外的 ssh 选项 / 远端命令只在 handler 里拼 argv，不进 usecase
+    let ssh_opts = split_ssh_opts(&args.ssh_opts)?;
+    let remote_command = args.remote_command;
+    let interactive = remote_command.is_empty();
+    let output = match (args.dry_run, args.print) {
+        (true, _) => ConnectOutput::DryRun,
+        (_, true) => ConnectOutput::Print,
+        // 单独的 --json 视同 --dry-run
+        _ if args.json => ConnectOutput::DryRun,
+        _ => ConnectOutput::Run,
+    };
+
     // 1. CLI -> usecase 输入
     let input = if let Some(target) = args.target {
         ConnectInput {
             target,
             id: args.id,
+            jumps: args.jumps,
+            mode: args.mode,
+            save_as: args.save_as,
         }
     } else {
         match pick_profile_for_connect(ctx).await? {
             Some(p) => ConnectInput {
                 target: p.label,
                 id: Some(p.id),
+                jumps: Vec::new(),
+                mode: None,
+                save_as: None,
             },
             None => {
                 log_error(c_accent("no profiles to connect"));
-                return Ok(());
+                return Ok(0);
             }
         }
     };
 
     // 2. 计算连接计划（含跳板链）
-    let plan = connect::build_connect_plan(ctx, input).await?;
+    let plan = connect::build_connect_plan(ctx, input.clone()).await?;
 
     if plan.hops.is_empty() {
         log_error(c_accent("empty connect plan (no hops)"));
-        return Ok(());
+        return Err(AppError::_InternalError("empty connect plan".to_string()));
+    }
+
+    // 3. 跳板需要各自的 key 时要生成 ssh_config：真正连接时写临时文件（随 Drop 删除），
+    // --print 按内容写一个可复用的文件（打印出的命令之后还要用），--dry-run 只算路径不落盘
+    let hop_config_text =
+        ssh_config::needs_hop_config(&plan.hops).then(|| ssh_config::render_hop_config(&plan.hops));
+    let scratch_dir = || db::scratch_dir().map_err(|e| AppError::IoError(e.to_string()));
+
+    let mut hop_config = None;
+    let hop_config_path = match (&hop_config_text, output) {
+        (None, _) => None,
+        (Some(_), ConnectOutput::DryRun) => Some(ssh_config::temp_config_path(&scratch_dir()?)),
+        (Some(text), ConnectOutput::Print) => {
+            Some(ssh_config::write_printed_config(&scratch_dir()?, text)?)
+        }
+        (Some(text), ConnectOutput::Run) => {
+            let cfg = TempSshConfig::write(&scratch_dir()?, text)?;
+            let path = cfg.path().to_path_buf();
+            hop_config = Some(cfg);
+            Some(path)
+        }
+    };
+
+    let ssh_args = build_ssh_args(
+        &plan,
+        &ctx.config.ssh.options,
+        hop_config_path.as_deref(),
+        ssh_opts,
+        remote_command,
+    );
+    let ssh_bin = ctx.config.ssh.binary.as_str();
+    let command_line =
+        shell::join(std::iter::once(ssh_bin).chain(ssh_args.iter().map(String::as_str)));
+
+    // 3.1 只输出不执行
+    if args.json {
+        let doc = plan_json(ctx, &plan, &ssh_args, hop_config_text.as_deref());
+        let raw = serde_json::to_string_pretty(&doc)
+            .map_err(|e| AppError::IoError(format!("failed to serialize plan: {e}")))?;
+        println!("{raw}");
+        return Ok(0);
+    }
+
+    if output == ConnectOutput::Print {
+        println!("{command_line}");
+        return Ok(0);
+    }
+
+    let target = plan.hops.last().unwrap();
+
+    // 4. 登入前 log（彩色）
+    let prefix_target = c_accent(&format!("{}@{}:{}", target.user, target.host, target.port));
+    let via_desc = if plan.hops.len() > 1 {
+        {
+            let chain = plan.hops[..plan.hops.len() - 1]
+                .iter()
+                .map(|h| format!("{}@{}", h.user, h.host))
+                .collect_vec()
+                .join(" -> ");
+            format!(" via {}", c_accent(&chain))
+        }
+    } else {
+        Default::default()
+    };
+
+    log_info(format!("connecting to {prefix_target}{via_desc} ..."));
+    if hop_config_text.is_some() {
+        log_info(format!(
+            "using {} for per-hop identity files",
+            c_accent("generated ssh_config"),
+        ));
+    }
+    log_info(format!("exec {} {}", c_accent(ssh_bin), ssh_args.join(" ")));
+
+    if output == ConnectOutput::DryRun {
+        if let (Some(text), Some(path)) = (&hop_config_text, &hop_config_path) {
+            log_info(format!(
+                "would write {} to {}:",
+                c_accent("ssh_config"),
+                path.display()
+            ));
+            eprint!("{text}");
+        }
+        log_info(c_accent("dry run, ssh not started"));
+        println!("{command_line}");
+        return Ok(0);
+    }
+
+    // 每一跳各取各的密码：password 模式是登录密码，取不到要报错；
+    // key 模式如果存了就当作私钥口令，vault 锁着、keyring 不可用都不影响用 key 连接
+    let hop_secrets = plan
+        .hops
+        .iter()
+        // 临时 hop 不在库里，也就没有存过的密码，交给 ssh 自己提示
+        .filter_map(|h| h.id.map(|id| (h, id)))
+        .filter_map(|(h, id)| match h.auth_mode {
+            AuthMode::Password => Some(
+                ctx.password_store
+                    .get_profile_password(id)
+                    .map(|secret| (h, secret)),
+            ),
+            AuthMode::Key => Some(Ok((h, ctx.password_store.peek_profile_password(id)))),
+            _ => None,
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+
+    for (hop, secret) in &hop_secrets {
+        match (&hop.auth_mode, secret) {
+            (AuthMode::Password, Some(_)) => log_info(format!(
+                "using {} for profile {}",
+                c_accent("stored password"),
+                c_accent(&hop.label),
+            )),
+            (AuthMode::Password, None) => log_error(format!(
+                "{} {}={} but {}",
+                c_accent(&hop.label),
+                c_accent("auth_mode"),
+                c_accent("password"),
+                c_accent(&format!(
+                    "no password stored in {}",
+                    ctx.password_store.backend_name()
+                )),
+            )),
+            (_, Some(_)) => log_info(format!(
+                "using {} for profile {}",
+                c_accent("stored key passphrase"),
+                c_accent(&hop.label),
+            )),
+            _ => {}
+        }
     }
 
-    let password_profile_id = match plan.hops.as_slice() {
-        [] => None,
-        [single] => (single.auth_mode == AuthMode::Password).then_some(single.id),
-        [first, ..] => (first.auth_mode == AuthMode::Password).then_some(first.id),
+    let secrets = hop_secrets
+        .into_iter()
+        .filter_map(|(h, secret)| {
+            // key 模式存的是私钥口令：没有 key 路径就无从匹配，绝不能当登录密码用
+            let key_path = match h.auth_mode {
+                AuthMode::Key => Some(h.key_path_local.clone()?),
+                _ => None,
+            };
+            secret.map(|secret| HopSecret {
+                user: h.user.clone(),
+                host: h.host.clone(),
+                key_path,
+                secret,
+            })
+        })
+        .collect_vec();
+
+    // 5. 调用系统 ssh，继承当前终端 I/O；前后各记一笔连接历史
+    let connection_id = history::start_connection(ctx, &input.target, &plan).await?;
+    let status = match run_ssh_with_password(&ctx.config.ssh, &ssh_args, secrets, interactive) {
+        Ok(status) => status,
+        Err(e) => {
+            history::finish_connection(ctx, connection_id, None).await?;
+            return Err(e);
+        }
     };
+    drop(hop_config);
 
-    let password_opt = password_profile_id
-        .map(|id| ctx.password_store.get_profile_password(id))
-        .transpose()?
-        .flatten();
+    // 6. 退出 log（彩色）
+    let code = exit_code_of(&status);
+    history::finish_connection(ctx, connection_id, Some(code)).await?;
+    if status.success() {
+        log_info(c_accent("ssh session finished OK"));
+    } else if let Some(code) = status.code() {
+        log_error(format!(
+            "{} {}",
+            c_accent("ssh exited with"),
+            c_accent(&format!("code {}", code)),
+        ));
+    } else {
+        // 信号之类的情况
+        log_error(format!(
+            "{} {}",
+            c_accent("ssh terminated by signal"),
+            c_accent(&format!("(exit code {code})")),
+        ));
+    }
 
-    // 3. 拼 ssh 参数（尽量不用 ssh_args 的 mut，按片段组合）
-    // 3.1 jump 链：前 N-1 个 hop 作为 ProxyJump
-    let proxy_args = if plan.hops.len() > 1 {
+    // 7. 临时目标连上了就按需存成 profile：ssh 自己出错（连不上、认证失败）固定返回 255，
+    // 其余退出码来自远端会话 / 命令，和连接本身无关
+    let established = status.code().is_some_and(|c| c != SSH_ERROR_EXIT);
+    if let Some(label) = input.save_as.clone().filter(|_| established) {
+        let view = connect::save_ad_hoc_profile(ctx, label, &input).await?;
+        log_info(format!(
+            "profile {} saved ({}@{}:{})",
+            c_accent(&view.label),
+            view.user,
+            view.host,
+            view.port
+        ));
+    } else if let Some(label) = &input.save_as {
+        log_warn(format!(
+            "ssh could not connect; profile {} was not saved",
+            c_accent(label)
+        ));
+    }
+
+    Ok(code)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectOutput {
+    /// 真正启动 ssh
+    Run,
+    /// 只打印计划和 argv
+    DryRun,
+    /// 只打印可以 eval 的命令
+    Print,
+}
+
+/// `--ssh-opt` 的每个值按 shell 规则切词（`'-L 8080:localhost:80'` 是两个参数）；
+/// 必须以选项开头，免得一个裸词被 ssh 当成主机名或远端命令
+fn split_ssh_opts(raw: &[String]) -> AppResult<Vec<String>> {
+    raw.iter()
+        .map(|opt| {
+            let words = shell::split(opt);
+            match words.first() {
+                Some(first) if first.starts_with('-') && first != "--" => Ok(words),
+                _ => Err(AppError::InvalidSshOption(format!(
+                    "{opt:?}: expected an ssh option such as -v or '-L 8080:localhost:80'"
+                ))),
+            }
+        })
+        .flatten_ok()
+        .collect()
+}
+
+/// 根据连接计划拼 ssh 参数（不含 `ssh` 本身）；`hop_config` 是生成的 ssh_config 路径
+fn build_ssh_args(
+    plan: &ConnectPlan,
+    default_options: &BTreeMap<String, String>,
+    hop_config: Option<&Path>,
+    ssh_opts: Vec<String>,
+    remote_command: Vec<String>,
+) -> Vec<String> {
+    // jump 链：前 N-1 个 hop 作为 ProxyJump
+    let proxy_args = if let Some(cfg) = hop_config {
+        vec!["-F".to_string(), cfg.to_string_lossy().into_owned()]
+    } else if plan.hops.len() > 1 {
         {
             vec![
                 "-J".to_string(),
                 plan.hops[..plan.hops.len() - 1]
                     .iter()
-                    .map(|h| format!("{}@{}:{}", h.user, h.host, h.port))
+                    .map(|h| match h.host.contains(':') {
+                        // IPv6 字面量在 -J 里要加方括号，否则端口分不出来
+                        true => format!("{}@[{}]:{}", h.user, h.host, h.port),
+                        false => format!("{}@{}:{}", h.user, h.host, h.port),
+                    })
                     .collect_vec()
                     .join(","),
             ]
@@ -65,11 +320,11 @@ pub async fn handle_connect(ctx: &AppContext, args: ConnectArgs) -> AppResult<()
         Default::default()
     };
 
-    // 3.2 最终目标
+    // 最终目标
     let target = plan.hops.last().unwrap();
 
-    // 端口参数
-    let port_args = if target.port != 22 {
+    // 端口参数（生成的配置里已经写了）
+    let port_args = if target.port != 22 && hop_config.is_none() {
         vec!["-p".to_string(), target.port.to_string()]
     } else {
         Vec::new()
@@ -87,128 +342,177 @@ pub async fn handle_connect(ctx: &AppContext, args: ConnectArgs) -> AppResult<()
             target.key_path_local.clone().unwrap(),
         ]
     })
+    .filter(|_| hop_config.is_none())
     .unwrap_or_default();
 
-    // user@host
-    let dest_arg = [target.user.clone(), target.host.clone()].join("@");
-
-    let compress_args = vec![String::from("-C")];
+    // user@host；走生成配置时用最后一跳的别名
+    let dest_arg = match &hop_config {
+        Some(_) => ssh_config::hop_alias(plan.hops.len() - 1),
+        None => [target.user.clone(), target.host.clone()].join("@"),
+    };
 
-    let keepalive_args = vec![
-        "-o".to_string(),
-        "ServerAliveInterval=30".to_string(),
-        "-o".to_string(),
-        "ServerAliveCountMax=3".to_string(),
-    ];
+    // config.toml 里的全局默认 + 目标 profile 自己的选项（同名时 profile 优先，选项名不区分大小写）
+    let option_args = target
+        .options
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .chain(
+            default_options
+                .iter()
+                .filter(|(k, _)| {
+                    !target
+                        .options
+                        .iter()
+                        .any(|(t, _)| t.eq_ignore_ascii_case(k))
+                })
+                .map(|(k, v)| (k.as_str(), v.as_str())),
+        )
+        .flat_map(|(k, v)| ["-o".to_string(), format!("{k}={v}")])
+        .collect_vec();
 
     // 汇总成最终的 ssh_args（这里才需要一次 collect）
-    let ssh_args = proxy_args
+    // 用户的选项放最前面：ssh 对同一个选项取第一次出现的值，这样 `-o ServerAliveInterval=60` 之类能覆盖默认
+    ssh_opts
         .into_iter()
-        .chain(compress_args)
-        .chain(keepalive_args)
+        .chain(proxy_args)
+        .chain(option_args)
         .chain(port_args)
         .chain(key_args)
         .chain(std::iter::once(dest_arg))
-        .collect_vec();
+        .chain(remote_command)
+        .collect_vec()
+}
 
-    // 4. 登入前 log（彩色）
-    let prefix_target = c_accent(&format!("{}@{}:{}", target.user, target.host, target.port));
-    let via_desc = if plan.hops.len() > 1 {
-        {
-            let chain = plan.hops[..plan.hops.len() - 1]
-                .iter()
-                .map(|h| format!("{}@{}", h.user, h.host))
-                .collect_vec()
-                .join(" -> ");
-            format!(" via {}", c_accent(&chain))
-        }
-    } else {
-        Default::default()
-    };
+#[derive(Serialize)]
+struct PlanJson<'a> {
+    hops: Vec<HopJson<'a>>,
+    argv: Vec<&'a str>,
+    ssh_config: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct HopJson<'a> {
+    id: Option<u32>,
+    label: &'a str,
+    user: &'a str,
+    host: &'a str,
+    port: u16,
+    auth_mode: &'static str,
+    key_path: Option<&'a str>,
+    options: &'a [(String, String)],
+    /// 只说明存没存密码，从不输出密码本身；只查不解锁，vault 锁着时也是 false
+    has_password: bool,
+}
 
-    if let Some(pid) = password_profile_id {
-        let label_for_log = plan
+fn plan_json<'a>(
+    ctx: &'a AppContext,
+    plan: &'a ConnectPlan,
+    ssh_args: &'a [String],
+    ssh_config: Option<&'a str>,
+) -> PlanJson<'a> {
+    PlanJson {
+        hops: plan
             .hops
             .iter()
-            .find(|h| h.id == pid)
-            .map(|h| h.label.clone())
-            .unwrap_or_else(|| format!("#{pid}"));
-
-        match password_opt.as_ref() {
-            Some(_) => log_info(format!(
-                "using {} for profile {}",
-                c_accent("stored password"),
-                c_accent(&label_for_log),
-            )),
-            None => log_error(format!(
-                "{}={} but {}",
-                c_accent("auth_mode"),
-                c_accent("password"),
-                c_accent("no password stored in keyring"),
-            )),
-        }
+            .map(|h| HopJson {
+                id: h.id,
+                label: &h.label,
+                user: &h.user,
+                host: &h.host,
+                port: h.port,
+                auth_mode: h.auth_mode.as_str(),
+                key_path: h.key_path_local.as_deref(),
+                options: &h.options,
+                has_password: h
+                    .id
+                    .is_some_and(|id| ctx.password_store.peek_profile_password(id).is_some()),
+            })
+            .collect(),
+        argv: std::iter::once(ctx.config.ssh.binary.as_str())
+            .chain(ssh_args.iter().map(String::as_str))
+            .collect(),
+        ssh_config,
     }
+}
 
-    log_info(format!("connecting to {prefix_target}{via_desc} ..."));
-    log_info(format!("exec {} {}", c_accent("ssh"), ssh_args.join(" ")));
+/// ssh 自己出错（而不是远端命令失败）时的退出码
+const SSH_ERROR_EXIT: i32 = 255;
 
-    // 5. 调用系统 ssh，继承当前终端 I/O
-    let status = run_ssh_with_password(&ssh_args, password_opt.as_deref())?;
+/// ssh 的退出状态 -> jmssh 的退出码：正常退出原样透传，被信号杀掉按 shell 惯例记为 128+N
+fn exit_code_of(status: &ExitStatus) -> u8 {
+    if let Some(code) = status.code() {
+        return clamp_exit_code(code);
+    }
 
-    // 6. 退出 log（彩色）
-    if status.success() {
-        log_info(c_accent("ssh session finished OK"));
-    } else if let Some(code) = status.code() {
-        log_error(format!(
-            "{} {}",
-            c_accent("ssh exited with"),
-            c_accent(&format!("code {}", code)),
-        ));
-    } else {
-        // 信号之类的情况
-        log_error(c_accent("ssh exited (terminated by signal)"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(sig) = status.signal() {
+            return 128u8.saturating_add(sig as u8);
+        }
     }
 
-    Ok(())
+    exit_code::FAILURE
+}
+
+/// 进程退出码只有低 8 位能传下去；Windows 上的码可能更宽（256、NTSTATUS），
+/// 低 8 位刚好是 0 时不能变成"成功"
+fn clamp_exit_code(code: i32) -> u8 {
+    match (code, code as u8) {
+        (0, _) => 0,
+        (_, 0) => exit_code::FAILURE,
+        (_, low) => low,
+    }
 }
 
 #[cfg(unix)]
-fn run_ssh_with_password(args: &[String], password: Option<&str>) -> AppResult<ExitStatus> {
-    fn plain_ssh(args: &[String]) -> AppResult<ExitStatus> {
-        match Command::new("ssh").args(args).status() {
-            Ok(status) => Ok(status),
-            Err(e) if e.kind() == ErrorKind::NotFound => {
-                log_error(format!(
-                    "{} `{}` {}",
-                    c_error("ssh binary not found,"),
-                    c_accent("ssh"),
-                    "is required on this system; please install an OpenSSH client package and try again.",
-                ));
-                Err(e.into())
-            }
-            Err(e) => Err(e.into()),
-        }
+fn run_ssh_with_password(
+    ssh: &SshSection,
+    args: &[String],
+    secrets: Vec<HopSecret>,
+    interactive: bool,
+) -> AppResult<ExitStatus> {
+    use std::io::IsTerminal;
+
+    if secrets.is_empty() {
+        return plain_ssh(&ssh.binary, args);
     }
 
-    let pwd = match password {
-        None => return plain_ssh(args),
-        Some(p) => p,
-    };
+    let prefer_sshpass = ssh.autofill == AutofillMode::Sshpass;
+    let is_tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
 
-    // 有密码：优先尝试 sshpass
-    match Command::new("sshpass")
-        .arg("-p")
-        .arg(pwd)
-        .arg("ssh")
-        .args(args)
-        .status()
-    {
+    // 有 TTY 的交互会话：内置 PTY 驱动，不依赖任何外部工具。
+    // 带远端命令时不进 PTY，stdout 原样交给调用方（管道 / 脚本）
+    if is_tty && interactive && !prefer_sshpass {
+        if !ssh_in_path(&ssh.binary) {
+            log_ssh_not_found(&ssh.binary);
+            return Err(AppError::SshNotFound);
+        }
+        return crate::infra::pty_session::run_with_autofill(&ssh.binary, args, secrets);
+    }
+
+    // 无 TTY 或显式要求：走 sshpass（只能喂一个密码）
+    if secrets.len() > 1 {
+        log_warn(format!(
+            "{} can only feed one password; other hops will prompt interactively",
+            c_accent("sshpass"),
+        ));
+    }
+    let pwd = &secrets[0].secret;
+    match sshpass_command(&ssh.binary, args, pwd).status() {
         Ok(status) => {
             log_info(format!(
                 "{} {}",
                 c_accent("sshpass finished with status"),
                 c_accent(&format!("{status}")),
             ));
+            // sshpass 不替用户确认主机 key，遇到没见过的 key 直接以 6 退出
+            if status.code() == Some(6) {
+                log_error(format!(
+                    "{} connect once from a terminal (or add it to known_hosts) to confirm it",
+                    c_error("ssh doesn't know this host key yet;"),
+                ));
+            }
             Ok(status)
         }
 
@@ -219,7 +523,7 @@ fn run_ssh_with_password(args: &[String], password: Option<&str>) -> AppResult<E
                 c_accent("sshpass"),
                 c_accent("sshpass"),
             ));
-            plain_ssh(args)
+            plain_ssh(&ssh.binary, args)
         }
 
         // 其他 IO 错误：上抛
@@ -227,8 +531,100 @@ fn run_ssh_with_password(args: &[String], password: Option<&str>) -> AppResult<E
     }
 }
 
+fn plain_ssh(binary: &str, args: &[String]) -> AppResult<ExitStatus> {
+    match Command::new(binary).args(args).status() {
+        Ok(status) => Ok(status),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            log_ssh_not_found(binary);
+            Err(AppError::SshNotFound)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn log_ssh_not_found(binary: &str) {
+    log_error(format!(
+        "{} `{}` {}",
+        c_error("ssh binary not found,"),
+        c_accent(binary),
+        "is required on this system; please install an OpenSSH client package and try again.",
+    ));
+}
+
+/// PTY 里 exec 失败只能拿到一个 errno，提前在 PATH 里找一下 ssh 以便给出友好提示；
+/// 配置里写的是路径就直接看文件在不在
+#[cfg(unix)]
+fn ssh_in_path(binary: &str) -> bool {
+    if binary.contains('/') {
+        return Path::new(binary).is_file();
+    }
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(binary).is_file()))
+        .unwrap_or(false)
+}
+
+/// `sshpass -e`：密码只放在子进程的 SSHPASS 环境变量里。
+///
+/// argv 对本机所有用户可见（`ps`、`/proc/*/cmdline`），environ 只有同一用户能读，
+/// 所以这里绝不能把密码拼进参数。
+#[cfg(unix)]
+fn sshpass_command(binary: &str, args: &[String], password: &str) -> Command {
+    let mut cmd = Command::new("sshpass");
+    cmd.arg("-e")
+        .arg(binary)
+        .args(args)
+        .env("SSHPASS", password);
+    cmd
+}
+
 #[cfg(windows)]
-fn run_ssh_with_password(args: &[String], password: Option<&str>) -> AppResult<ExitStatus> {
-    let status = Command::new("ssh").args(args).status()?;
-    Ok(status)
+fn run_ssh_with_password(
+    ssh: &SshSection,
+    args: &[String],
+    _secrets: Vec<HopSecret>,
+    _interactive: bool,
+) -> AppResult<ExitStatus> {
+    plain_ssh(&ssh.binary, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ssh_opts_are_split_and_must_be_options() {
+        let raw = ["-v".to_string(), "-L 8080:localhost:80".to_string()];
+        assert_eq!(
+            split_ssh_opts(&raw).unwrap(),
+            ["-v", "-L", "8080:localhost:80"]
+        );
+        assert!(split_ssh_opts(&["uptime".to_string()]).is_err());
+        assert!(split_ssh_opts(&["".to_string()]).is_err());
+    }
+
+    #[test]
+    fn exit_codes_stay_non_zero() {
+        assert_eq!(clamp_exit_code(0), 0);
+        assert_eq!(clamp_exit_code(255), 255);
+        assert_ne!(clamp_exit_code(256), 0);
+        assert_ne!(clamp_exit_code(0xC000_0005_u32 as i32), 0);
+        assert_ne!(clamp_exit_code(-256), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sshpass_keeps_the_password_out_of_argv() {
+        use std::ffi::OsStr;
+
+        let args = vec!["-p".to_string(), "22".to_string(), "u@web".to_string()];
+        let cmd = sshpass_command("ssh", &args, "s3cret");
+
+        assert_eq!(cmd.get_program(), "sshpass");
+        let argv = cmd.get_args().collect::<Vec<_>>();
+        assert_eq!(argv, ["-e", "ssh", "-p", "22", "u@web"]);
+        assert!(!argv.iter().any(|a| a.to_string_lossy().contains("s3cret")));
+
+        let envs = cmd.get_envs().collect::<Vec<_>>();
+        assert!(envs.contains(&(OsStr::new("SSHPASS"), Some(OsStr::new("s3cret")))));
+    }
 }
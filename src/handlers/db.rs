@@ -0,0 +1,79 @@
+use crate::app::AppContext;
+use crate::cli::{DbArgs, DbCommand};
+use crate::db;
+use crate::db::migrations;
+use crate::error::{AppError, AppResult};
+use crate::term::{c_accent, log_info};
+use sea_orm::DatabaseConnection;
+use std::io;
+use std::io::Write;
+use tabwriter::TabWriter;
+
+pub async fn handle_db(ctx: &AppContext, args: DbArgs) -> AppResult<()> {
+    match args.cmd {
+        DbCommand::Status => handle_db_status(&ctx.db).await,
+        DbCommand::Migrate => handle_db_migrate(&ctx.db).await,
+    }
+}
+
+/// 启动时调用：有待应用的迁移就静默补齐，只在真的升级了时提示一行
+pub async fn auto_migrate(db: &DatabaseConnection) -> AppResult<()> {
+    let applied = migrations::migrate(db).await?;
+    if let Some(last) = applied.last() {
+        log_info(format!(
+            "database upgraded to schema {}",
+            c_accent(&format!("v{}", last.version))
+        ));
+    }
+    Ok(())
+}
+
+async fn handle_db_status(db: &DatabaseConnection) -> AppResult<()> {
+    let path = db::db_path().map_err(|e| AppError::IoError(e.to_string()))?;
+    let status = migrations::status(db).await?;
+    let pending = status.iter().filter(|m| m.applied_at.is_none()).count();
+
+    log_info(format!(
+        "database {}",
+        c_accent(&path.display().to_string())
+    ));
+
+    let mut tw = TabWriter::new(io::stdout()).padding(2);
+    writeln!(&mut tw, "VERSION\tNAME\tAPPLIED")?;
+    for m in &status {
+        let applied = match &m.applied_at {
+            Some(at) => at.clone(),
+            None => "pending".to_string(),
+        };
+        writeln!(&mut tw, "v{}\t{}\t{}", m.version, m.name, applied)?;
+    }
+    tw.flush()?;
+
+    if pending > 0 {
+        log_info(format!(
+            "{pending} pending migration(s); run {} to apply",
+            c_accent("jmssh db migrate")
+        ));
+    }
+    Ok(())
+}
+
+async fn handle_db_migrate(db: &DatabaseConnection) -> AppResult<()> {
+    let applied = migrations::migrate(db).await?;
+    if applied.is_empty() {
+        log_info(format!(
+            "database is up to date (schema {})",
+            c_accent(&format!("v{}", migrations::latest_version()))
+        ));
+        return Ok(());
+    }
+
+    for m in applied {
+        log_info(format!(
+            "applied {} {}",
+            c_accent(&format!("v{}", m.version)),
+            m.name
+        ));
+    }
+    Ok(())
+}
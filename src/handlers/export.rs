@@ -0,0 +1,104 @@
+use crate::app::AppContext;
+use crate::cli::{ExportAnsibleArgs, ExportArgs, ExportCommand, ExportSshConfigArgs};
+use crate::db;
+use crate::error::{AppError, AppResult};
+use crate::infra::files::{write_atomic, write_private};
+use crate::infra::{ansible, ssh_config};
+use crate::term::{c_accent, log_info, log_warn};
+use crate::usecase;
+use crate::usecase::backup;
+use std::fs;
+use std::path::Path;
+
+pub async fn handle_export(ctx: &AppContext, args: ExportArgs) -> AppResult<()> {
+    match args.cmd {
+        Some(ExportCommand::SshConfig(a)) => export_ssh_config(ctx, a).await,
+        Some(ExportCommand::Ansible(a)) => export_ansible(ctx, a).await,
+        None => export_backup(ctx, args).await,
+    }
+}
+
+async fn export_backup(ctx: &AppContext, args: ExportArgs) -> AppResult<()> {
+    let doc = backup::build_document(ctx, args.include_secrets).await?;
+    let text = backup::encode(&doc, args.format)?;
+
+    let Some(output) = args.output else {
+        print!("{text}");
+        return Ok(());
+    };
+    write_private(Path::new(&output), &text)?;
+    log_info(format!(
+        "exported {} profiles to {}",
+        doc.profiles.len(),
+        c_accent(&output)
+    ));
+    if args.include_secrets {
+        log_warn("the file contains passwords in plain text; keep it somewhere safe");
+    }
+    Ok(())
+}
+
+async fn export_ssh_config(ctx: &AppContext, args: ExportSshConfigArgs) -> AppResult<()> {
+    let profiles = usecase::export::export_profiles(ctx).await?;
+    // 每个被跳过的 profile 一条提示
+    let (text, skipped) = ssh_config::render_profiles_config(&profiles, &ctx.config.ssh.options);
+    skipped.iter().for_each(log_warn);
+
+    if !args.write {
+        print!("{text}");
+        return Ok(());
+    }
+
+    let path = db::managed_ssh_config_path().map_err(|e| AppError::IoError(e.to_string()))?;
+    write_atomic(&path, &text)?;
+    log_info(format!(
+        "wrote {} profiles to {}",
+        profiles.len() - skipped.len(),
+        c_accent(&path.display().to_string())
+    ));
+
+    if !is_included(&path) {
+        log_info(format!(
+            "add this line near the top of ~/.ssh/config (before any Host block): Include {}",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+async fn export_ansible(ctx: &AppContext, args: ExportAnsibleArgs) -> AppResult<()> {
+    let profiles = usecase::export::export_profiles(ctx).await?;
+    let (text, warnings) = ansible::render_inventory(&profiles, args.format);
+    warnings.iter().for_each(log_warn);
+
+    let Some(output) = args.output else {
+        print!("{text}");
+        return Ok(());
+    };
+    write_atomic(Path::new(&output), &text)?;
+    log_info(format!("wrote inventory to {}", c_accent(&output)));
+    Ok(())
+}
+
+/// 粗略检查 `~/.ssh/config` 里是不是已经 Include 了这个文件（绝对路径或 `~/` 写法）
+fn is_included(path: &Path) -> bool {
+    let Some(home) = directories::BaseDirs::new().map(|b| b.home_dir().to_path_buf()) else {
+        return false;
+    };
+    let Ok(user_config) = fs::read_to_string(home.join(".ssh").join("config")) else {
+        return false;
+    };
+
+    let absolute = path.display().to_string();
+    let tilde = path
+        .strip_prefix(&home)
+        .map(|rest| format!("~/{}", rest.display()))
+        .ok();
+
+    user_config.lines().any(|line| {
+        let line = line.trim();
+        line.get(..7)
+            .is_some_and(|k| k.eq_ignore_ascii_case("include"))
+            && (line.contains(&absolute) || tilde.as_deref().is_some_and(|t| line.contains(t)))
+    })
+}
@@ -0,0 +1,72 @@
+use crate::app::AppContext;
+use crate::cli::{ConnectArgs, HistoryArgs};
+use crate::error::{AppResult, exit_code};
+use crate::handlers;
+use crate::term::{c_accent, log_error, log_info};
+use crate::usecase;
+use std::io;
+use std::io::Write;
+use tabwriter::TabWriter;
+
+pub async fn handle_history(ctx: &AppContext, args: HistoryArgs) -> AppResult<()> {
+    let profile_id = match args.label {
+        Some(label) => Some(usecase::profile::get_profile_by_label(ctx, label).await?.id),
+        None => None,
+    };
+
+    let entries = usecase::history::list_history(ctx, profile_id, args.limit).await?;
+    if entries.is_empty() {
+        log_info("no connections recorded yet");
+        return Ok(());
+    }
+
+    let mut tw = TabWriter::new(io::stdout());
+    writeln!(&mut tw, "STARTED\tTARGET\tDURATION\tEXIT\tHOPS")?;
+    for e in entries {
+        let duration = e
+            .duration_secs
+            .map(format_duration)
+            .unwrap_or_else(|| "-".to_string());
+        let exit = e
+            .exit_code
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        writeln!(
+            &mut tw,
+            "{}\t{}\t{}\t{}\t{}",
+            e.started_at, e.target, duration, exit, e.hops
+        )?;
+    }
+    tw.flush()?;
+    Ok(())
+}
+
+/// 重新连上一次用过的 profile（临时目标不算）
+pub async fn handle_last(ctx: &AppContext) -> AppResult<u8> {
+    let Some(id) = usecase::history::last_profile_id(ctx).await? else {
+        // 和找不到 label 一样按 EX_NOINPUT 退出，脚本里 `jmssh last || ...` 才有意义
+        log_error("no previous profile connection to repeat");
+        return Ok(exit_code::NOT_FOUND);
+    };
+
+    let profile = usecase::profile::get_profile_by_id(ctx, id).await?;
+    log_info(format!("reconnecting to {}", c_accent(&profile.label)));
+
+    handlers::connect::handle_connect(
+        ctx,
+        ConnectArgs {
+            target: Some(profile.label),
+            id: Some(profile.id),
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+fn format_duration(secs: i64) -> String {
+    match secs {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m{:02}s", s / 60, s % 60),
+        s => format!("{}h{:02}m", s / 3600, (s % 3600) / 60),
+    }
+}
@@ -0,0 +1,126 @@
+use crate::app::AppContext;
+use crate::cli::{ImportArgs, ImportCommand, ImportOptions};
+use crate::error::{AppError, AppResult};
+use crate::infra::import::{ansible, mobaxterm, putty, securecrt, ssh_config, xshell};
+use crate::term::{log_info, log_warn};
+use crate::usecase;
+use crate::usecase::backup::{self, BackupFormat};
+use crate::usecase::{ImportAction, ImportBatch, ImportMode, ImportOutcome};
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use tabwriter::TabWriter;
+
+pub async fn handle_import(ctx: &AppContext, args: ImportArgs) -> AppResult<()> {
+    match (args.cmd, args.file) {
+        (Some(ImportCommand::SshConfig(a)), _) => {
+            let batch = ssh_config::parse_ssh_config(a.path.as_deref())?;
+            run_import(ctx, batch, ImportMode::Merge(a.opts.on_conflict), &a.opts).await
+        }
+        (Some(ImportCommand::Xshell(a)), _) => {
+            let batch = xshell::parse_xshell(&a.paths)?;
+            run_import(ctx, batch, ImportMode::Merge(a.opts.on_conflict), &a.opts).await
+        }
+        (Some(ImportCommand::Mobaxterm(a)), _) => {
+            let batch = mobaxterm::parse_mobaxterm(&a.paths)?;
+            run_import(ctx, batch, ImportMode::Merge(a.opts.on_conflict), &a.opts).await
+        }
+        (Some(ImportCommand::Putty(a)), _) => {
+            let batch = putty::parse_putty(&a.paths)?;
+            run_import(ctx, batch, ImportMode::Merge(a.opts.on_conflict), &a.opts).await
+        }
+        (Some(ImportCommand::Securecrt(a)), _) => {
+            let batch = securecrt::parse_securecrt(&a.paths)?;
+            run_import(ctx, batch, ImportMode::Merge(a.opts.on_conflict), &a.opts).await
+        }
+        (Some(ImportCommand::Ansible(a)), _) => {
+            let batch = ansible::parse_ansible_inventory(&a.path)?;
+            run_import(ctx, batch, ImportMode::Merge(a.opts.on_conflict), &a.opts).await
+        }
+        (None, Some(file)) => {
+            let text =
+                fs::read_to_string(&file).map_err(|e| AppError::IoError(format!("{file}: {e}")))?;
+            let doc = backup::decode(&text, guess_format(&file, &text))?;
+            let batch = backup::document_to_batch(doc)?;
+            let mode = if args.replace {
+                ImportMode::Replace
+            } else {
+                ImportMode::Merge(args.opts.on_conflict)
+            };
+            run_import(ctx, batch, mode, &args.opts).await
+        }
+        // clap 保证二者必有其一
+        (None, None) => Ok(()),
+    }
+}
+
+/// 看扩展名，没有就看内容：JSON 文档以 `{` 开头
+fn guess_format(file: &str, text: &str) -> BackupFormat {
+    match Path::new(file).extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("toml") => BackupFormat::Toml,
+        Some(ext) if ext.eq_ignore_ascii_case("json") => BackupFormat::Json,
+        _ if text.trim_start().starts_with('{') => BackupFormat::Json,
+        _ => BackupFormat::Toml,
+    }
+}
+
+async fn run_import(
+    ctx: &AppContext,
+    batch: ImportBatch,
+    mode: ImportMode,
+    opts: &ImportOptions,
+) -> AppResult<()> {
+    // Replace 时空文件也有意义（清空），其余情况没东西就直接返回
+    if batch.profiles.is_empty() && mode != ImportMode::Replace {
+        batch.warnings.iter().for_each(log_warn);
+        log_info("nothing to import");
+        return Ok(());
+    }
+
+    let outcome = usecase::import::import_profiles(ctx, batch, mode, opts.dry_run).await?;
+    print_outcome(&outcome)?;
+
+    outcome.warnings.iter().for_each(log_warn);
+
+    let count =
+        |f: fn(&ImportAction) -> bool| outcome.entries.iter().filter(|e| f(&e.action)).count();
+    let summary = format!(
+        "{} created, {} overwritten, {} renamed, {} skipped, {} removed",
+        count(|a| *a == ImportAction::Create),
+        count(|a| *a == ImportAction::Overwrite),
+        count(|a| matches!(a, ImportAction::Rename { .. })),
+        count(|a| *a == ImportAction::Skip),
+        count(|a| *a == ImportAction::Remove),
+    );
+    if opts.dry_run {
+        log_info(format!("dry run: {summary}; nothing was written"));
+    } else {
+        log_info(summary);
+    }
+    Ok(())
+}
+
+fn print_outcome(outcome: &ImportOutcome) -> AppResult<()> {
+    let mut tw = TabWriter::new(io::stdout());
+    writeln!(&mut tw, "ACTION\tLABEL\tDEST\tVIA")?;
+
+    for e in &outcome.entries {
+        let action = match &e.action {
+            ImportAction::Create => "create".to_string(),
+            ImportAction::Overwrite => "overwrite".to_string(),
+            ImportAction::Skip => "skip (exists)".to_string(),
+            ImportAction::Rename { from } => format!("rename ({from})"),
+            ImportAction::Remove => "remove".to_string(),
+        };
+        let via = if e.jumps.is_empty() {
+            "-".to_string()
+        } else {
+            e.jumps.join(" -> ")
+        };
+        writeln!(&mut tw, "{action}\t{}\t{}\t{via}", e.label, e.dest)?;
+    }
+
+    tw.flush()?;
+    Ok(())
+}
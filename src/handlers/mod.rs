@@ -1,3 +1,10 @@
+pub mod config;
 pub mod connect;
+pub mod db;
+pub mod export;
+pub mod history;
+pub mod import;
 pub mod password;
 pub mod profile;
+pub mod tag;
+pub mod vault;
@@ -31,8 +31,9 @@ async fn handle_password_set(ctx: &AppContext, args: PasswordLabelArgs) -> AppRe
 
     log_info(c_accent(
         format!(
-            "{} password stored in OS keyring",
-            c_warning(profile_info.to_string().as_str())
+            "{} password stored in {}",
+            c_warning(profile_info.to_string().as_str()),
+            ctx.password_store.backend_name(),
         )
         .as_str(),
     ));
@@ -1,8 +1,13 @@
 use crate::app::AppContext;
-use crate::cli::{EditProfileArgs, ProfileArgs, ProfileCommand, RmArgs, ShowArgs};
-use crate::error::{AppError, AppResult};
-use crate::term::{c_accent, log_error, log_info, log_warn};
+use crate::cli::{
+    EditProfileArgs, FindArgs, ListArgs, ProfileArgs, ProfileCommand, RmArgs, ShowArgs,
+};
+use crate::error::AppResult;
+use crate::term::{c_accent, c_warning, log_info, log_warn};
 use crate::usecase;
+use crate::usecase::DeleteMode;
+use crate::usecase::ProfileView;
+use crate::usecase::query::Query;
 use itertools::Itertools;
 use std::io;
 use std::io::Write;
@@ -12,7 +17,8 @@ pub async fn handle_profile(ctx: &AppContext, args: ProfileArgs) -> AppResult<()
     match args.cmd {
         ProfileCommand::Add(args) => profile_add(ctx, args).await?,
         ProfileCommand::Set(args) => profile_set(ctx, args).await?,
-        ProfileCommand::List => profile_list(ctx).await?,
+        ProfileCommand::List(args) => profile_list(ctx, args).await?,
+        ProfileCommand::Find(args) => profile_find(ctx, args).await?,
         ProfileCommand::Rm(args) => profile_rm(ctx, args).await?,
         ProfileCommand::Show(args) => profile_show(ctx, args).await?,
     };
@@ -30,8 +36,13 @@ async fn profile_add(ctx: &AppContext, args: EditProfileArgs) -> AppResult<()> {
             port: args.port,
             mode: args.mode,
             tags: args.tags,
+            tag_changes: args.tag_changes,
             notes: args.note,
+            key_path: args.key,
+            clear_key: args.clear_key,
             jumps: args.jumps,
+            options: args.options,
+            unset_options: args.unset_options,
         },
     )
     .await?;
@@ -55,8 +66,13 @@ async fn profile_set(ctx: &AppContext, args: EditProfileArgs) -> AppResult<()> {
             port: args.port,
             mode: args.mode,
             tags: args.tags,
+            tag_changes: args.tag_changes,
             notes: args.note,
+            key_path: args.key,
+            clear_key: args.clear_key,
             jumps: args.jumps,
+            options: args.options,
+            unset_options: args.unset_options,
         },
     )
     .await?;
@@ -66,9 +82,29 @@ async fn profile_set(ctx: &AppContext, args: EditProfileArgs) -> AppResult<()> {
     Ok(())
 }
 
-async fn profile_list(ctx: &AppContext) -> AppResult<()> {
-    let profiles = usecase::profile::list_profiles(ctx).await?;
+async fn profile_list(ctx: &AppContext, args: ListArgs) -> AppResult<()> {
+    let query = Query::from_tags(&args.tags)?;
+    let mut profiles = usecase::profile::find_profiles(ctx, &query).await?;
+    usecase::profile::sort_profiles(ctx, &mut profiles, args.sort).await?;
+    print_profile_table(profiles)
+}
+
+async fn profile_find(ctx: &AppContext, args: FindArgs) -> AppResult<()> {
+    // 各个参数拼回一句查询，引号里的短语由 shell 保留或由查询语言自己解析
+    let raw = args
+        .query
+        .iter()
+        .map(|q| match q.contains(char::is_whitespace) {
+            true => format!("\"{q}\""),
+            false => q.clone(),
+        })
+        .join(" ");
+    let query = Query::parse(&raw)?;
+    let profiles = usecase::profile::find_profiles(ctx, &query).await?;
+    print_profile_table(profiles)
+}
 
+fn print_profile_table(profiles: Vec<ProfileView>) -> AppResult<()> {
     if profiles.is_empty() {
         log_info("no profiles found");
         return Ok(());
@@ -76,14 +112,19 @@ async fn profile_list(ctx: &AppContext) -> AppResult<()> {
 
     let mut tw = TabWriter::new(io::stdout());
 
-    // 表头：label + 目标 + mode
-    writeln!(&mut tw, "LABEL\tDEST\tMODE")?;
+    // 表头：label + 目标 + mode + tags
+    writeln!(&mut tw, "LABEL\tDEST\tMODE\tTAGS")?;
 
     profiles.into_iter().for_each(|p| {
         writeln!(
             &mut tw,
-            "{}\t{}@{}:{}\t{}",
-            p.label, p.user, p.host, p.port, p.mode,
+            "{}\t{}@{}:{}\t{}\t{}",
+            p.label,
+            p.user,
+            p.host,
+            p.port,
+            p.mode,
+            p.tags.join(","),
         )
         .unwrap_or_default();
     });
@@ -104,13 +145,21 @@ async fn profile_show(ctx: &AppContext, args: ShowArgs) -> AppResult<()> {
     writeln!(&mut tw, "port\t{}", base.port)?;
     writeln!(&mut tw, "mode\t{}", base.mode)?;
 
+    if let Some(key) = base.key_path {
+        writeln!(&mut tw, "key\t{key}")?;
+    }
+
+    for (key, value) in &base.options {
+        writeln!(&mut tw, "opt\t{key}={value}")?;
+    }
+
     if !jumps.is_empty() {
         let jumps_str = jumps.iter().map(|j| j.label.as_str()).join(" -> ");
         writeln!(&mut tw, "jumps\t{jumps_str}")?;
     }
 
-    if let Some(tags) = base.tags {
-        writeln!(&mut tw, "tags\t{tags}")?;
+    if !base.tags.is_empty() {
+        writeln!(&mut tw, "tags\t{}", base.tags.join(", "))?;
     }
     if let Some(note) = base.note {
         writeln!(&mut tw, "note\t{note}")?;
@@ -122,14 +171,29 @@ async fn profile_show(ctx: &AppContext, args: ShowArgs) -> AppResult<()> {
 
 async fn profile_rm(ctx: &AppContext, args: RmArgs) -> AppResult<()> {
     let label = args.label;
-    match usecase::profile::delete_profile_by_label(ctx, label.clone()).await {
-        Ok(()) => {
-            log_warn(format!("profile {} removed", c_accent(&label)));
-        }
-        Err(AppError::ProfileNotFound(_)) => {
-            log_error(format!("profile not found for label {}", c_accent(&label)));
-        }
-        Err(e) => return Err(e),
+    let mode = match (args.force, args.cascade) {
+        (_, true) => DeleteMode::Cascade,
+        (true, _) => DeleteMode::Force,
+        _ => DeleteMode::Refuse,
+    };
+
+    // 找不到 / 仍被依赖时直接上抛，由 main 打印并给出对应退出码
+    let outcome = usecase::profile::delete_profile_by_label(ctx, label.clone(), mode).await?;
+    for removed in &outcome.removed {
+        log_warn(format!("profile {} removed", c_accent(removed)));
+    }
+    for detached in &outcome.detached {
+        log_warn(format!(
+            "profile {} no longer jumps via {}",
+            c_accent(detached),
+            c_accent(&label)
+        ));
+    }
+    for warning in &outcome.secret_warnings {
+        log_warn(format!(
+            "{} {warning}",
+            c_warning("stored password not removed:")
+        ));
     }
     Ok(())
 }
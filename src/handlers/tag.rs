@@ -0,0 +1,24 @@
+use crate::app::AppContext;
+use crate::error::AppResult;
+use crate::term::log_info;
+use crate::usecase;
+use std::io;
+use std::io::Write;
+use tabwriter::TabWriter;
+
+pub async fn handle_tags(ctx: &AppContext) -> AppResult<()> {
+    let tags = usecase::tag::list_tags(ctx).await?;
+
+    if tags.is_empty() {
+        log_info("no tags yet; add one with `jmssh profile set <label> --tag <tag>`");
+        return Ok(());
+    }
+
+    let mut tw = TabWriter::new(io::stdout());
+    writeln!(&mut tw, "TAG\tPROFILES")?;
+    for t in tags {
+        writeln!(&mut tw, "{}\t{}", t.name, t.count)?;
+    }
+    tw.flush()?;
+    Ok(())
+}
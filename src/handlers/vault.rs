@@ -0,0 +1,104 @@
+use crate::cli::{VaultArgs, VaultCommand};
+use crate::error::{AppError, AppResult};
+use crate::term::{c_accent, c_warning, log_info, log_warn};
+use crate::usecase;
+
+pub async fn handle_vault(args: VaultArgs) -> AppResult<()> {
+    match args.cmd {
+        VaultCommand::Unlock => handle_vault_unlock(),
+        VaultCommand::Lock => handle_vault_lock(),
+        VaultCommand::Rekey => handle_vault_rekey(),
+        VaultCommand::Status => handle_vault_status(),
+    }
+}
+
+fn prompt_passphrase(prompt: &str) -> AppResult<String> {
+    rpassword::prompt_password(prompt)
+        .map_err(|e| AppError::IoError(format!("failed to read passphrase: {e}")))
+}
+
+/// 新口令要求输入两次，且不能为空
+fn prompt_new_passphrase(prompt: &str) -> AppResult<String> {
+    let first = prompt_passphrase(prompt)?;
+    if first.is_empty() {
+        return Err(AppError::VaultError(
+            "passphrase must not be empty".to_string(),
+        ));
+    }
+    let second = prompt_passphrase("confirm passphrase: ")?;
+    if first != second {
+        return Err(AppError::VaultError("passphrases do not match".to_string()));
+    }
+    Ok(first)
+}
+
+fn handle_vault_unlock() -> AppResult<()> {
+    let status = usecase::vault::vault_status()?;
+
+    if !status.exists {
+        log_info(format!(
+            "no vault yet, creating {}",
+            c_accent(&status.path.to_string_lossy()),
+        ));
+        let pass = prompt_new_passphrase("new vault passphrase: ")?;
+        usecase::vault::create_vault(&pass)?;
+        if status.caches_key {
+            log_info(c_accent("vault created and unlocked for this session"));
+        } else {
+            log_info(c_accent("vault created"));
+            warn_no_key_cache();
+        }
+        return Ok(());
+    }
+
+    let pass = prompt_passphrase("vault passphrase: ")?;
+    usecase::vault::unlock_vault(&pass)?;
+    if status.caches_key {
+        log_info(c_accent("vault unlocked for this session"));
+    } else {
+        log_info(c_accent("passphrase ok"));
+        warn_no_key_cache();
+    }
+    Ok(())
+}
+
+fn warn_no_key_cache() {
+    log_warn(c_warning(
+        "no per-session runtime directory, so the key is not cached; \
+         jmssh will ask for the passphrase whenever it needs the vault",
+    ));
+}
+
+fn handle_vault_lock() -> AppResult<()> {
+    usecase::vault::lock_vault()?;
+    log_info(c_accent("vault locked"));
+    Ok(())
+}
+
+fn handle_vault_rekey() -> AppResult<()> {
+    let old = prompt_passphrase("current vault passphrase: ")?;
+    let new = prompt_new_passphrase("new vault passphrase: ")?;
+    usecase::vault::rekey_vault(&old, &new)?;
+    log_info(c_accent("vault re-encrypted with the new passphrase"));
+    Ok(())
+}
+
+fn handle_vault_status() -> AppResult<()> {
+    let status = usecase::vault::vault_status()?;
+
+    if !status.exists {
+        log_warn(format!(
+            "no vault at {}",
+            c_warning(&status.path.to_string_lossy()),
+        ));
+        return Ok(());
+    }
+
+    let state = match (status.unlocked, status.caches_key) {
+        (true, _) => "unlocked",
+        (false, true) => "locked",
+        (false, false) => "locked (passphrase asked on use)",
+    };
+    println!("{}\t{}", status.path.display(), state);
+    Ok(())
+}
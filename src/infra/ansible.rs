@@ -0,0 +1,356 @@
+//! 把 profile 渲染成 Ansible inventory（`jmssh export ansible`），让同一份 profile 库直接跑 playbook。
+//!
+//! 每个 profile 是一台主机（inventory 主机名就是 label），标签变成组。Ansible 不认识 jmssh 的 label，
+//! 跳板链展开成 `-o ProxyJump=user@host:port,...` 写进 `ansible_ssh_common_args`；
+//! profile 自己的 ssh 选项也一起写成 `-o Key=Value`。
+
+use crate::entity::profiles::AuthMode;
+use crate::infra::shell;
+use crate::usecase::ProfileExport;
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InventoryFormat {
+    Yaml,
+    Ini,
+}
+
+/// Ansible 自己的组，标签用了这两个名字就不写成组
+const IMPLICIT_GROUPS: [&str; 2] = ["all", "ungrouped"];
+
+const HEADER: &str = "# generated by jmssh from its profile database; regenerate with\n\
+                      # `jmssh export ansible` instead of editing this file\n";
+
+/// 返回 inventory 内容和提示（跳过的 profile、没法带过去的设置，每条一行）
+pub fn render_inventory(
+    profiles: &[ProfileExport],
+    format: InventoryFormat,
+) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let by_label = profiles
+        .iter()
+        .map(|p| (p.profile.label.as_str(), p))
+        .collect::<HashMap<_, _>>();
+
+    // (主机名, 变量)，变量按固定顺序
+    let mut hosts = Vec::new();
+    let mut groups = BTreeMap::<String, Vec<String>>::new();
+    // 组名 -> 映射到它的标签，用来发现 `cloud/aws` 和 `cloud-aws` 这种撞名
+    let mut group_tags = BTreeMap::<String, Vec<&str>>::new();
+
+    for p in profiles {
+        let v = &p.profile;
+        if !is_valid_host_name(&v.label, format) {
+            warnings.push(format!(
+                "{:?}: label can't be used as an inventory host name; skipped",
+                v.label
+            ));
+            continue;
+        }
+
+        let mut vars = vec![
+            ("ansible_host", Value::from(v.host.clone())),
+            ("ansible_user", Value::from(v.user.clone())),
+            ("ansible_port", Value::from(v.port)),
+        ];
+        if let Some(key) = v
+            .key_path
+            .as_deref()
+            .filter(|_| v.mode == AuthMode::Key.as_str())
+        {
+            vars.push(("ansible_ssh_private_key_file", Value::from(key)));
+        }
+        if v.mode == AuthMode::Password.as_str() {
+            warnings.push(format!(
+                "{}: password auth; the stored password is not exported \
+                 (use --ask-pass or set ansible_password yourself)",
+                v.label
+            ));
+        }
+
+        let mut args = Vec::new();
+        let chain = jump_chain(p, &by_label);
+        if !chain.is_empty() {
+            let carries_settings = |h: &&&ProfileExport| {
+                !h.profile.options.is_empty()
+                    || (h.profile.mode == AuthMode::Key.as_str() && h.profile.key_path.is_some())
+            };
+            if let Some(hop) = chain.iter().find(carries_settings) {
+                warnings.push(format!(
+                    "{}: jump {} has its own key or ssh options, which ProxyJump can't carry",
+                    v.label, hop.profile.label
+                ));
+            }
+            let jumps = chain
+                .iter()
+                .map(|h| destination(&h.profile.user, &h.profile.host, h.profile.port))
+                .collect::<Vec<_>>();
+            args.push(format!("-o ProxyJump={}", jumps.join(",")));
+        }
+        for (key, value) in &v.options {
+            args.push(format!("-o {}", shell::quote(&format!("{key}={value}"))));
+        }
+        if !args.is_empty() {
+            vars.push(("ansible_ssh_common_args", Value::from(args.join(" "))));
+        }
+
+        for tag in &v.tags {
+            let group = group_name(tag);
+            if IMPLICIT_GROUPS.contains(&group.as_str()) {
+                warnings.push(format!(
+                    "{}: tag {tag} clashes with Ansible's implicit {group} group; not exported as a group",
+                    v.label
+                ));
+                continue;
+            }
+            let tags = group_tags.entry(group.clone()).or_default();
+            if !tags.contains(&tag.as_str()) {
+                tags.push(tag);
+            }
+            groups.entry(group).or_default().push(v.label.clone());
+        }
+        hosts.push((v.label.clone(), vars));
+    }
+
+    for (group, tags) in &group_tags {
+        if tags.len() > 1 {
+            warnings.push(format!(
+                "tags {} all become group {group}; their hosts are merged",
+                tags.join(", ")
+            ));
+        }
+    }
+
+    let body = match format {
+        InventoryFormat::Yaml => render_yaml(&hosts, &groups),
+        InventoryFormat::Ini => render_ini(&hosts, &groups),
+    };
+    (format!("{HEADER}{body}"), warnings)
+}
+
+type HostVars = Vec<(&'static str, Value)>;
+
+fn render_yaml(hosts: &[(String, HostVars)], groups: &BTreeMap<String, Vec<String>>) -> String {
+    let mut all_hosts = Mapping::new();
+    for (label, vars) in hosts {
+        let vars = vars
+            .iter()
+            .map(|(k, v)| (Value::from(*k), v.clone()))
+            .collect::<Mapping>();
+        all_hosts.insert(Value::from(label.clone()), Value::Mapping(vars));
+    }
+
+    let mut children = Mapping::new();
+    for (group, members) in groups {
+        let members = members
+            .iter()
+            .map(|m| (Value::from(m.clone()), Value::Null))
+            .collect::<Mapping>();
+        let mut body = Mapping::new();
+        body.insert("hosts".into(), Value::Mapping(members));
+        children.insert(Value::from(group.clone()), Value::Mapping(body));
+    }
+
+    let mut all = Mapping::new();
+    all.insert("hosts".into(), Value::Mapping(all_hosts));
+    if !children.is_empty() {
+        all.insert("children".into(), Value::Mapping(children));
+    }
+    let mut doc = Mapping::new();
+    doc.insert("all".into(), Value::Mapping(all));
+
+    // Mapping 只含字符串和数字，序列化不会失败
+    serde_yaml::to_string(&doc).unwrap_or_default()
+}
+
+fn render_ini(hosts: &[(String, HostVars)], groups: &BTreeMap<String, Vec<String>>) -> String {
+    // 主机都写在第一个 section 之前，组里只列名字
+    let mut out = String::from("\n");
+    for (label, vars) in hosts {
+        out.push_str(label);
+        for (k, v) in vars {
+            let v = match v {
+                Value::String(s) => s.clone(),
+                v => serde_yaml::to_string(v)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            };
+            out.push_str(&format!(" {k}={}", shell::quote(&v)));
+        }
+        out.push('\n');
+    }
+
+    for (group, members) in groups {
+        out.push_str(&format!("\n[{group}]\n"));
+        for m in members {
+            out.push_str(m);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// 完整跳板链（跳板自己的跳板在前），和连接时走的顺序一致
+fn jump_chain<'a>(
+    p: &'a ProfileExport,
+    by_label: &HashMap<&str, &'a ProfileExport>,
+) -> Vec<&'a ProfileExport> {
+    fn walk<'a>(
+        p: &'a ProfileExport,
+        by_label: &HashMap<&str, &'a ProfileExport>,
+        visiting: &mut HashSet<&'a str>,
+        out: &mut Vec<&'a ProfileExport>,
+    ) {
+        for j in &p.jumps {
+            let Some(hop) = by_label.get(j.as_str()).copied() else {
+                continue;
+            };
+            // 库里不允许成环，这里只是防御
+            if !visiting.insert(hop.profile.label.as_str()) {
+                continue;
+            }
+            walk(hop, by_label, visiting, out);
+            out.push(hop);
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut visiting = HashSet::from([p.profile.label.as_str()]);
+    walk(p, by_label, &mut visiting, &mut out);
+    out
+}
+
+fn destination(user: &str, host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("{user}@[{host}]:{port}")
+    } else {
+        format!("{user}@{host}:{port}")
+    }
+}
+
+/// Ansible 的组名只能是字母、数字和下划线，不能以数字开头：`cloud/aws` -> `cloud_aws`
+fn group_name(tag: &str) -> String {
+    let name = tag
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    match name.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{name}"),
+        false => name,
+    }
+}
+
+/// 方括号会被当成范围、冒号会被当成端口；INI 里空白、`=`、`#` 和引号也不行
+fn is_valid_host_name(label: &str, format: InventoryFormat) -> bool {
+    let forbidden = |c: char| {
+        c.is_control()
+            || matches!(c, '[' | ']' | ':')
+            || (format == InventoryFormat::Ini
+                && (c.is_whitespace() || matches!(c, '=' | '#' | ';' | '"' | '\'')))
+    };
+    !label.is_empty() && !label.contains(forbidden)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::import::ansible::parse_ansible_inventory;
+    use crate::test_support::TempDir;
+    use crate::usecase::ProfileView;
+
+    fn export(label: &str, mode: &str, port: u16, tags: &[&str], jumps: &[&str]) -> ProfileExport {
+        ProfileExport {
+            profile: ProfileView {
+                port,
+                ..ProfileView::fixture(label, mode, tags)
+            },
+            jumps: jumps.iter().map(|j| j.to_string()).collect(),
+        }
+    }
+
+    fn sample() -> Vec<ProfileExport> {
+        let mut db = export("db", "agent", 5432, &["prod"], &["web"]);
+        db.profile.options = vec![("ServerAliveInterval".into(), "15".into())];
+        vec![
+            export("bastion", "agent", 2222, &["infra"], &[]),
+            export("web", "agent", 22, &["prod", "web"], &["bastion"]),
+            db,
+        ]
+    }
+
+    fn round_trip(format: InventoryFormat, file: &str) {
+        let profiles = sample();
+        let (text, warnings) = render_inventory(&profiles, format);
+        assert!(warnings.is_empty(), "{warnings:?}");
+
+        let dir = TempDir::new("ansible");
+        let path = dir.write(file, &text);
+        let batch = parse_ansible_inventory(&path.display().to_string()).unwrap();
+        assert!(batch.warnings.is_empty(), "{:?}", batch.warnings);
+        assert_eq!(batch.profiles.len(), profiles.len(), "{text}");
+
+        let by_label = profiles
+            .iter()
+            .map(|p| (p.profile.label.as_str(), p))
+            .collect::<HashMap<_, _>>();
+        for want in &profiles {
+            let v = &want.profile;
+            let got = batch.profiles.iter().find(|p| p.label == v.label).unwrap();
+            assert_eq!(got.host, v.host);
+            assert_eq!(got.user.as_deref(), Some(v.user.as_str()));
+            assert_eq!(got.port, Some(v.port));
+            assert_eq!(got.options, v.options);
+
+            let mut tags = got.tags.clone();
+            tags.sort();
+            assert_eq!(tags, v.tags, "{}", v.label);
+
+            // Ansible 里只有展开后的整条链
+            let chain = jump_chain(want, &by_label)
+                .iter()
+                .map(|h| h.profile.label.clone())
+                .collect::<Vec<_>>();
+            assert_eq!(got.jumps, chain, "{}", v.label);
+        }
+    }
+
+    #[test]
+    fn yaml_inventory_round_trips() {
+        round_trip(InventoryFormat::Yaml, "hosts.yml");
+    }
+
+    #[test]
+    fn ini_inventory_round_trips() {
+        round_trip(InventoryFormat::Ini, "hosts.ini");
+    }
+
+    #[test]
+    fn lossy_tags_and_passwords_are_reported() {
+        let profiles = [
+            export("a", "password", 22, &["all", "cloud/aws"], &[]),
+            export("b", "agent", 22, &["cloud-aws", "ungrouped"], &[]),
+        ];
+        let (text, warnings) = render_inventory(&profiles, InventoryFormat::Ini);
+
+        let warned = |needle: &str| warnings.iter().any(|w| w.contains(needle));
+        assert!(warned("a: password auth"));
+        assert!(warned("a: tag all clashes"));
+        assert!(warned("b: tag ungrouped clashes"));
+        assert!(warned(
+            "tags cloud/aws, cloud-aws all become group cloud_aws"
+        ));
+        assert!(!text.contains("[all]") && !text.contains("[ungrouped]"));
+        assert!(text.contains("[cloud_aws]\na\nb\n"));
+    }
+
+    #[test]
+    fn group_names_are_ansible_identifiers() {
+        assert_eq!(group_name("cloud/aws"), "cloud_aws");
+        assert_eq!(group_name("2024-q1"), "_2024_q1");
+        assert!(is_valid_host_name("web 1", InventoryFormat::Yaml));
+        assert!(!is_valid_host_name("web 1", InventoryFormat::Ini));
+        assert!(!is_valid_host_name("web[1]", InventoryFormat::Yaml));
+    }
+}
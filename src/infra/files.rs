@@ -0,0 +1,41 @@
+//! 落盘的小工具：先写同目录下的 `<name>.tmp`，fsync 后再 rename，
+//! 读的一方（以及写到一半崩溃之后）不会看到半个文件。
+
+use crate::error::AppResult;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 普通文件（config.toml、导出的 ssh_config 等），权限按 umask
+pub fn write_atomic(path: &Path, data: impl AsRef<[u8]>) -> AppResult<()> {
+    write_with(path, data.as_ref(), None)
+}
+
+/// 可能含秘密的文件（vault、key 缓存、带密码的备份），只有自己能读写（0600）
+pub fn write_private(path: &Path, data: impl AsRef<[u8]>) -> AppResult<()> {
+    write_with(path, data.as_ref(), Some(0o600))
+}
+
+fn write_with(path: &Path, data: &[u8], mode: Option<u32>) -> AppResult<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut opts = fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    let mut f = opts.open(&tmp)?;
+    f.write_all(data)?;
+    f.sync_all()?;
+    drop(f);
+
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
@@ -0,0 +1,796 @@
+//! Ansible inventory（INI 或 YAML）。
+//!
+//! 每个 inventory 主机一个 profile，label 就是 inventory 里的主机名（`web[01:03]` 这样的范围会展开）。
+//! 变量按 Ansible 的优先级合并（all < 父组 < 子组 < 主机），所在的组（含父组，不含 all / ungrouped）当标签。
+//! `ansible_ssh_common_args` / `ansible_ssh_extra_args` 里的 ProxyJump（`-J` / `-o ProxyJump=` /
+//! `ssh -W` 形式的 ProxyCommand）翻译成跳板，指向 inventory 里的主机时直接连到那个 profile；
+//! 其余 `-o` 选项成为 profile 的 ssh 选项。Jinja 模板和 vault 加密的值没法求值，只给提示。
+
+use crate::error::{AppError, AppResult};
+use crate::infra::import::{jump_host, push_jump, push_profile};
+use crate::infra::shell;
+use crate::usecase::{ImportBatch, ImportedProfile};
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::Path;
+
+/// 不参与标签的内置组
+const IMPLICIT_GROUPS: [&str; 2] = ["all", "ungrouped"];
+
+/// 一个主机模式最多展开成多少台主机，防止 `web[0:99999999]` 这种写错的范围
+const MAX_RANGE_HOSTS: usize = 10_000;
+
+#[derive(Debug, Clone)]
+enum Var {
+    Text(String),
+    /// `!vault` 之类带标签的值，拿不到明文
+    Encrypted,
+}
+
+type Vars = BTreeMap<String, Var>;
+
+#[derive(Debug, Default)]
+struct Inventory {
+    /// 按第一次出现的顺序
+    hosts: Vec<String>,
+    host_vars: HashMap<String, Vars>,
+    /// 主机直接所在的组
+    host_groups: HashMap<String, Vec<String>>,
+    group_vars: HashMap<String, Vars>,
+    /// 子组 -> 父组
+    parents: HashMap<String, Vec<String>>,
+    warnings: Vec<String>,
+}
+
+/// 解析出来、还没连跳板的主机
+struct Host {
+    label: String,
+    host: String,
+    user: Option<String>,
+    port: Option<u16>,
+    jump_specs: Vec<String>,
+    profile: ImportedProfile,
+}
+
+pub fn parse_ansible_inventory(path: &str) -> AppResult<ImportBatch> {
+    let text = fs::read_to_string(path).map_err(|e| AppError::IoError(format!("{path}: {e}")))?;
+
+    let mut inv = Inventory::default();
+    if is_yaml(path, &text) {
+        parse_yaml(&text, &mut inv)?;
+    } else {
+        parse_ini(&text, &mut inv);
+    }
+
+    let mut batch = ImportBatch {
+        warnings: std::mem::take(&mut inv.warnings),
+        ..Default::default()
+    };
+    let hosts = inv
+        .hosts
+        .iter()
+        .filter_map(|name| {
+            let (vars, groups) = inv.resolve(name);
+            match host(name, &vars, groups, &mut batch.warnings) {
+                Ok(h) => Some(h),
+                Err(why) => {
+                    batch.warnings.push(format!("{name}: skipped: {why}"));
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // 跳板写的是地址，能对上 inventory 里的主机就连到它，否则单独建一个跳板 profile
+    let known = hosts
+        .iter()
+        .map(|h| (h.label.clone(), h.host.clone(), h.user.clone(), h.port))
+        .collect::<Vec<_>>();
+    for mut h in hosts {
+        for spec in &h.jump_specs {
+            match jump_target(spec, &known, &mut batch) {
+                Some(label) => h.profile.jumps.push(label),
+                None => batch
+                    .warnings
+                    .push(format!("{}: jump {spec} not understood; skipped", h.label)),
+            }
+        }
+        push_profile(&mut batch, path, h.profile);
+    }
+
+    Ok(batch)
+}
+
+fn is_yaml(path: &str, text: &str) -> bool {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("yml") || ext.eq_ignore_ascii_case("yaml") => true,
+        Some(ext) if ext.eq_ignore_ascii_case("ini") => false,
+        // 没有扩展名（比如 `hosts`）：YAML 的第一行是 `---` 或 `组名:`
+        _ => text
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty() && !l.starts_with(['#', ';']))
+            .is_some_and(|l| l == "---" || (l.ends_with(':') && !l.starts_with('['))),
+    }
+}
+
+impl Inventory {
+    fn add_host(&mut self, pattern: &str, group: &str, vars: Vars) {
+        let names = match expand_ranges(pattern) {
+            Ok(names) => names,
+            Err(why) => {
+                self.warnings.push(format!("{pattern}: {why}; skipped"));
+                return;
+            }
+        };
+        for name in names {
+            // `host:port` 是 inventory 里写端口的简写
+            let (name, port) = split_host_port(&name);
+            if !self.host_vars.contains_key(&name) {
+                self.hosts.push(name.clone());
+            }
+            let host_vars = self.host_vars.entry(name.clone()).or_default();
+            if let Some(port) = port {
+                host_vars.insert("ansible_port".into(), Var::Text(port));
+            }
+            host_vars.extend(vars.clone());
+
+            let groups = self.host_groups.entry(name).or_default();
+            if !groups.iter().any(|g| g == group) {
+                groups.push(group.to_string());
+            }
+        }
+    }
+
+    fn add_child(&mut self, parent: &str, child: &str) {
+        let parents = self.parents.entry(child.to_string()).or_default();
+        if !parents.iter().any(|p| p == parent) {
+            parents.push(parent.to_string());
+        }
+    }
+
+    /// 合并后的变量，以及主机所在的全部组（近的在前）
+    fn resolve(&self, host: &str) -> (Vars, Vec<String>) {
+        let mut groups = Vec::new();
+        let mut seen = HashSet::new();
+        let mut queue = self
+            .host_groups
+            .get(host)
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<VecDeque<_>>();
+        while let Some(g) = queue.pop_front() {
+            if !seen.insert(g.clone()) {
+                continue;
+            }
+            queue.extend(self.parents.get(&g).cloned().unwrap_or_default());
+            groups.push(g);
+        }
+
+        let mut vars = self.group_vars.get("all").cloned().unwrap_or_default();
+        for g in groups.iter().rev().filter(|g| *g != "all") {
+            vars.extend(self.group_vars.get(g).cloned().unwrap_or_default());
+        }
+        vars.extend(self.host_vars.get(host).cloned().unwrap_or_default());
+
+        groups.retain(|g| !IMPLICIT_GROUPS.contains(&g.as_str()));
+        (vars, groups)
+    }
+}
+
+fn parse_ini(text: &str, inv: &mut Inventory) {
+    enum Section {
+        Hosts(String),
+        Vars(String),
+        Children(String),
+        Unknown,
+    }
+    let mut section = Section::Hosts("ungrouped".into());
+
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        let at = n + 1;
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = match name.split_once(':') {
+                None => Section::Hosts(name.to_string()),
+                Some((group, "vars")) => Section::Vars(group.to_string()),
+                Some((group, "children")) => Section::Children(group.to_string()),
+                Some(_) => {
+                    inv.warnings.push(format!(
+                        "line {at}: section [{name}] not understood; skipped"
+                    ));
+                    Section::Unknown
+                }
+            };
+            if let Section::Hosts(g) | Section::Vars(g) | Section::Children(g) = &section {
+                inv.group_vars.entry(g.clone()).or_default();
+            }
+            continue;
+        }
+
+        match &section {
+            Section::Hosts(group) => {
+                let words = shell::split(line);
+                let Some((pattern, rest)) = words.split_first() else {
+                    continue;
+                };
+                let mut vars = Vars::new();
+                for word in rest {
+                    match word.split_once('=') {
+                        Some((k, v)) => {
+                            vars.insert(k.to_string(), Var::Text(v.to_string()));
+                        }
+                        None => inv
+                            .warnings
+                            .push(format!("line {at}: {word:?} is not key=value; ignored")),
+                    }
+                }
+                inv.add_host(pattern, group, vars);
+            }
+            Section::Vars(group) => {
+                let Some((k, v)) = line.split_once('=') else {
+                    inv.warnings
+                        .push(format!("line {at}: {line:?} is not key=value; ignored"));
+                    continue;
+                };
+                let v = shell::split(v).join(" ");
+                inv.group_vars
+                    .entry(group.clone())
+                    .or_default()
+                    .insert(k.trim().to_string(), Var::Text(v));
+            }
+            Section::Children(group) => {
+                let group = group.clone();
+                if let Some(child) = line.split_whitespace().next() {
+                    inv.group_vars.entry(child.to_string()).or_default();
+                    inv.add_child(&group, child);
+                }
+            }
+            Section::Unknown => {}
+        }
+    }
+}
+
+fn parse_yaml(text: &str, inv: &mut Inventory) -> AppResult<()> {
+    let doc =
+        serde_yaml::from_str::<Value>(text).map_err(|e| AppError::InvalidImport(e.to_string()))?;
+
+    match doc {
+        Value::Mapping(groups) => {
+            for (name, body) in &groups {
+                if let Some(name) = scalar(name) {
+                    yaml_group(inv, &name, body, None);
+                }
+            }
+            Ok(())
+        }
+        Value::Null => Ok(()),
+        _ => Err(AppError::InvalidImport(
+            "expected groups at the top level of the inventory".into(),
+        )),
+    }
+}
+
+/// `组名: { hosts: {...}, vars: {...}, children: {...} }`，每一项都可以省略或是 null
+fn yaml_group(inv: &mut Inventory, name: &str, body: &Value, parent: Option<&str>) {
+    inv.group_vars.entry(name.to_string()).or_default();
+    if let Some(parent) = parent {
+        inv.add_child(parent, name);
+    }
+
+    if let Some(Value::Mapping(hosts)) = body.get("hosts") {
+        for (host, vars) in hosts {
+            if let Some(host) = scalar(host) {
+                inv.add_host(&host, name, yaml_vars(vars));
+            }
+        }
+    }
+    if let Some(vars) = body.get("vars") {
+        let vars = yaml_vars(vars);
+        inv.group_vars
+            .entry(name.to_string())
+            .or_default()
+            .extend(vars);
+    }
+    if let Some(Value::Mapping(children)) = body.get("children") {
+        for (child, child_body) in children {
+            if let Some(child) = scalar(child) {
+                yaml_group(inv, &child, child_body, Some(name));
+            }
+        }
+    }
+}
+
+/// 只收标量；列表、字典这种不是连接参数，直接忽略
+fn yaml_vars(value: &Value) -> Vars {
+    let Value::Mapping(map) = value else {
+        return Vars::new();
+    };
+    map.iter()
+        .filter_map(|(k, v)| {
+            let k = scalar(k)?;
+            match v {
+                Value::Tagged(_) => Some((k, Var::Encrypted)),
+                v => scalar(v).map(|v| (k, Var::Text(v))),
+            }
+        })
+        .collect()
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn host(
+    name: &str,
+    vars: &Vars,
+    groups: Vec<String>,
+    warnings: &mut Vec<String>,
+) -> Result<Host, String> {
+    // 取第一个有值的变量名（新名字在前，旧的 ansible_ssh_* 在后）
+    let mut get = |keys: &[&str]| {
+        let (key, var) = keys.iter().find_map(|k| vars.get(*k).map(|v| (*k, v)))?;
+        match var {
+            Var::Encrypted => {
+                warnings.push(format!("{name}: {key} is encrypted; not imported"));
+                None
+            }
+            Var::Text(v) if v.contains("{{") || v.contains("{%") => {
+                warnings.push(format!("{name}: {key} is a template; not imported"));
+                None
+            }
+            Var::Text(v) if v.is_empty() => None,
+            Var::Text(v) => Some(v.clone()),
+        }
+    };
+
+    if let Some(conn) = get(&["ansible_connection"])
+        && !matches!(conn.as_str(), "ssh" | "smart" | "paramiko" | "paramiko_ssh")
+    {
+        return Err(format!("ansible_connection is {conn}, not ssh"));
+    }
+
+    let host = get(&["ansible_host", "ansible_ssh_host"]).unwrap_or_else(|| name.to_string());
+    let user = get(&["ansible_user", "ansible_ssh_user", "ansible_remote_user"]);
+    let port = match get(&["ansible_port", "ansible_ssh_port"]) {
+        Some(raw) => Some(raw.parse::<u16>().map_err(|_| format!("bad port {raw}"))?),
+        None => None,
+    };
+    let key_path = get(&["ansible_ssh_private_key_file", "ansible_private_key_file"]);
+    let has_password = get(&["ansible_password", "ansible_ssh_pass"]).is_some();
+    let ssh_arg_lists = ["ansible_ssh_common_args", "ansible_ssh_extra_args"]
+        .into_iter()
+        .filter_map(|key| get(&[key]))
+        .collect::<Vec<_>>();
+
+    if has_password {
+        warnings.push(format!(
+            "{name}: inventory password not imported; run `jmssh password set {name}`"
+        ));
+    }
+    let mut jump_specs = Vec::new();
+    let mut options = Vec::new();
+    for args in &ssh_arg_lists {
+        ssh_args(name, args, &mut jump_specs, &mut options, warnings);
+    }
+
+    Ok(Host {
+        label: name.to_string(),
+        host: host.clone(),
+        user: user.clone(),
+        port,
+        jump_specs,
+        profile: ImportedProfile {
+            label: name.to_string(),
+            host,
+            user,
+            port,
+            key_path,
+            tags: groups,
+            options,
+            ..Default::default()
+        },
+    })
+}
+
+/// ssh 命令行参数里能翻译的部分：`-J`、`-o ProxyJump=`、`ssh -W` 形式的 ProxyCommand 和其余 `-o`
+fn ssh_args(
+    name: &str,
+    args: &str,
+    jumps: &mut Vec<String>,
+    options: &mut Vec<(String, String)>,
+    warnings: &mut Vec<String>,
+) {
+    let words = shell::split(args);
+    let mut iter = words.iter();
+
+    while let Some(word) = iter.next() {
+        let (flag, inline) = match word.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => rest.split_at(1),
+            _ => {
+                warnings.push(format!("{name}: ssh argument {word:?} not imported"));
+                continue;
+            }
+        };
+        let value = match (flag, inline) {
+            ("J" | "o", "") => iter.next().cloned(),
+            ("J" | "o", v) => Some(v.to_string()),
+            _ => {
+                warnings.push(format!("{name}: ssh argument {word:?} not imported"));
+                continue;
+            }
+        };
+        let Some(value) = value else {
+            warnings.push(format!("{name}: {word} has no value"));
+            continue;
+        };
+
+        if flag == "J" {
+            jumps.extend(value.split(',').map(str::to_string));
+            continue;
+        }
+        let (key, v) = match value.split_once(['=', ' ']) {
+            Some((k, v)) => (k.trim(), v.trim()),
+            None => (value.as_str(), ""),
+        };
+        if key.eq_ignore_ascii_case("ProxyJump") {
+            jumps.extend(v.split(',').map(str::to_string));
+        } else if key.eq_ignore_ascii_case("ProxyCommand") {
+            match proxy_command_jump(v) {
+                Some(spec) => jumps.push(spec),
+                None => warnings.push(format!("{name}: ProxyCommand {v:?} not imported")),
+            }
+        } else {
+            options.push((key.to_string(), v.to_string()));
+        }
+    }
+}
+
+/// `ssh -W %h:%p [-p port] [-l user] [user@]host` 就是 ProxyJump 的老写法
+fn proxy_command_jump(command: &str) -> Option<String> {
+    let words = shell::split(command);
+    let (program, args) = words.split_first()?;
+    if !(program == "ssh" || program.ends_with("/ssh")) || !words.iter().any(|w| w == "-W") {
+        return None;
+    }
+
+    let (mut user, mut port, mut dest) = (None, None, None);
+    let mut iter = args.iter();
+    while let Some(w) = iter.next() {
+        match w.as_str() {
+            "-p" => port = iter.next(),
+            "-l" => user = iter.next(),
+            // 其余带参数的选项，参数跳过
+            "-W" | "-i" | "-o" | "-F" | "-J" | "-b" | "-c" | "-m" => {
+                iter.next();
+            }
+            w if w.starts_with('-') => {}
+            _ => dest = Some(w.as_str()),
+        }
+    }
+
+    let dest = dest?;
+    let mut spec = match (user, dest.contains('@')) {
+        (Some(user), false) => format!("{user}@{dest}"),
+        _ => dest.to_string(),
+    };
+    if let Some(port) = port {
+        spec = format!("{spec}:{port}");
+    }
+    Some(spec)
+}
+
+/// 跳板：inventory 里的主机名，或者能对上某台主机的 `[user@]host[:port]`，都连到那个 profile
+fn jump_target(
+    spec: &str,
+    known: &[(String, String, Option<String>, Option<u16>)],
+    batch: &mut ImportBatch,
+) -> Option<String> {
+    if known.iter().any(|(label, ..)| label == spec) {
+        return Some(spec.to_string());
+    }
+
+    let (user, rest) = match spec.rsplit_once('@') {
+        Some((user, rest)) => (Some(user), rest),
+        None => (None, spec),
+    };
+    let (host, port) = split_host_port(rest);
+    let port = match port {
+        Some(p) => p.parse::<u16>().ok()?,
+        None => 22,
+    };
+    if host.is_empty() {
+        return None;
+    }
+
+    let matched = known.iter().find(|(_, h, u, p)| {
+        *h == host
+            && p.unwrap_or(22) == port
+            && match (user, u) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            }
+    });
+    Some(match matched {
+        Some((label, ..)) => label.clone(),
+        None => push_jump(batch, jump_host(user, &host, port)),
+    })
+}
+
+/// `host:port` / `[v6]:port`；不带方括号、有多个冒号的是 IPv6 地址本身
+fn split_host_port(s: &str) -> (String, Option<String>) {
+    if let Some(rest) = s.strip_prefix('[')
+        && let Some((host, after)) = rest.split_once(']')
+    {
+        return (
+            host.to_string(),
+            after.strip_prefix(':').map(str::to_string),
+        );
+    }
+    match s.split_once(':') {
+        Some((host, port))
+            if !port.contains(':')
+                && !port.is_empty()
+                && port.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            (host.to_string(), Some(port.to_string()))
+        }
+        _ => (s.to_string(), None),
+    }
+}
+
+/// `web[01:03].example.com` -> web01、web02、web03；`[a:c]` 字母范围，`[1:9:2]` 带步长。
+/// 写不对的方括号原样保留；开头大于结尾、展开太多的范围是错误
+fn expand_ranges(pattern: &str) -> Result<Vec<String>, String> {
+    let Some(open) = pattern.find('[') else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    let Some(close) = pattern[open..].find(']').map(|i| open + i) else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    let (head, body, tail) = (
+        &pattern[..open],
+        &pattern[open + 1..close],
+        &pattern[close + 1..],
+    );
+
+    let parts = body.split(':').collect::<Vec<_>>();
+    let (begin, end, step) = match parts.as_slice() {
+        [b, e] => (*b, *e, 1),
+        [b, e, s] => match s.parse::<usize>() {
+            Ok(s) if s > 0 => (*b, *e, s),
+            _ => return Ok(vec![pattern.to_string()]),
+        },
+        _ => return Ok(vec![pattern.to_string()]),
+    };
+
+    let check = |b: u64, e: u64| {
+        if b > e {
+            return Err(format!("range [{body}] starts after it ends"));
+        }
+        if (e - b) / step as u64 >= MAX_RANGE_HOSTS as u64 {
+            return Err(format!(
+                "range [{body}] expands to more than {MAX_RANGE_HOSTS} hosts"
+            ));
+        }
+        Ok(())
+    };
+
+    let items = if let (Ok(b), Ok(e)) = (begin.parse::<u64>(), end.parse::<u64>()) {
+        check(b, e)?;
+        // 开头补零的按开头的宽度补齐
+        let width = if begin.len() > 1 && begin.starts_with('0') {
+            begin.len()
+        } else {
+            0
+        };
+        (b..=e)
+            .step_by(step)
+            .map(|n| format!("{n:0width$}"))
+            .collect::<Vec<_>>()
+    } else if let ([b], [e]) = (begin.as_bytes(), end.as_bytes())
+        && b.is_ascii_alphabetic()
+        && e.is_ascii_alphabetic()
+    {
+        check(u64::from(*b), u64::from(*e))?;
+        (*b..=*e)
+            .step_by(step)
+            .map(|c| (c as char).to_string())
+            .collect()
+    } else {
+        return Ok(vec![pattern.to_string()]);
+    };
+
+    let mut out = Vec::new();
+    for item in &items {
+        out.extend(expand_ranges(&format!("{head}{item}{tail}"))?);
+        if out.len() > MAX_RANGE_HOSTS {
+            return Err(format!("expands to more than {MAX_RANGE_HOSTS} hosts"));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn parse(file: &str, text: &str) -> ImportBatch {
+        let dir = TempDir::new("ansible-import");
+        let path = dir.write(file, text);
+        parse_ansible_inventory(&path.display().to_string()).unwrap()
+    }
+
+    fn find<'a>(batch: &'a ImportBatch, label: &str) -> &'a ImportedProfile {
+        batch
+            .profiles
+            .iter()
+            .find(|p| p.label == label)
+            .unwrap_or_else(|| panic!("{label} not imported"))
+    }
+
+    #[test]
+    fn ranges_expand_with_padding_letters_and_steps() {
+        assert_eq!(
+            expand_ranges("web[01:03].example.com").unwrap(),
+            [
+                "web01.example.com",
+                "web02.example.com",
+                "web03.example.com"
+            ]
+        );
+        assert_eq!(expand_ranges("db-[a:c]").unwrap(), ["db-a", "db-b", "db-c"]);
+        assert_eq!(expand_ranges("n[1:9:4]").unwrap(), ["n1", "n5", "n9"]);
+        assert_eq!(
+            expand_ranges("r[1:2]c[a:b]").unwrap(),
+            ["r1ca", "r1cb", "r2ca", "r2cb"]
+        );
+        // 不是范围的方括号原样保留
+        assert_eq!(expand_ranges("odd[1]").unwrap(), ["odd[1]"]);
+        assert_eq!(expand_ranges("odd[1:x]").unwrap(), ["odd[1:x]"]);
+    }
+
+    #[test]
+    fn reversed_and_huge_ranges_are_errors() {
+        assert!(
+            expand_ranges("web[3:1]")
+                .unwrap_err()
+                .contains("starts after it ends")
+        );
+        assert!(expand_ranges("web[c:a]").is_err());
+        assert!(
+            expand_ranges("web[0:99999999999]")
+                .unwrap_err()
+                .contains("more than")
+        );
+        assert!(
+            expand_ranges("a[0:999]b[0:999]")
+                .unwrap_err()
+                .contains("more than")
+        );
+        assert_eq!(expand_ranges("web[0:9999]").unwrap().len(), MAX_RANGE_HOSTS);
+    }
+
+    #[test]
+    fn ini_inventory_applies_group_vars_and_jumps() {
+        let batch = parse(
+            "inventory.ini",
+            "bastion.example.com:2222 ansible_user=jump\n\
+             \n\
+             [web]\n\
+             web[1:2] ansible_host=10.0.0.1 ansible_ssh_common_args='-J jump@bastion.example.com:2222 -o ServerAliveInterval=15'\n\
+             broken[3:1]\n\
+             \n\
+             [db]\n\
+             db1 ansible_port=5432 ansible_ssh_common_args=\"-o ProxyCommand='ssh -W %h:%p -p 2200 ops@gw.example.com'\"\n\
+             \n\
+             [prod:children]\n\
+             web\n\
+             db\n\
+             \n\
+             [prod:vars]\n\
+             ansible_user=deploy\n\
+             \n\
+             [all:vars]\n\
+             ansible_user=nobody\n\
+             ansible_password=secret\n",
+        );
+
+        let labels = batch
+            .profiles
+            .iter()
+            .map(|p| p.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                "bastion.example.com",
+                "web1",
+                "web2",
+                "ops@gw.example.com:2200",
+                "db1"
+            ]
+        );
+
+        let web1 = find(&batch, "web1");
+        assert_eq!(web1.host, "10.0.0.1");
+        assert_eq!(web1.user.as_deref(), Some("deploy"));
+        assert_eq!(web1.tags, ["web", "prod"]);
+        assert_eq!(web1.jumps, ["bastion.example.com"]);
+        assert_eq!(web1.options, [("ServerAliveInterval".into(), "15".into())]);
+
+        let bastion = find(&batch, "bastion.example.com");
+        assert_eq!(bastion.port, Some(2222));
+        assert_eq!(bastion.user.as_deref(), Some("jump"));
+        assert!(bastion.tags.is_empty());
+
+        let db1 = find(&batch, "db1");
+        assert_eq!(db1.port, Some(5432));
+        assert_eq!(db1.jumps, ["ops@gw.example.com:2200"]);
+
+        let warned = |needle: &str| batch.warnings.iter().any(|w| w.contains(needle));
+        assert!(warned(
+            "broken[3:1]: range [3:1] starts after it ends; skipped"
+        ));
+        assert!(warned("db1: inventory password not imported"));
+    }
+
+    #[test]
+    fn yaml_inventory_reports_vault_and_template_values() {
+        let batch = parse(
+            "hosts.yml",
+            "all:\n\
+            \x20 vars:\n\
+            \x20   ansible_user: deploy\n\
+            \x20 children:\n\
+            \x20   cloud:\n\
+            \x20     children:\n\
+            \x20       aws:\n\
+            \x20         hosts:\n\
+            \x20           api:\n\
+            \x20             ansible_host: 10.1.0.5\n\
+            \x20             ansible_port: 2200\n\
+            \x20             ansible_password: !vault |\n\
+            \x20               $ANSIBLE_VAULT;1.1;AES256\n\
+            \x20               6162\n\
+            \x20           worker:\n\
+            \x20             ansible_host: \"{{ lookup('env', 'WORKER') }}\"\n\
+            \x20   windows:\n\
+            \x20     hosts:\n\
+            \x20       dc1:\n\
+            \x20         ansible_connection: winrm\n",
+        );
+
+        let labels = batch
+            .profiles
+            .iter()
+            .map(|p| p.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["api", "worker"]);
+
+        let api = find(&batch, "api");
+        assert_eq!(api.host, "10.1.0.5");
+        assert_eq!(api.port, Some(2200));
+        assert_eq!(api.user.as_deref(), Some("deploy"));
+        assert_eq!(api.tags, ["aws", "cloud"]);
+        // 模板没法求值，退回到 inventory 主机名
+        assert_eq!(find(&batch, "worker").host, "worker");
+
+        let warned = |needle: &str| batch.warnings.iter().any(|w| w.contains(needle));
+        assert!(warned("api: ansible_password is encrypted"));
+        assert!(warned("worker: ansible_host is a template"));
+        assert!(warned("dc1: skipped: ansible_connection is winrm"));
+    }
+}
@@ -0,0 +1,164 @@
+//! MobaXterm 会话导出（`.mxtsessions`，INI）。
+//!
+//! 每个 `[Bookmarks*]` section 是一个文件夹（`SubRep`），其余每行 `名字=#类型#...` 是一个会话。
+//! SSH 会话（类型 109）的参数段用 `%` 分隔：1 主机、2 端口、3 用户、8/9/10 SSH 网关的
+//! 主机/端口/用户、14 私钥。网关翻译成跳板；其它类型的会话和 Windows 上的 key 路径只给提示。
+
+use crate::error::AppResult;
+use crate::infra::import::{
+    Ini, collect_files, folder_tag, is_windows_path, jump_host, push_session, read_text,
+};
+use crate::usecase::{ImportBatch, ImportedProfile};
+
+const SSH_SESSION: &str = "109";
+
+pub fn parse_mobaxterm(paths: &[String]) -> AppResult<ImportBatch> {
+    let mut batch = ImportBatch::default();
+
+    for file in collect_files(paths, &["mxtsessions"])? {
+        let ini = Ini::parse(&read_text(&file.path)?);
+        let source = file.display();
+
+        for (section, entries) in &ini.sections {
+            if !section.to_ascii_lowercase().starts_with("bookmarks") {
+                continue;
+            }
+            let folder = entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("SubRep"))
+                .and_then(|(_, v)| folder_tag(v));
+
+            for (name, value) in entries {
+                if name.eq_ignore_ascii_case("SubRep") || name.eq_ignore_ascii_case("ImgNum") {
+                    continue;
+                }
+                match session(name, value, &mut batch.warnings) {
+                    Ok((mut p, gateway)) => {
+                        p.tags.extend(folder.clone());
+                        push_session(&mut batch, &source, p, gateway);
+                    }
+                    Err(why) => batch
+                        .warnings
+                        .push(format!("{source}: {name}: skipped: {why}")),
+                }
+            }
+        }
+    }
+
+    Ok(batch)
+}
+
+/// 会话本身和它的 SSH 网关（如果有）；网关等会话被收下了才加进批次
+fn session(
+    name: &str,
+    value: &str,
+    warnings: &mut Vec<String>,
+) -> Result<(ImportedProfile, Option<ImportedProfile>), String> {
+    // ` #109#0%host%22%user%...#MobaFont%...#0# #-1`
+    let parts = value.trim().split('#').collect::<Vec<_>>();
+    let kind = parts.get(1).copied().unwrap_or_default();
+    if kind != SSH_SESSION {
+        return Err(format!("not an SSH session (type {kind})"));
+    }
+
+    let fields = parts
+        .get(2)
+        .map(|p| p.split('%').map(str::trim).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let field = |i: usize| fields.get(i).copied().filter(|v| !v.is_empty());
+    let port = |i: usize| match field(i) {
+        Some(raw) => raw
+            .parse::<u16>()
+            .map(Some)
+            .map_err(|_| format!("bad port {raw}")),
+        None => Ok(None),
+    };
+
+    let host = field(1).ok_or("no host")?;
+    let session_port = port(2)?;
+
+    let gateway = match field(8) {
+        Some(gateway) => Some(jump_host(field(10), gateway, port(9)?.unwrap_or(22))),
+        None => None,
+    };
+
+    let key_path = match field(14) {
+        Some(key) if is_windows_path(key) => {
+            warnings.push(format!(
+                "{name}: key {key} is a Windows path; copy it over and run \
+                 `jmssh profile set {name} --key <file>`"
+            ));
+            None
+        }
+        key => key.map(str::to_string),
+    };
+    if let Some(command) = field(7) {
+        warnings.push(format!("{name}: startup command {command:?} not imported"));
+    }
+
+    let p = ImportedProfile {
+        label: name.to_string(),
+        host: host.to_string(),
+        user: field(3).map(str::to_string),
+        port: session_port,
+        key_path,
+        jumps: gateway.iter().map(|g| g.label.clone()).collect(),
+        ..Default::default()
+    };
+    Ok((p, gateway))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const EXPORT: &str = "[Bookmarks]\r\n\
+        SubRep=\r\n\
+        ImgNum=42\r\n\
+        web= #109#0%web.example.com%2222%deploy%%-1%-1%%bastion.example.com%22%jump%0%0%0%/home/me/.ssh/id_web%%-1%0%0%0%%1080%%0%0%1#MobaFont%10%0%0%-1%15%236,236,236%30,30,30%180,180,255%0%-1%0%%xterm%-1%-1%_Std_Colors_0_%80%24%0%1%-1%<none>%%0%0%-1#0# #-1\r\n\
+        desktop= #91#4%rdp.example.com%3389%%-1%0%0%0%-1%0%0%-1#MobaFont%10#0# #-1\r\n\
+        [Bookmarks_1]\r\n\
+        SubRep=Prod\\DB\r\n\
+        ImgNum=41\r\n\
+        db= #109#0%db.example.com%%dba%%-1%-1%uptime%%%%0%0%0%_ProfileDir_\\keys\\db.pem%#MobaFont%10#0# #-1\r\n\
+        web= #109#0%other.example.com%22%root%%-1%-1%%gw2.example.com%22%%0%0%0%%#MobaFont%10#0# #-1\r\n\
+        broken= #109#0%bad.example.com%notaport%root%%-1%-1%%gw3.example.com%22%%0%0%0%%#MobaFont%10#0# #-1\r\n";
+
+    #[test]
+    fn ssh_bookmarks_become_profiles_with_gateways() {
+        let dir = TempDir::new("mobaxterm");
+        let path = dir.write("MobaXterm Sessions.mxtsessions", EXPORT);
+
+        let batch = parse_mobaxterm(&[path.display().to_string()]).unwrap();
+        let labels = batch
+            .profiles
+            .iter()
+            .map(|p| p.label.as_str())
+            .collect::<Vec<_>>();
+        // 重名和端口不对的会话被跳过，它们的网关（gw2 / gw3）也不留下
+        assert_eq!(labels, ["web", "jump@bastion.example.com:22", "db"]);
+
+        let web = &batch.profiles[0];
+        assert_eq!(web.host, "web.example.com");
+        assert_eq!(web.port, Some(2222));
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        assert_eq!(web.key_path.as_deref(), Some("/home/me/.ssh/id_web"));
+        assert_eq!(web.jumps, ["jump@bastion.example.com:22"]);
+        assert!(web.tags.is_empty());
+
+        let db = &batch.profiles[2];
+        assert_eq!(db.port, None);
+        assert_eq!(db.key_path, None);
+        assert_eq!(db.tags, ["prod/db"]);
+
+        let warned = |needle: &str| batch.warnings.iter().any(|w| w.contains(needle));
+        assert!(warned("desktop: skipped: not an SSH session (type 91)"));
+        assert!(warned(
+            "db: key _ProfileDir_\\keys\\db.pem is a Windows path"
+        ));
+        assert!(warned("db: startup command \"uptime\""));
+        assert!(warned("web: another session already uses this name"));
+        assert!(warned("broken: skipped: bad port notaport"));
+    }
+}
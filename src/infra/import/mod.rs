@@ -0,0 +1,227 @@
+//! 各种外部格式 -> `ImportBatch`，真正落库在 `usecase::import`
+pub mod ansible;
+pub mod mobaxterm;
+pub mod putty;
+pub mod securecrt;
+pub mod ssh_config;
+pub mod xshell;
+
+use crate::error::{AppError, AppResult};
+use crate::usecase::{ImportBatch, ImportedProfile};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 命令行给的一个会话文件；从目录里找到的带上相对这个目录的子目录（`prod/db`），用作标签
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub folder: Option<String>,
+}
+
+impl SourceFile {
+    /// 文件名去掉扩展名，Windows 客户端一般拿它当会话名
+    pub fn stem(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    pub fn display(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// 文件原样收下；目录递归找扩展名在 `exts` 里的文件（不区分大小写），按路径排序
+pub fn collect_files(paths: &[String], exts: &[&str]) -> AppResult<Vec<SourceFile>> {
+    let mut files = Vec::new();
+
+    for raw in paths {
+        let path = PathBuf::from(raw);
+        let meta = fs::metadata(&path).map_err(|e| AppError::IoError(format!("{raw}: {e}")))?;
+        if !meta.is_dir() {
+            files.push(SourceFile { path, folder: None });
+            continue;
+        }
+
+        let mut found = Vec::new();
+        walk(&path, exts, &mut found)?;
+        found.sort();
+        files.extend(found.into_iter().map(|p| {
+            let folder = p
+                .parent()
+                .and_then(|dir| dir.strip_prefix(&path).ok())
+                .map(|rel| {
+                    rel.components()
+                        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                        .collect::<Vec<_>>()
+                        .join("/")
+                })
+                .filter(|f| !f.is_empty());
+            SourceFile { path: p, folder }
+        }));
+    }
+
+    Ok(files)
+}
+
+fn walk(dir: &Path, exts: &[&str], found: &mut Vec<PathBuf>) -> AppResult<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, exts, found)?;
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| exts.iter().any(|x| x.eq_ignore_ascii_case(e)))
+        {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Windows 客户端导出的文件经常是 UTF-16（带或不带 BOM），其余按 UTF-8，坏字节替换掉
+pub fn read_text(path: &Path) -> AppResult<String> {
+    let bytes =
+        fs::read(path).map_err(|e| AppError::IoError(format!("{}: {e}", path.display())))?;
+
+    let utf16 = |body: &[u8], le: bool| {
+        let units = body
+            .chunks_exact(2)
+            .map(|c| match le {
+                true => u16::from_le_bytes([c[0], c[1]]),
+                false => u16::from_be_bytes([c[0], c[1]]),
+            })
+            .collect::<Vec<_>>();
+        String::from_utf16_lossy(&units)
+    };
+
+    let text = match bytes.as_slice() {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, true),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, false),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        // 没有 BOM 的 UTF-16LE：ASCII 字符的高字节全是 0
+        [_, 0, _, 0, ..] => utf16(&bytes, true),
+        _ => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+    Ok(text)
+}
+
+/// 简单的 INI：`[section]` + `key=value`，`;` / `#` 开头是注释；同一 section 可以出现多次
+#[derive(Debug, Default)]
+pub struct Ini {
+    pub sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Ini {
+    pub fn parse(text: &str) -> Self {
+        let mut ini = Ini::default();
+        let mut current = None;
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with([';', '#']) {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                ini.sections.push((name.trim().to_string(), Vec::new()));
+                current = Some(ini.sections.len() - 1);
+                continue;
+            }
+            if let (Some(idx), Some((k, v))) = (current, line.split_once('=')) {
+                ini.sections[idx]
+                    .1
+                    .push((k.trim().to_string(), v.trim().to_string()));
+            }
+        }
+
+        ini
+    }
+
+    /// section 和 key 都不区分大小写；空值当作没有
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(section))
+            .flat_map(|(_, entries)| entries)
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// 加进批次；同名的会话（比如不同文件夹下重名）只留第一个
+pub fn push_profile(batch: &mut ImportBatch, source: &str, p: ImportedProfile) {
+    if batch.profiles.iter().any(|e| e.label == p.label) {
+        batch.warnings.push(format!(
+            "{source}: {}: another session already uses this name; skipped",
+            p.label
+        ));
+        return;
+    }
+    batch.profiles.push(p);
+}
+
+/// 会话里直接写的跳板主机：以 `user@host:port` 为 label 单独建一个 profile
+pub fn jump_host(user: Option<&str>, host: &str, port: u16) -> ImportedProfile {
+    let label = match user {
+        Some(user) => format!("{user}@{host}:{port}"),
+        None => format!("{host}:{port}"),
+    };
+    ImportedProfile {
+        label,
+        host: host.to_string(),
+        user: user.map(str::to_string),
+        port: Some(port),
+        ..Default::default()
+    }
+}
+
+/// 会话收下之后再加它的跳板，免得会话被跳过时留下没人用的跳板
+pub fn push_session(
+    batch: &mut ImportBatch,
+    source: &str,
+    p: ImportedProfile,
+    jump: Option<ImportedProfile>,
+) {
+    let accepted = !batch.profiles.iter().any(|e| e.label == p.label);
+    push_profile(batch, source, p);
+    if let Some(jump) = jump.filter(|_| accepted) {
+        push_jump(batch, jump);
+    }
+}
+
+/// 加一个跳板 profile，同一批里已经有了就复用；返回它的 label
+pub fn push_jump(batch: &mut ImportBatch, jump: ImportedProfile) -> String {
+    let label = jump.label.clone();
+    if !batch.profiles.iter().any(|p| p.label == label) {
+        batch.profiles.push(jump);
+    }
+    label
+}
+
+/// 会话文件夹 -> 标签：`Prod Servers\DB` 变成 `prod-servers/db`，标签里不能用的字符都当分隔
+pub fn folder_tag(folder: &str) -> Option<String> {
+    let tag = folder
+        .split(['/', '\\'])
+        .map(|part| {
+            part.split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                .filter(|w| !w.is_empty())
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+        .to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// 看起来是 Windows 上的路径（`C:\...`、`\\server\...`、MobaXterm 的 `_ProfileDir_\...`）
+pub fn is_windows_path(path: &str) -> bool {
+    let b = path.as_bytes();
+    (b.len() > 2 && b[0].is_ascii_alphabetic() && b[1] == b':')
+        || path.starts_with("\\\\")
+        || path.starts_with('_')
+}
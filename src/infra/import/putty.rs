@@ -0,0 +1,318 @@
+//! PuTTY 会话的注册表导出（`reg export HKCU\Software\SimonTatham\PuTTY\Sessions`）。
+//!
+//! 每个 `...\PuTTY\Sessions\<名字>` 键是一个会话，名字是 URL 编码的（空格是 `%20`）。
+//! 代理类型是 SSH（ProxyMethod 6，PuTTY 0.77 起）时翻译成跳板，其余代理、`.ppk` key、
+//! 端口转发和远程命令只给提示。
+
+use crate::error::AppResult;
+use crate::infra::import::{collect_files, is_windows_path, jump_host, push_session, read_text};
+use crate::usecase::{ImportBatch, ImportedProfile};
+use std::collections::HashMap;
+
+const SESSIONS_KEY: &str = "\\software\\simontatham\\putty\\sessions\\";
+const PROXY_SSH: u32 = 6;
+
+/// 注册表值：只用到字符串和 dword 两种
+#[derive(Debug, Clone)]
+enum RegValue {
+    Str(String),
+    Dword(u32),
+}
+
+type Values = HashMap<String, RegValue>;
+
+pub fn parse_putty(paths: &[String]) -> AppResult<ImportBatch> {
+    let mut batch = ImportBatch::default();
+
+    for file in collect_files(paths, &["reg"])? {
+        let source = file.display();
+        for (name, values) in sessions(&read_text(&file.path)?) {
+            match session(&name, &values, &mut batch.warnings) {
+                Ok((p, proxy)) => push_session(&mut batch, &source, p, proxy),
+                // 模板会话没填主机是常态，不算问题
+                Err(_) if name == "Default Settings" => {}
+                Err(why) => batch
+                    .warnings
+                    .push(format!("{source}: {name}: skipped: {why}")),
+            }
+        }
+    }
+
+    Ok(batch)
+}
+
+/// 按文件里的顺序返回 (会话名, 值)；不在 Sessions 下的键（比如 SshHostKeys）忽略
+fn sessions(text: &str) -> Vec<(String, Values)> {
+    let mut out: Vec<(String, Values)> = Vec::new();
+    let mut current = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(key) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let lower = key.to_ascii_lowercase();
+            current = match lower.find(SESSIONS_KEY) {
+                Some(at) => {
+                    let name = &key[at + SESSIONS_KEY.len()..];
+                    out.push((percent_decode(name), Values::new()));
+                    true
+                }
+                None => false,
+            };
+            continue;
+        }
+        if !current {
+            continue;
+        }
+        let (Some((_, values)), Some((name, value))) = (out.last_mut(), reg_value(line)) else {
+            continue;
+        };
+        values.insert(name.to_ascii_lowercase(), value);
+    }
+
+    out
+}
+
+/// 会话本身和它的 SSH 代理（如果有）；代理等会话被收下了才加进批次
+fn session(
+    name: &str,
+    values: &Values,
+    warnings: &mut Vec<String>,
+) -> Result<(ImportedProfile, Option<ImportedProfile>), String> {
+    let text = |key: &str| match values.get(key) {
+        Some(RegValue::Str(s)) if !s.is_empty() => Some(s.as_str()),
+        _ => None,
+    };
+    let number = |key: &str| match values.get(key) {
+        Some(RegValue::Dword(n)) => Some(*n),
+        _ => None,
+    };
+    let port = |key: &str| match number(key) {
+        Some(n) => u16::try_from(n)
+            .map(Some)
+            .map_err(|_| format!("bad port {n}")),
+        None => Ok(None),
+    };
+
+    let protocol = text("protocol").unwrap_or("ssh");
+    if !protocol.eq_ignore_ascii_case("ssh") {
+        return Err(format!("{protocol} session, only SSH is supported"));
+    }
+
+    // HostName 里可以直接写 user@host
+    let raw_host = text("hostname").ok_or("no host")?;
+    let (host_user, host) = match raw_host.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, raw_host),
+    };
+    let user = text("username").or(host_user);
+    let session_port = port("portnumber")?;
+
+    let proxy = match (number("proxymethod").unwrap_or(0), text("proxyhost")) {
+        (0, _) => None,
+        (PROXY_SSH, Some(proxy)) => {
+            let proxy_port = port("proxyport")?.unwrap_or(22);
+            Some(jump_host(text("proxyusername"), proxy, proxy_port))
+        }
+        (method, proxy) => {
+            warnings.push(format!(
+                "{name}: {} proxy {} not imported",
+                proxy_kind(method),
+                proxy.unwrap_or("-")
+            ));
+            None
+        }
+    };
+
+    let key_path = match text("publickeyfile") {
+        Some(key) if key.to_ascii_lowercase().ends_with(".ppk") || is_windows_path(key) => {
+            warnings.push(format!(
+                "{name}: key {key} is a PuTTY/Windows key; convert it with \
+                 `puttygen <file> -O private-openssh -o <out>` and run \
+                 `jmssh profile set {name} --key <out>`"
+            ));
+            None
+        }
+        key => key.map(str::to_string),
+    };
+    if text("portforwardings").is_some() {
+        warnings.push(format!("{name}: port forwardings not imported"));
+    }
+    if let Some(command) = text("remotecommand") {
+        warnings.push(format!("{name}: remote command {command:?} not imported"));
+    }
+
+    let p = ImportedProfile {
+        label: name.to_string(),
+        host: host.to_string(),
+        user: user.map(str::to_string),
+        port: session_port,
+        key_path,
+        jumps: proxy.iter().map(|j| j.label.clone()).collect(),
+        ..Default::default()
+    };
+    Ok((p, proxy))
+}
+
+fn proxy_kind(method: u32) -> &'static str {
+    match method {
+        1 => "SOCKS 4",
+        2 => "SOCKS 5",
+        3 => "HTTP",
+        4 => "Telnet",
+        5 => "local command",
+        PROXY_SSH => "SSH",
+        _ => "unknown",
+    }
+}
+
+/// `"Name"="va\"lue"` / `"Name"=dword:00000016`；其它类型（hex:、hex(7):）用不到
+fn reg_value(line: &str) -> Option<(String, RegValue)> {
+    let (name, rest) = reg_string(line)?;
+    let rest = rest.strip_prefix('=')?;
+
+    let value = if rest.starts_with('"') {
+        RegValue::Str(reg_string(rest)?.0)
+    } else if let Some(hex) = rest.strip_prefix("dword:") {
+        RegValue::Dword(u32::from_str_radix(hex.trim(), 16).ok()?)
+    } else {
+        return None;
+    };
+    Some((name, value))
+}
+
+/// 开头的带引号字符串（`\\` 和 `\"` 转义），返回内容和剩下的部分
+fn reg_string(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut out = String::new();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => out.push(chars.next()?.1),
+            '"' => return Some((out, &s[i + 2..])),
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+/// PuTTY 存会话名时把空格、`%` 和几个特殊字符编码成 `%XX`
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempDir, utf16le};
+
+    const EXPORT: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Default%20Settings]
+"HostName"=""
+"PortNumber"=dword:00000016
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\prod%20web]
+"HostName"="admin@web.example.com"
+"PortNumber"=dword:00000016
+"Protocol"="ssh"
+"ProxyMethod"=dword:00000006
+"ProxyHost"="bastion.example.com"
+"ProxyPort"=dword:000008ae
+"ProxyUsername"="jump"
+"PublicKeyFile"="C:\\Users\\me\\.ssh\\web.ppk"
+"RemoteCommand"="echo \"hi\""
+"TerminalSpeed"=hex:33,38,34
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\SshHostKeys]
+"ssh-ed25519@22:web.example.com"="0x1234"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\switch]
+"HostName"="10.0.0.1"
+"Protocol"="telnet"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\socks]
+"HostName"="db.example.com"
+"UserName"="dba"
+"ProxyMethod"=dword:00000002
+"ProxyHost"="proxy.example.com"
+"#;
+
+    #[test]
+    fn reg_values_unescape_strings_and_read_dwords() {
+        let (name, value) = reg_value(r#""Name"="C:\\dir \"x\"""#).unwrap();
+        assert_eq!(name, "Name");
+        assert!(matches!(value, RegValue::Str(s) if s == r#"C:\dir "x""#));
+        assert!(matches!(
+            reg_value(r#""Port"=dword:000008ae"#),
+            Some((_, RegValue::Dword(2222)))
+        ));
+        assert!(reg_value(r#""Blob"=hex:00,01"#).is_none());
+        assert!(reg_value(r#""Broken"="no end"#).is_none());
+    }
+
+    #[test]
+    fn session_names_are_percent_decoded() {
+        assert_eq!(percent_decode("prod%20web"), "prod web");
+        assert_eq!(percent_decode("100%25%2F"), "100%/");
+        assert_eq!(percent_decode("bad%zz%2"), "bad%zz%2");
+    }
+
+    #[test]
+    fn registry_export_becomes_profiles_and_jumps() {
+        let dir = TempDir::new("putty");
+        let path = dir.write("putty.reg", utf16le(&EXPORT.replace('\n', "\r\n")));
+
+        let batch = parse_putty(&[path.display().to_string()]).unwrap();
+        let labels = batch
+            .profiles
+            .iter()
+            .map(|p| p.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            ["prod web", "jump@bastion.example.com:2222", "socks"]
+        );
+
+        let web = &batch.profiles[0];
+        assert_eq!(web.host, "web.example.com");
+        assert_eq!(web.user.as_deref(), Some("admin"));
+        assert_eq!(web.port, Some(22));
+        assert_eq!(web.jumps, ["jump@bastion.example.com:2222"]);
+        assert_eq!(web.key_path, None);
+
+        let jump = &batch.profiles[1];
+        assert_eq!(jump.host, "bastion.example.com");
+        assert_eq!(jump.user.as_deref(), Some("jump"));
+        assert_eq!(jump.port, Some(2222));
+
+        assert!(batch.profiles[2].jumps.is_empty());
+
+        let warned = |needle: &str| batch.warnings.iter().any(|w| w.contains(needle));
+        assert!(warned("prod web: key C:\\Users\\me\\.ssh\\web.ppk"));
+        assert!(warned(r#"remote command "echo \"hi\"""#));
+        assert!(warned("switch: skipped: telnet session"));
+        assert!(warned("socks: SOCKS 5 proxy proxy.example.com"));
+        assert!(!warned("Default Settings"));
+    }
+}
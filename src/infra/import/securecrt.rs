@@ -0,0 +1,423 @@
+//! SecureCRT 会话：`Sessions` 目录下每个会话一个 `.ini`，或者"导出设置"得到的 XML。
+//!
+//! 两种格式里的字段一样（`S:"Hostname"=...` / `<string name="Hostname">...`），
+//! 先统一成 字段名 -> 值 再翻译。防火墙设成另一个会话（`Session:<路径>`）时翻译成跳板；
+//! 其它防火墙、加密保存的密码、Windows 上的 key 路径只给提示。
+
+use crate::error::AppResult;
+use crate::infra::import::{collect_files, folder_tag, is_windows_path, push_profile, read_text};
+use crate::usecase::{ImportBatch, ImportedProfile};
+use std::collections::{HashMap, HashSet};
+
+/// 字段名统一小写
+type Fields = HashMap<String, Value>;
+
+#[derive(Debug, Clone)]
+enum Value {
+    Str(String),
+    Dword(u32),
+}
+
+/// 目录里的这两个文件是文件夹属性和默认会话模板，不是会话
+const NOT_SESSIONS: [&str; 2] = ["__FolderData__", "Default"];
+
+pub fn parse_securecrt(paths: &[String]) -> AppResult<ImportBatch> {
+    let mut batch = ImportBatch::default();
+
+    for file in collect_files(paths, &["ini", "xml"])? {
+        let source = file.display();
+        let text = read_text(&file.path)?;
+
+        let sessions = if text.trim_start().starts_with('<') {
+            xml_sessions(&text)
+        } else {
+            let label = file.stem();
+            if NOT_SESSIONS.contains(&label.as_str()) {
+                continue;
+            }
+            vec![(label, file.folder.clone(), ini_fields(&text))]
+        };
+
+        for (label, folder, fields) in sessions {
+            match session(&label, folder.as_deref(), &fields, &mut batch.warnings) {
+                Ok(p) => push_profile(&mut batch, &source, p),
+                Err(why) => batch
+                    .warnings
+                    .push(format!("{source}: {label}: skipped: {why}")),
+            }
+        }
+    }
+
+    // 跳板会话不在这次导入的范围里（比如只给了一个文件）就不连
+    let labels = batch
+        .profiles
+        .iter()
+        .map(|p| p.label.clone())
+        .collect::<HashSet<_>>();
+    for p in &mut batch.profiles {
+        if let Some(missing) = p.jumps.iter().find(|j| !labels.contains(*j)) {
+            batch.warnings.push(format!(
+                "{}: jump session {missing} is not part of this import; jump not imported",
+                p.label
+            ));
+            p.jumps.clear();
+        }
+    }
+
+    Ok(batch)
+}
+
+fn session(
+    label: &str,
+    folder: Option<&str>,
+    fields: &Fields,
+    warnings: &mut Vec<String>,
+) -> Result<ImportedProfile, String> {
+    let text = |key: &str| match fields.get(key) {
+        Some(Value::Str(s)) if !s.is_empty() => Some(s.as_str()),
+        _ => None,
+    };
+
+    let protocol = text("protocol name").unwrap_or("SSH2");
+    if !protocol.to_ascii_uppercase().starts_with("SSH") {
+        return Err(format!("{protocol} session, only SSH is supported"));
+    }
+    let host = text("hostname").ok_or("no host")?;
+    let port = match fields.get("[ssh2] port") {
+        Some(Value::Dword(n)) => Some(u16::try_from(*n).map_err(|_| format!("bad port {n}"))?),
+        _ => None,
+    };
+
+    // 防火墙是另一个会话：`Session:Folder/bastion`，按会话名（最后一段）连
+    let mut jumps = Vec::new();
+    match text("firewall name") {
+        None | Some("None") => {}
+        Some(fw) => match fw.strip_prefix("Session:") {
+            Some(path) => jumps.extend(path.rsplit(['/', '\\']).next().map(str::to_string)),
+            None => warnings.push(format!("{label}: firewall {fw} not imported")),
+        },
+    }
+
+    // `路径::rawkey` 之类的后缀是 SecureCRT 自己的标记
+    let key_path = match text("identity filename v2").map(|k| k.split("::").next().unwrap_or(k)) {
+        Some(key) if is_windows_path(key) => {
+            warnings.push(format!(
+                "{label}: key {key} is a Windows path; copy it over and run \
+                 `jmssh profile set {label} --key <file>`"
+            ));
+            None
+        }
+        key => key.filter(|k| !k.is_empty()).map(str::to_string),
+    };
+    if text("password v2").or(text("password")).is_some() {
+        warnings.push(format!(
+            "{label}: saved password is encrypted by SecureCRT and was not imported"
+        ));
+    }
+
+    Ok(ImportedProfile {
+        label: label.to_string(),
+        host: host.to_string(),
+        user: text("username").map(str::to_string),
+        port,
+        key_path,
+        jumps,
+        tags: folder.and_then(folder_tag).into_iter().collect(),
+        ..Default::default()
+    })
+}
+
+/// `S:"Hostname"=web` / `D:"[SSH2] Port"=00000016`（十六进制）；
+/// `B:` / `Z:` 是多行的二进制和列表，用不到，连同它们的续行一起跳过
+fn ini_fields(text: &str) -> Fields {
+    let mut fields = Fields::new();
+
+    for line in text.lines() {
+        let Some((kind, rest)) = line.split_once(":\"") else {
+            continue;
+        };
+        let Some((name, value)) = rest.split_once("\"=") else {
+            continue;
+        };
+        let value = match kind {
+            "S" => Value::Str(value.trim_end_matches(['\r', '\n']).to_string()),
+            "D" => match u32::from_str_radix(value.trim(), 16) {
+                Ok(n) => Value::Dword(n),
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        fields.insert(name.to_ascii_lowercase(), value);
+    }
+
+    fields
+}
+
+/// XML 导出里 `<key name="Sessions">` 下的会话：(会话名, 文件夹, 字段)。
+/// 只认 key / string / dword 三种元素，够用就行，不是完整的 XML 解析器
+fn xml_sessions(text: &str) -> Vec<(String, Option<String>, Fields)> {
+    let mut out = Vec::new();
+    // 打开着的 <key>：(name, 字段)
+    let mut keys: Vec<(String, Fields)> = Vec::new();
+    // 正在读的 <string> / <dword>：(元素名, name 属性, 内容)
+    let mut leaf: Option<(String, String, String)> = None;
+
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        if let Some((_, _, content)) = &mut leaf {
+            content.push_str(&unescape(&rest[..start]));
+        }
+        rest = &rest[start..];
+
+        // 注释、声明、CDATA 整块跳过（CDATA 当文本）
+        let skip = [
+            ("<!--", "-->"),
+            ("<?", "?>"),
+            ("<![CDATA[", "]]>"),
+            ("<!", ">"),
+        ]
+        .into_iter()
+        .find(|(open, _)| rest.starts_with(open));
+        if let Some((open, close)) = skip {
+            let Some(end) = rest.find(close) else { break };
+            if let (Some((_, _, content)), "<![CDATA[") = (&mut leaf, open) {
+                content.push_str(&rest[open.len()..end]);
+            }
+            rest = &rest[end + close.len()..];
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else { break };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if let Some(elem) = tag.strip_prefix('/') {
+            match elem.trim() {
+                "key" => {
+                    let Some((name, fields)) = keys.pop() else {
+                        continue;
+                    };
+                    let in_sessions = keys.first().is_some_and(|(k, _)| k == "Sessions");
+                    if in_sessions && fields.contains_key("hostname") {
+                        let folder = keys[1..]
+                            .iter()
+                            .map(|(k, _)| k.as_str())
+                            .collect::<Vec<_>>();
+                        let folder = (!folder.is_empty()).then(|| folder.join("/"));
+                        out.push((name, folder, fields));
+                    }
+                }
+                "string" | "dword" => {
+                    let (Some((kind, name, content)), Some((_, fields))) =
+                        (leaf.take(), keys.last_mut())
+                    else {
+                        continue;
+                    };
+                    let value = match kind.as_str() {
+                        "dword" => match content.trim().parse() {
+                            Ok(n) => Value::Dword(n),
+                            Err(_) => continue,
+                        },
+                        _ => Value::Str(content),
+                    };
+                    fields.insert(name.to_ascii_lowercase(), value);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let elem = tag.split_whitespace().next().unwrap_or_default();
+        let Some(name) = attr(tag, "name") else {
+            continue;
+        };
+        match (elem, self_closing) {
+            ("key", false) => keys.push((name, Fields::new())),
+            ("string", true) => {
+                if let Some((_, fields)) = keys.last_mut() {
+                    fields.insert(name.to_ascii_lowercase(), Value::Str(String::new()));
+                }
+            }
+            ("string" | "dword", false) => leaf = Some((elem.to_string(), name, String::new())),
+            _ => {}
+        }
+    }
+
+    out
+}
+
+/// `name="..."` 或 `name='...'`
+fn attr(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(at) = rest.find(name) {
+        let standalone = rest[..at].ends_with(char::is_whitespace);
+        rest = &rest[at + name.len()..];
+        let Some(value) = rest.trim_start().strip_prefix('=').filter(|_| standalone) else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|q| matches!(q, '"' | '\''))?;
+        return value[1..]
+            .find(quote)
+            .map(|end| unescape(&value[1..1 + end]));
+    }
+    None
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(at) = rest.find('&') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let Some(end) = rest.find(';') else { break };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|h| u32::from_str_radix(h, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<VanDyke version="3.0">
+  <key name="Sessions">
+    <key name="Default">
+      <string name="Username">template</string>
+    </key>
+    <key name="Lab &amp; Test">
+      <key name="db">
+        <string name="Hostname">db.example.com</string>
+        <string name='Username'>o&apos;brien</string>
+        <dword name="[SSH2] Port">2222</dword>
+        <string name="Password V2"/>
+        <!-- <string name="Hostname">commented.example.com</string> -->
+        <string name="Firewall Name"><![CDATA[Session:Lab & Test/bastion]]></string>
+      </key>
+      <key name="bastion">
+        <string name="Hostname">gw.example.com</string>
+      </key>
+    </key>
+  </key>
+</VanDyke>
+"#;
+
+    fn str_field<'a>(fields: &'a Fields, key: &str) -> &'a str {
+        match fields.get(key) {
+            Some(Value::Str(s)) => s,
+            other => panic!("{key}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unescape_handles_named_and_numeric_entities() {
+        assert_eq!(unescape("a &lt;b&gt; &amp;amp;"), "a <b> &amp;");
+        assert_eq!(unescape("&#65;&#x42;&quot;&apos;"), "AB\"'");
+        assert_eq!(unescape("&bogus; & tail"), "&bogus; & tail");
+    }
+
+    #[test]
+    fn xml_export_yields_sessions_with_folders() {
+        let sessions = xml_sessions(XML);
+        let names = sessions
+            .iter()
+            .map(|(name, folder, _)| (name.as_str(), folder.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [("db", Some("Lab & Test")), ("bastion", Some("Lab & Test"))]
+        );
+
+        let db = &sessions[0].2;
+        assert_eq!(str_field(db, "hostname"), "db.example.com");
+        assert_eq!(str_field(db, "username"), "o'brien");
+        assert_eq!(str_field(db, "password v2"), "");
+        assert_eq!(str_field(db, "firewall name"), "Session:Lab & Test/bastion");
+        assert!(matches!(db.get("[ssh2] port"), Some(Value::Dword(2222))));
+    }
+
+    #[test]
+    fn ini_sessions_and_xml_exports_import_the_same_way() {
+        let dir = TempDir::new("securecrt");
+        dir.write(
+            "Sessions/Prod/web.ini",
+            "S:\"Protocol Name\"=SSH2\r\n\
+             S:\"Hostname\"=web.example.com\r\n\
+             S:\"Username\"=deploy\r\n\
+             D:\"[SSH2] Port\"=000008ae\r\n\
+             S:\"Firewall Name\"=Session:Prod/bastion\r\n\
+             S:\"Identity Filename V2\"=/home/me/.ssh/id_web::rawkey\r\n\
+             B:\"Keyword Set\"=00000002\r\n 01 02\r\n\
+             Z:\"Port Forward Filter\"=00000001\r\n allow,tcp\r\n",
+        );
+        dir.write(
+            "Sessions/Prod/bastion.ini",
+            "S:\"Hostname\"=gw.example.com\r\n",
+        );
+        dir.write(
+            "Sessions/Prod/__FolderData__.ini",
+            "S:\"Description\"=x\r\n",
+        );
+        dir.write(
+            "Sessions/switch.ini",
+            "S:\"Protocol Name\"=Telnet\r\nS:\"Hostname\"=10.0.0.1\r\n",
+        );
+        let xml = dir.write("export.xml", XML);
+
+        let paths = [dir.join("Sessions"), xml].map(|p| p.display().to_string());
+        let batch = parse_securecrt(&paths).unwrap();
+        let labels = batch
+            .profiles
+            .iter()
+            .map(|p| p.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["bastion", "web", "db"]);
+
+        let web = &batch.profiles[1];
+        assert_eq!(web.host, "web.example.com");
+        assert_eq!(web.port, Some(2222));
+        assert_eq!(web.jumps, ["bastion"]);
+        assert_eq!(web.key_path.as_deref(), Some("/home/me/.ssh/id_web"));
+        assert_eq!(web.tags, ["prod"]);
+
+        let db = &batch.profiles[2];
+        assert_eq!(db.user.as_deref(), Some("o'brien"));
+        assert_eq!(db.jumps, ["bastion"]);
+        assert_eq!(db.tags, ["lab-test"]);
+
+        let warned = |needle: &str| batch.warnings.iter().any(|w| w.contains(needle));
+        assert!(warned("switch: skipped: Telnet session"));
+        // XML 里的 bastion 和 ini 里的重名，只留第一个
+        assert!(warned("bastion: another session already uses this name"));
+    }
+}
@@ -0,0 +1,700 @@
+//! 解析 OpenSSH 客户端配置（默认 `~/.ssh/config`），翻译成待导入的 profile。
+//!
+//! 每个不带通配符的 Host 别名是一个 profile。字段按 ssh 自己的规则求值：
+//! 从上到下所有匹配这个别名的块（包括 `Host *.prod` 这类通配块）里，每个参数第一次出现的值生效。
+//! `Match` 块没法静态求值，整块跳过并给出提示。
+
+use crate::error::{AppError, AppResult};
+use crate::usecase::connect::{local_username, parse_destination};
+use crate::usecase::{ImportBatch, ImportedProfile};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// 和 OpenSSH 的 READCONF_MAX_DEPTH 一致
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// 直接对应 profile 字段的关键字（小写），其余的都当作这个 profile 的 ssh 选项
+const PROFILE_KEYWORDS: &[&str] = &["hostname", "user", "port", "identityfile", "proxyjump"];
+
+/// ssh 会把多次出现的值累加起来的关键字；profile 的每个选项只能存一个值
+const MULTI_VALUE_KEYWORDS: &[&str] = &[
+    "localforward",
+    "remoteforward",
+    "dynamicforward",
+    "sendenv",
+    "setenv",
+    "certificatefile",
+];
+
+#[derive(Debug)]
+struct Block {
+    /// None 表示 Match 块，不参与求值
+    patterns: Option<Vec<String>>,
+    entries: Vec<Entry>,
+}
+
+impl Block {
+    /// `Host *` 和第一个 Host 之前的部分：对所有主机生效
+    fn is_global(&self) -> bool {
+        self.patterns
+            .as_ref()
+            .is_some_and(|p| p.iter().all(|p| p == "*"))
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// 原始写法，作为 ssh 选项名时保留大小写
+    name: String,
+    args: Vec<String>,
+}
+
+/// 某个主机名最终生效的配置
+#[derive(Debug, Default)]
+struct Resolved {
+    /// 小写关键字 -> 第一次出现的参数
+    fields: HashMap<String, Vec<String>>,
+    identity_files: Vec<String>,
+    /// `Host *` 里的 IdentityFile 只在没有更具体的时候才用
+    global_identity_files: Vec<String>,
+    options: Vec<(String, String)>,
+    /// 只保留了第一个值的累加型选项
+    dropped: Vec<String>,
+}
+
+struct Parser {
+    home: PathBuf,
+    ssh_dir: PathBuf,
+    blocks: Vec<Block>,
+    /// 按出现顺序的 Host 别名
+    aliases: Vec<String>,
+    warnings: Vec<String>,
+    /// 正在读的文件（防 Include 成环）
+    reading: Vec<PathBuf>,
+}
+
+/// `path` 为 None 时读 `~/.ssh/config`；相对路径的 Include 和 ssh 一样相对 `~/.ssh`
+pub fn parse_ssh_config(path: Option<&str>) -> AppResult<ImportBatch> {
+    let home = directories::BaseDirs::new()
+        .map(|b| b.home_dir().to_path_buf())
+        .ok_or_else(|| AppError::IoError("cannot resolve home dir".to_string()))?;
+    parse_with_home(path, home)
+}
+
+fn parse_with_home(path: Option<&str>, home: PathBuf) -> AppResult<ImportBatch> {
+    let ssh_dir = home.join(".ssh");
+    let path = match path {
+        Some(p) => expand_tilde(p, &home),
+        None => ssh_dir.join("config"),
+    };
+
+    let mut parser = Parser {
+        home,
+        ssh_dir,
+        blocks: Vec::new(),
+        aliases: Vec::new(),
+        warnings: Vec::new(),
+        reading: Vec::new(),
+    };
+    parser.read_file(&path, 0, Some(vec!["*".to_string()]))?;
+
+    let mut batch = ImportBatch::default();
+    let mut skipped = HashSet::new();
+    for alias in parser.aliases.clone() {
+        match parser.profile_for(&alias, &mut batch.profiles) {
+            Ok(p) => batch.profiles.push(p),
+            Err(why) => {
+                parser.warnings.push(format!("{alias}: skipped: {why}"));
+                skipped.insert(alias);
+            }
+        }
+    }
+    drop_jumps_through(&mut batch.profiles, skipped, &mut parser.warnings);
+
+    batch.warnings = parser.warnings;
+    Ok(batch)
+}
+
+/// 经过被跳过的别名跳转的 profile 也跳过（直连会连到别的地方去），直到不再变化
+fn drop_jumps_through(
+    profiles: &mut Vec<ImportedProfile>,
+    mut skipped: HashSet<String>,
+    warnings: &mut Vec<String>,
+) {
+    loop {
+        let before = skipped.len();
+        profiles.retain(|p| match p.jumps.iter().find(|j| skipped.contains(*j)) {
+            Some(via) => {
+                warnings.push(format!(
+                    "{}: skipped: jumps through {via}, which was skipped",
+                    p.label
+                ));
+                skipped.insert(p.label.clone());
+                false
+            }
+            None => true,
+        });
+        if skipped.len() == before {
+            break;
+        }
+    }
+}
+
+impl Parser {
+    /// `patterns` 是文件开头（第一个 Host 之前）那部分所在的块：Host 里的 Include 只对这个 Host 生效
+    fn read_file(
+        &mut self,
+        path: &Path,
+        depth: usize,
+        patterns: Option<Vec<String>>,
+    ) -> AppResult<()> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| AppError::IoError(format!("{}: {e}", path.display())))?;
+        self.reading.push(path.to_path_buf());
+        self.blocks.push(Block {
+            patterns,
+            entries: Vec::new(),
+        });
+
+        for (idx, line) in text.lines().enumerate() {
+            let Some((name, args)) = split_line(line) else {
+                continue;
+            };
+            let at = format!("{}:{}", path.display(), idx + 1);
+
+            match name.to_ascii_lowercase().as_str() {
+                "host" => {
+                    if args.is_empty() {
+                        self.warnings.push(format!("{at}: Host without a pattern"));
+                    }
+                    for a in &args {
+                        if !is_pattern(a) && !self.aliases.contains(a) {
+                            self.aliases.push(a.clone());
+                        }
+                    }
+                    self.blocks.push(Block {
+                        patterns: Some(args),
+                        entries: Vec::new(),
+                    });
+                }
+                "match" => {
+                    self.warnings.push(format!(
+                        "{at}: Match blocks are not supported; block skipped"
+                    ));
+                    self.blocks.push(Block {
+                        patterns: None,
+                        entries: Vec::new(),
+                    });
+                }
+                "include" => {
+                    let current = self.blocks.last().and_then(|b| b.patterns.clone());
+                    for arg in &args {
+                        self.include(arg, &at, depth, &current)?;
+                    }
+                    // 被 Include 的文件里可能换了 Host，回来以后接着算外层的块
+                    self.blocks.push(Block {
+                        patterns: current,
+                        entries: Vec::new(),
+                    });
+                }
+                _ if args.is_empty() => {
+                    self.warnings.push(format!("{at}: {name} has no value"));
+                }
+                _ => {
+                    if let Some(block) = self.blocks.last_mut() {
+                        block.entries.push(Entry { name, args });
+                    }
+                }
+            }
+        }
+
+        self.reading.pop();
+        Ok(())
+    }
+
+    fn include(
+        &mut self,
+        raw: &str,
+        at: &str,
+        depth: usize,
+        patterns: &Option<Vec<String>>,
+    ) -> AppResult<()> {
+        let path = expand_tilde(raw, &self.home);
+        let path = if path.is_absolute() {
+            path
+        } else {
+            self.ssh_dir.join(path)
+        };
+
+        let files = if has_glob(&path.to_string_lossy()) {
+            glob_files(&path)
+        } else if path.is_file() {
+            vec![path]
+        } else {
+            self.warnings
+                .push(format!("{at}: Include {raw}: no such file"));
+            Vec::new()
+        };
+
+        for file in files {
+            if depth + 1 > MAX_INCLUDE_DEPTH {
+                self.warnings
+                    .push(format!("{at}: Include {raw}: nested too deeply; ignored"));
+            } else if self.reading.contains(&file) {
+                self.warnings.push(format!(
+                    "{at}: Include {}: includes itself; ignored",
+                    file.display()
+                ));
+            } else {
+                self.read_file(&file, depth + 1, patterns.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 按 ssh 的规则求出 `host` 生效的配置。全局块里的 ssh 选项不导入：
+    /// jmssh 连接时 ssh 照样会读 `~/.ssh/config`，没必要把它们复制到每个 profile 上
+    fn resolve(&self, host: &str) -> Resolved {
+        let mut r = Resolved::default();
+
+        for block in &self.blocks {
+            let Some(patterns) = &block.patterns else {
+                continue;
+            };
+            if !host_matches(patterns, host) {
+                continue;
+            }
+
+            for e in &block.entries {
+                let key = e.name.to_ascii_lowercase();
+                if key == "identityfile" && block.is_global() {
+                    r.global_identity_files.extend(e.args.iter().cloned());
+                } else if key == "identityfile" {
+                    r.identity_files.extend(e.args.iter().cloned());
+                } else if PROFILE_KEYWORDS.contains(&key.as_str()) {
+                    r.fields.entry(key).or_insert_with(|| e.args.clone());
+                } else if block.is_global() {
+                    continue;
+                } else if r.options.iter().any(|(k, _)| k.eq_ignore_ascii_case(&key)) {
+                    if MULTI_VALUE_KEYWORDS.contains(&key.as_str()) && !r.dropped.contains(&e.name)
+                    {
+                        r.dropped.push(e.name.clone());
+                    }
+                } else {
+                    r.options.push((e.name.clone(), e.args.join(" ")));
+                }
+            }
+        }
+
+        r
+    }
+
+    /// 一个 Host 别名 -> profile；ProxyJump 里不是别名的原始地址会单独建成跳板 profile 追加到 `extra`
+    fn profile_for(
+        &mut self,
+        alias: &str,
+        extra: &mut Vec<ImportedProfile>,
+    ) -> Result<ImportedProfile, String> {
+        let r = self.resolve(alias);
+        let mut p = self.base_profile(alias, alias, &r);
+
+        for name in &r.dropped {
+            self.warnings
+                .push(format!("{alias}: only the first {name} is imported"));
+        }
+        p.options = r.options;
+
+        let jump_spec = r
+            .fields
+            .get("proxyjump")
+            .and_then(|a| a.first())
+            .filter(|s| !s.eq_ignore_ascii_case("none"));
+        // 有一跳不合法就整个跳过，前面几跳建出来的跳板 profile 也不要
+        let mut new_jumps = Vec::new();
+        if let Some(spec) = jump_spec {
+            for hop in spec.split(',').map(str::trim).filter(|h| !h.is_empty()) {
+                let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+                let known = self.aliases.iter().any(|a| a == hop)
+                    || extra.iter().chain(&new_jumps).any(|e| e.label == hop);
+                if !known {
+                    new_jumps.push(self.jump_profile(hop)?);
+                }
+                p.jumps.push(hop.to_string());
+            }
+        }
+        extra.append(&mut new_jumps);
+
+        Ok(p)
+    }
+
+    /// ProxyJump 里直接写的 `[user@]host[:port]`：以原文作为 label，主机名按配置再求值一次
+    fn jump_profile(&mut self, spec: &str) -> Result<ImportedProfile, String> {
+        let dest = parse_destination(spec).map_err(|e| format!("ProxyJump {e}"))?;
+        let r = self.resolve(&dest.host);
+        let mut p = self.base_profile(spec, &dest.host, &r);
+
+        if spec.contains('@') {
+            p.user = Some(dest.user);
+        }
+        if let Some(port) = dest.port {
+            p.port = Some(port);
+        }
+        Ok(p)
+    }
+
+    /// HostName / User / Port / IdentityFile；没写的和 ssh 一样取本地用户名和 22
+    fn base_profile(&mut self, label: &str, host: &str, r: &Resolved) -> ImportedProfile {
+        let first = |key: &str| r.fields.get(key).and_then(|a| a.first()).cloned();
+
+        let hostname = first("hostname")
+            .map(|h| expand_tokens(&h, &[('h', host)]))
+            .unwrap_or_else(|| host.to_string());
+        let user = first("user").unwrap_or_else(local_username);
+
+        let port = match first("port").map(|p| p.parse::<u16>()) {
+            Some(Ok(port)) if port != 0 => port,
+            Some(_) => {
+                self.warnings
+                    .push(format!("{label}: invalid Port; using 22"));
+                22
+            }
+            None => 22,
+        };
+
+        let identity_files = if r.identity_files.is_empty() {
+            &r.global_identity_files
+        } else {
+            &r.identity_files
+        };
+        let identity_files = identity_files
+            .iter()
+            .filter(|f| !f.eq_ignore_ascii_case("none"))
+            .collect::<Vec<_>>();
+        if identity_files.len() > 1 {
+            self.warnings.push(format!(
+                "{label}: only the first IdentityFile ({}) is imported",
+                identity_files[0]
+            ));
+        }
+        let home = self.home.to_string_lossy().into_owned();
+        let local_user = local_username();
+        let key_path = identity_files.first().map(|f| {
+            let f = expand_tokens(
+                f,
+                &[
+                    ('d', &home),
+                    ('h', &hostname),
+                    ('r', &user),
+                    ('u', &local_user),
+                ],
+            );
+            expand_tilde(&f, &self.home).to_string_lossy().into_owned()
+        });
+
+        ImportedProfile {
+            label: label.to_string(),
+            host: hostname,
+            user: Some(user),
+            port: Some(port),
+            key_path,
+            ..Default::default()
+        }
+    }
+}
+
+/// `Keyword value` 或 `Keyword=value`；返回原始关键字和参数（引号内的空白不切）
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let (name, rest) = line.split_at(end);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+    Some((name.to_string(), split_args(rest)))
+}
+
+/// 没加引号、以 `#` 开头的词之后都是注释
+fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.peek() {
+            None | Some('#') => break,
+            _ => {}
+        }
+
+        let mut arg = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    for q in chars.by_ref() {
+                        if q == '"' {
+                            break;
+                        }
+                        arg.push(q);
+                    }
+                }
+                c if c.is_whitespace() => break,
+                c => arg.push(c),
+            }
+        }
+        args.push(arg);
+    }
+
+    args
+}
+
+fn is_pattern(s: &str) -> bool {
+    s.starts_with('!') || has_glob(s)
+}
+
+fn has_glob(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Host 的匹配规则：任一取反模式命中就不匹配，否则任一普通模式命中即匹配（不区分大小写）
+fn host_matches(patterns: &[String], host: &str) -> bool {
+    let host = host.to_lowercase();
+    let mut matched = false;
+    for p in patterns {
+        let p = p.to_lowercase();
+        match p.strip_prefix('!') {
+            Some(negated) if glob_match(negated, &host) => return false,
+            Some(_) => {}
+            None => matched |= glob_match(&p, &host),
+        }
+    }
+    matched
+}
+
+/// 只支持 `*` 和 `?`，区分大小写
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p = pattern.chars().collect::<Vec<_>>();
+    let t = text.chars().collect::<Vec<_>>();
+    let (mut pi, mut ti) = (0, 0);
+    // 最近一个 `*` 的位置，以及它当时吃到 text 的哪里
+    let mut star = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// 逐级展开路径里带通配符的部分，结果排好序；和 shell 一样 `*` 不匹配隐藏文件
+fn glob_files(path: &Path) -> Vec<PathBuf> {
+    let mut found = vec![PathBuf::new()];
+
+    for comp in path.components() {
+        let part = comp.as_os_str().to_string_lossy();
+        if !matches!(comp, Component::Normal(_)) || !has_glob(&part) {
+            found.iter_mut().for_each(|f| f.push(comp));
+            continue;
+        }
+
+        found = found
+            .into_iter()
+            .flat_map(|dir| {
+                let mut names = fs::read_dir(&dir)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .filter(|n| !n.starts_with('.') || part.starts_with('.'))
+                    .filter(|n| glob_match(&part, n))
+                    .collect::<Vec<_>>();
+                names.sort();
+                names.into_iter().map(move |n| dir.join(n))
+            })
+            .collect();
+    }
+
+    found.into_iter().filter(|p| p.is_file()).collect()
+}
+
+fn expand_tilde(raw: &str, home: &Path) -> PathBuf {
+    match raw.strip_prefix('~') {
+        Some("") => home.to_path_buf(),
+        Some(rest) if rest.starts_with('/') => home.join(&rest[1..]),
+        _ => PathBuf::from(raw),
+    }
+}
+
+/// ssh_config 里的 `%h` 之类的记号；不认识的原样保留
+fn expand_tokens(raw: &str, tokens: &[(char, &str)]) -> String {
+    let mut out = String::new();
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some(t) => match tokens.iter().find(|(k, _)| *k == t) {
+                Some((_, v)) => out.push_str(v),
+                None => {
+                    out.push('%');
+                    out.push(t);
+                }
+            },
+            None => out.push('%'),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn parse(files: &[(&str, &str)]) -> ImportBatch {
+        let home = TempDir::new("ssh-config");
+        for (rel, text) in files {
+            home.write(&format!(".ssh/{rel}"), text);
+        }
+        parse_with_home(None, home.to_path_buf()).unwrap()
+    }
+
+    fn find<'a>(batch: &'a ImportBatch, label: &str) -> &'a ImportedProfile {
+        batch
+            .profiles
+            .iter()
+            .find(|p| p.label == label)
+            .unwrap_or_else(|| panic!("{label} not imported"))
+    }
+
+    fn labels(batch: &ImportBatch) -> Vec<&str> {
+        batch.profiles.iter().map(|p| p.label.as_str()).collect()
+    }
+
+    #[test]
+    fn glob_match_handles_star_and_question_mark() {
+        assert!(glob_match("*.prod", "web.prod"));
+        assert!(!glob_match("*.prod", "web.prod.old"));
+        assert!(glob_match("web-??", "web-01"));
+        assert!(!glob_match("web-??", "web-1"));
+        assert!(glob_match("a*b*c", "axxbyybc"));
+        assert!(!glob_match("a*b*c", "axxbyy"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("db", "db"));
+        assert!(!glob_match("DB", "db"));
+    }
+
+    #[test]
+    fn host_matches_honours_negated_patterns() {
+        let patterns = ["*.prod", "!db.prod"].map(String::from);
+        assert!(host_matches(&patterns, "web.prod"));
+        assert!(host_matches(&patterns, "WEB.PROD"));
+        assert!(!host_matches(&patterns, "db.prod"));
+        assert!(!host_matches(&patterns, "web.dev"));
+        // 只有取反模式的块什么都不匹配
+        assert!(!host_matches(&["!db".to_string()], "web"));
+    }
+
+    #[test]
+    fn expand_tokens_replaces_known_tokens_only() {
+        let tokens = [('h', "web"), ('d', "/home/me")];
+        assert_eq!(expand_tokens("%h.internal", &tokens), "web.internal");
+        assert_eq!(
+            expand_tokens("%d/.ssh/id_%h", &tokens),
+            "/home/me/.ssh/id_web"
+        );
+        assert_eq!(expand_tokens("100%%", &tokens), "100%");
+        assert_eq!(expand_tokens("%z-%", &tokens), "%z-%");
+    }
+
+    #[test]
+    fn include_globs_are_read_in_order() {
+        let batch = parse(&[
+            (
+                "config",
+                "Include conf.d/*.conf\n\
+                 Host *\n    User fallback\n",
+            ),
+            (
+                "conf.d/10-alpha.conf",
+                "Host alpha\n    HostName %h.internal\n    Port 2200\n",
+            ),
+            (
+                "conf.d/20-beta.conf",
+                "Host beta\n    ProxyJump alpha\n    Include conf.d/20-beta.conf\n",
+            ),
+            ("conf.d/.hidden.conf", "Host hidden\n"),
+        ]);
+
+        assert_eq!(labels(&batch), ["alpha", "beta"]);
+        let alpha = find(&batch, "alpha");
+        assert_eq!(alpha.host, "alpha.internal");
+        assert_eq!(alpha.port, Some(2200));
+        assert_eq!(alpha.user.as_deref(), Some("fallback"));
+        assert_eq!(find(&batch, "beta").jumps, ["alpha"]);
+        assert!(
+            batch.warnings.iter().any(|w| w.contains("includes itself")),
+            "{:?}",
+            batch.warnings
+        );
+    }
+
+    #[test]
+    fn include_inside_host_only_applies_to_that_host() {
+        let batch = parse(&[
+            (
+                "config",
+                "Host web\n    Include web.conf\n    HostName web.example.com\n\
+                 Host db\n    User dba\n",
+            ),
+            ("web.conf", "User deploy\n"),
+        ]);
+        assert_eq!(find(&batch, "web").user.as_deref(), Some("deploy"));
+        assert_eq!(find(&batch, "web").host, "web.example.com");
+        assert_eq!(find(&batch, "db").user.as_deref(), Some("dba"));
+    }
+
+    #[test]
+    fn jumps_through_skipped_aliases_are_skipped_too() {
+        let batch = parse(&[(
+            "config",
+            "Host broken\n    ProxyJump gw1,gw2:notaport\n\
+             Host web\n    ProxyJump broken\n\
+             Host api\n    ProxyJump web\n\
+             Host ok\n    User me\n",
+        )]);
+
+        // gw1 本身没问题，但它所在的链整个跳过了，不留下孤立的跳板
+        assert_eq!(labels(&batch), ["ok"]);
+        for label in ["broken", "web", "api"] {
+            assert!(
+                batch
+                    .warnings
+                    .iter()
+                    .any(|w| w.starts_with(&format!("{label}: skipped"))),
+                "{label}: {:?}",
+                batch.warnings
+            );
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Xshell 会话文件（`.xsh`，一个会话一个文件，通常是 UTF-16 的 INI）。
+//!
+//! 会话名就是文件名，所在子目录当标签。保存的密码是 Xshell 按本机加密的，
+//! 用户 key 存在 Xshell 自己的密钥管理器里，代理也定义在会话文件之外，这几样都只给提示。
+
+use crate::entity::profiles::AuthMode;
+use crate::error::AppResult;
+use crate::infra::import::{Ini, SourceFile, collect_files, folder_tag, push_profile, read_text};
+use crate::usecase::{ImportBatch, ImportedProfile};
+
+pub fn parse_xshell(paths: &[String]) -> AppResult<ImportBatch> {
+    let mut batch = ImportBatch::default();
+
+    for file in collect_files(paths, &["xsh"])? {
+        let ini = Ini::parse(&read_text(&file.path)?);
+        let source = file.display();
+        match session(&file, &ini, &mut batch.warnings) {
+            Ok(p) => push_profile(&mut batch, &source, p),
+            Err(why) => batch.warnings.push(format!("{source}: skipped: {why}")),
+        }
+    }
+
+    Ok(batch)
+}
+
+fn session(
+    file: &SourceFile,
+    ini: &Ini,
+    warnings: &mut Vec<String>,
+) -> Result<ImportedProfile, String> {
+    let label = file.stem();
+
+    let protocol = ini.get("CONNECTION", "Protocol").unwrap_or("SSH");
+    if !protocol.eq_ignore_ascii_case("SSH") {
+        return Err(format!("{protocol} session, only SSH is supported"));
+    }
+    let host = ini
+        .get("CONNECTION", "Host")
+        .ok_or("no host in [CONNECTION]")?;
+    let port = match ini.get("CONNECTION", "Port") {
+        Some(raw) => Some(raw.parse::<u16>().map_err(|_| format!("bad port {raw}"))?),
+        None => None,
+    };
+
+    // Method：0 密码；公钥用的是 Xshell 密钥管理器里的 key，拿不到文件，
+    // 和其余方式（键盘交互、GSSAPI……）一样先交给 agent / ssh 自己协商
+    let auth = "CONNECTION:AUTHENTICATION";
+    let auth_mode = match ini.get(auth, "Method") {
+        Some("0") => Some(AuthMode::Password),
+        _ => None,
+    };
+    if let Some(key) = ini.get(auth, "UserKey") {
+        warnings.push(format!(
+            "{label}: key {key} lives in Xshell's key manager; export it and run \
+             `jmssh profile set {label} --key <file>`"
+        ));
+    }
+    if ini.get(auth, "Password").is_some() {
+        warnings.push(format!(
+            "{label}: saved password is encrypted by Xshell and was not imported"
+        ));
+    }
+    if let Some(proxy) = ini.get("CONNECTION:PROXY", "Proxy") {
+        warnings.push(format!("{label}: proxy {proxy} not imported"));
+    }
+
+    Ok(ImportedProfile {
+        host: host.to_string(),
+        user: ini.get(auth, "UserName").map(str::to_string),
+        port,
+        auth_mode,
+        note: ini.get("CONNECTION", "Description").map(str::to_string),
+        tags: file
+            .folder
+            .as_deref()
+            .and_then(folder_tag)
+            .into_iter()
+            .collect(),
+        label,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempDir, utf16le};
+
+    const WEB: &str = "[CONNECTION]\r\n\
+        Host=10.0.0.5\r\n\
+        Port=2222\r\n\
+        Protocol=SSH\r\n\
+        Description=Frontend\r\n\
+        [CONNECTION:AUTHENTICATION]\r\n\
+        UserName=deploy\r\n\
+        Method=0\r\n\
+        Password=c2VjcmV0\r\n\
+        [CONNECTION:PROXY]\r\n\
+        Proxy=corp-socks\r\n";
+
+    #[test]
+    fn utf16_sessions_are_imported_with_folder_tags() {
+        let dir = TempDir::new("xshell");
+        dir.write("Prod Servers/web01.xsh", utf16le(WEB));
+        // 没有 BOM 的 UTF-16LE 也认
+        dir.write(
+            "db.xsh",
+            &utf16le("[CONNECTION]\r\nHost=db.example.com\r\n")[2..],
+        );
+        dir.write(
+            "switch.xsh",
+            "[CONNECTION]\nHost=10.0.0.1\nProtocol=TELNET\n",
+        );
+
+        let batch = parse_xshell(&[dir.display().to_string()]).unwrap();
+        let labels = batch
+            .profiles
+            .iter()
+            .map(|p| p.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["web01", "db"]);
+
+        let web = &batch.profiles[0];
+        assert_eq!(web.host, "10.0.0.5");
+        assert_eq!(web.port, Some(2222));
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        assert_eq!(web.auth_mode, Some(AuthMode::Password));
+        assert_eq!(web.note.as_deref(), Some("Frontend"));
+        assert_eq!(web.tags, ["prod-servers"]);
+        assert_eq!(web.password, None);
+
+        let db = &batch.profiles[1];
+        assert_eq!(db.host, "db.example.com");
+        assert_eq!((db.port, db.auth_mode.clone()), (None, None));
+
+        let warned = |needle: &str| batch.warnings.iter().any(|w| w.contains(needle));
+        assert!(warned("web01: saved password is encrypted"));
+        assert!(warned("web01: proxy corp-socks"));
+        assert!(warned("switch.xsh: skipped: TELNET session"));
+    }
+}
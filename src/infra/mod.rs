@@ -1 +1,9 @@
+pub mod ansible;
+pub mod files;
+pub mod import;
 pub mod password_store;
+#[cfg(unix)]
+pub mod pty_session;
+pub mod shell;
+pub mod ssh_config;
+pub mod vault_store;
@@ -4,18 +4,12 @@ use keyring::Entry;
 pub trait PasswordStore: Send + Sync {
     fn set_profile_password(&self, profile_id: u32, password: Option<String>) -> AppResult<()>;
     fn get_profile_password(&self, profile_id: u32) -> AppResult<Option<String>>;
-}
-
-pub struct NoopPasswordStore;
-
-impl PasswordStore for NoopPasswordStore {
-    fn set_profile_password(&self, _profile_id: u32, _password: Option<String>) -> AppResult<()> {
-        Ok(())
-    }
-
-    fn get_profile_password(&self, _profile_id: u32) -> AppResult<Option<String>> {
-        Ok(None)
+    /// 可有可无的密钥（key 模式下顺手存的私钥口令）：不为它问 vault 口令，后端出错也当没有
+    fn peek_profile_password(&self, profile_id: u32) -> Option<String> {
+        self.get_profile_password(profile_id).ok().flatten()
     }
+    /// 给日志用的后端名字，比如 "OS keyring"
+    fn backend_name(&self) -> &'static str;
 }
 
 #[derive(Clone)]
@@ -41,39 +35,166 @@ impl OsPasswordStore {
 }
 
 impl PasswordStore for OsPasswordStore {
+    fn backend_name(&self) -> &'static str {
+        "OS keyring"
+    }
+
     fn set_profile_password(&self, profile_id: u32, password: Option<String>) -> AppResult<()> {
-        let entry = self.entry_for_profile(profile_id)?;
-        match password {
-            Some(pwd) => {
-                entry.set_password(&pwd).map_err(|e| {
-                    AppError::PasswordStoreError(format!(
-                        "failed to set password for profile #{profile_id}: {e}"
-                    ))
-                })?;
+        set_entry_password(&self.entry_for_profile(profile_id)?, profile_id, password)
+    }
+
+    fn get_profile_password(&self, profile_id: u32) -> AppResult<Option<String>> {
+        get_entry_password(&self.entry_for_profile(profile_id)?, profile_id)
+    }
+}
+
+/// 单个 entry 上的读写；`None` 表示删除，本来就没有也算成功
+fn set_entry_password(entry: &Entry, profile_id: u32, password: Option<String>) -> AppResult<()> {
+    match password {
+        Some(pwd) => {
+            entry.set_password(&pwd).map_err(|e| {
+                AppError::PasswordStoreError(format!(
+                    "failed to set password for profile #{profile_id}: {}",
+                    describe_keyring_error(&e)
+                ))
+            })?;
+        }
+        None => match entry.delete_credential() {
+            Ok(()) => {}
+            Err(keyring::Error::NoEntry) => {}
+            Err(e) => {
+                return Err(AppError::PasswordStoreError(format!(
+                    "failed to delete password for profile #{profile_id}: {}",
+                    describe_keyring_error(&e)
+                )));
+            }
+        },
+    }
+
+    Ok(())
+}
+
+fn get_entry_password(entry: &Entry, profile_id: u32) -> AppResult<Option<String>> {
+    match entry.get_password() {
+        Ok(pwd) => Ok(Some(pwd)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AppError::PasswordStoreError(format!(
+            "failed to get password for profile #{profile_id}: {}",
+            describe_keyring_error(&e)
+        ))),
+    }
+}
+
+/// 把 keyring 的底层错误翻译成带排查提示的文案。
+///
+/// Linux 上最常见的失败是没有可用的 Secret Service（无桌面会话 / 没有 D-Bus），
+/// 这种情况下直接报错，而不是假装存储成功。
+fn describe_keyring_error(e: &keyring::Error) -> String {
+    match e {
+        keyring::Error::NoStorageAccess(inner) | keyring::Error::PlatformFailure(inner) => {
+            if cfg!(target_os = "linux") {
+                format!(
+                    "no usable credential backend ({inner}); \
+                     make sure a Secret Service provider (e.g. gnome-keyring, KeePassXC) \
+                     is running and unlocked in this session"
+                )
+            } else {
+                format!("credential store unavailable ({inner})")
             }
-            None => match entry.delete_credential() {
-                Ok(()) => {}
-                Err(keyring::Error::NoEntry) => {}
-                Err(e) => {
-                    return Err(AppError::PasswordStoreError(format!(
-                        "failed to delete password for profile #{profile_id}: {e}"
-                    )));
-                }
-            },
         }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keyring::mock::{self, MockCredential};
 
-        Ok(())
+    /// mock 后端不跨 entry 持久化，round-trip 都在同一个 entry 上做
+    fn mock_entry() -> Entry {
+        keyring::set_default_credential_builder(mock::default_credential_builder());
+        Entry::new("jmssh-test", "profile:1").unwrap()
     }
 
-    fn get_profile_password(&self, profile_id: u32) -> AppResult<Option<String>> {
-        let entry = self.entry_for_profile(profile_id)?;
-
-        match entry.get_password() {
-            Ok(pwd) => Ok(Some(pwd)),
-            Err(keyring::Error::NoEntry) => Ok(None),
-            Err(e) => Err(AppError::PasswordStoreError(format!(
-                "failed to get password for profile #{profile_id}: {e}"
-            ))),
+    fn fail_next(entry: &Entry, err: keyring::Error) {
+        let mock: &MockCredential = entry.get_credential().downcast_ref().unwrap();
+        mock.set_error(err);
+    }
+
+    #[test]
+    fn set_get_delete_round_trip() {
+        let entry = mock_entry();
+
+        assert_eq!(get_entry_password(&entry, 1).unwrap(), None);
+        set_entry_password(&entry, 1, Some("s3cret".into())).unwrap();
+        assert_eq!(
+            get_entry_password(&entry, 1).unwrap().as_deref(),
+            Some("s3cret")
+        );
+
+        set_entry_password(&entry, 1, Some("changed".into())).unwrap();
+        assert_eq!(
+            get_entry_password(&entry, 1).unwrap().as_deref(),
+            Some("changed")
+        );
+
+        set_entry_password(&entry, 1, None).unwrap();
+        assert_eq!(get_entry_password(&entry, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn missing_entry_is_not_an_error() {
+        let entry = mock_entry();
+
+        // 删除不存在的密码也算成功
+        set_entry_password(&entry, 1, None).unwrap();
+
+        fail_next(&entry, keyring::Error::NoEntry);
+        assert_eq!(get_entry_password(&entry, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn os_store_reads_missing_password_as_none() {
+        keyring::set_default_credential_builder(mock::default_credential_builder());
+        let store = OsPasswordStore::new("jmssh-test");
+
+        assert_eq!(store.get_profile_password(7).unwrap(), None);
+        store.set_profile_password(7, None).unwrap();
+    }
+
+    #[test]
+    fn unavailable_platform_is_reported() {
+        let entry = mock_entry();
+
+        fail_next(&entry, keyring::Error::NoStorageAccess("dbus down".into()));
+        let err = get_entry_password(&entry, 1).unwrap_err();
+        let AppError::PasswordStoreError(msg) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert!(msg.contains("profile #1"), "{msg}");
+        assert!(msg.contains("dbus down"), "{msg}");
+        if cfg!(target_os = "linux") {
+            assert!(msg.contains("no usable credential backend"), "{msg}");
+        } else {
+            assert!(msg.contains("credential store unavailable"), "{msg}");
         }
+
+        fail_next(&entry, keyring::Error::PlatformFailure("locked".into()));
+        let err = set_entry_password(&entry, 1, Some("x".into())).unwrap_err();
+        assert!(matches!(err, AppError::PasswordStoreError(ref m) if m.contains("locked")));
+        // 出错时不写入
+        assert_eq!(get_entry_password(&entry, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn describe_keyring_error_passes_other_errors_through() {
+        let e = keyring::Error::NoEntry;
+        assert_eq!(describe_keyring_error(&e), e.to_string());
+
+        let e = keyring::Error::PlatformFailure("boom".into());
+        let msg = describe_keyring_error(&e);
+        assert!(msg.contains("boom"), "{msg}");
+        assert_ne!(msg, e.to_string());
     }
 }
@@ -0,0 +1,391 @@
+//! 基于 expectrl 的 PTY 会话驱动：在伪终端里跑 ssh，识别认证提示并自动应答，
+//! 其余时间把 PTY 原样交给用户交互。
+//!
+//! 只匹配 OpenSSH 自己格式的提示（`user@host's password:` / `(user@host) Password:` /
+//! `Enter passphrase for key '...'`），每个密钥只用一次：
+//! 登录后远端出现的 `[sudo] password for ...` 之类不会被误填，密码输错时重试交给用户手动输入。
+//! 主机 key 的确认（`Are you sure you want to continue connecting`）永远不自动应答，
+//! 提示一行后由用户自己核对指纹回答，之后的密码提示照常自动填。
+//! 密码提示里的 user@host 必须和某一跳完全对上才填，对不上就提示一行、交给用户输入，
+//! 绝不把一跳的密码发给另一台主机。
+
+use crate::error::{AppError, AppResult};
+use crate::term::{c_prefix, c_warning};
+use crate::usecase::HopSecret;
+use expectrl::Session;
+use expectrl::interact::Context;
+use expectrl::process::unix::WaitStatus;
+use regex::bytes::Regex;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, RawFd};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, ExitStatus};
+use std::sync::LazyLock;
+
+/// 交互时的 escape 字符：0xFF 不会出现在合法 UTF-8 输入里，等于禁用 expectrl 的 Ctrl-] 退出
+const ESCAPE_DISABLED: u8 = 0xFF;
+
+/// 只保留输出尾部这么多字节用于匹配提示，避免长会话里缓冲无限增长
+const PROMPT_WINDOW: usize = 1024;
+
+static PASSWORD_PROMPT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:([^\s@()]+)@([^\s'()]+)'s password:|\(([^\s@()]+)@([^\s()]+)\) Password:)\s*$")
+        .expect("valid password prompt regex")
+});
+
+static PASSPHRASE_PROMPT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"Enter passphrase for key '([^']+)':\s*$").expect("valid passphrase prompt regex")
+});
+
+static HOST_KEY_PROMPT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?:continue connecting \([^)]*\)\?|Please type 'yes', 'no' or the fingerprint:)\s*$",
+    )
+    .expect("valid host key prompt regex")
+});
+
+/// 窗口里认出来的提示
+#[derive(Debug, PartialEq, Eq)]
+enum Prompt {
+    /// 要自动填的密码 / passphrase
+    Answer(String),
+    /// 主机 key 确认，交给用户
+    HostKey,
+    /// 密码提示来自不认识的 user@host，交给用户
+    Unmatched(String),
+}
+
+/// 终端输入：直接读 fd 0，不经过 std 的 BufReader，避免 poll 之后数据卡在用户态缓冲里
+struct TermInput(File);
+
+impl Read for &mut TermInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl AsRawFd for &mut TermInput {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+struct PromptState {
+    secrets: Vec<(HopSecret, bool)>,
+    window: Vec<u8>,
+    term_size: Option<(u16, u16)>,
+}
+
+impl PromptState {
+    fn new(secrets: Vec<HopSecret>) -> Self {
+        Self {
+            secrets: secrets.into_iter().map(|s| (s, false)).collect(),
+            window: Vec::new(),
+            term_size: None,
+        }
+    }
+
+    fn armed(&self) -> bool {
+        self.secrets.iter().any(|(_, used)| !used)
+    }
+
+    fn push_output(&mut self, buf: &[u8]) {
+        self.window.extend_from_slice(buf);
+        if self.window.len() > PROMPT_WINDOW {
+            let cut = self.window.len() - PROMPT_WINDOW;
+            self.window.drain(..cut);
+        }
+    }
+
+    /// 一段 PTY 输出：认出提示就往 `answer`（PTY）里写密钥，或者在终端上提示一行
+    fn on_output(&mut self, buf: &[u8], answer: &mut impl Write) -> io::Result<()> {
+        if !self.armed() {
+            return Ok(());
+        }
+        self.push_output(buf);
+        match self.take_prompt() {
+            Some(Prompt::Answer(secret)) => {
+                answer.write_all(secret.as_bytes())?;
+                answer.write_all(b"\n")?;
+            }
+            // 输出动作在转发这段输出之前执行，提示正好出现在 ssh 的问题上方
+            Some(Prompt::HostKey) => notify(
+                "ssh doesn't know this host key yet; check the fingerprint and answer ssh \
+                 yourself (saved passwords are filled in after that)",
+            ),
+            Some(Prompt::Unmatched(dest)) => notify(&format!(
+                "password prompt for {dest} doesn't match any hop; not filling it in"
+            )),
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// 根据当前窗口里的提示挑出要填的密钥（命中后标记已用）
+    fn take_prompt(&mut self) -> Option<Prompt> {
+        if HOST_KEY_PROMPT.is_match(&self.window) {
+            self.window.clear();
+            return Some(Prompt::HostKey);
+        }
+
+        let idx = if let Some(caps) = PASSWORD_PROMPT.captures(&self.window) {
+            let user = caps.get(1).or(caps.get(3)).map(|m| m.as_bytes());
+            let host = caps.get(2).or(caps.get(4)).map(|m| m.as_bytes());
+            let Some(idx) = self.match_hop(user, host) else {
+                let dest = format!(
+                    "{}@{}",
+                    String::from_utf8_lossy(user.unwrap_or_default()),
+                    String::from_utf8_lossy(host.unwrap_or_default()),
+                );
+                self.window.clear();
+                return Some(Prompt::Unmatched(dest));
+            };
+            Some(idx)
+        } else if let Some(caps) = PASSPHRASE_PROMPT.captures(&self.window) {
+            let path = caps.get(1).map(|m| m.as_bytes());
+            self.secrets.iter().position(|(s, used)| {
+                !used
+                    && s.key_path
+                        .as_deref()
+                        .is_some_and(|p| Some(p.as_bytes()) == path)
+            })
+        } else {
+            None
+        }?;
+
+        self.window.clear();
+        let (secret, used) = &mut self.secrets[idx];
+        *used = true;
+        Some(Prompt::Answer(secret.secret.clone()))
+    }
+
+    /// 只认 user 和 host 都对上的那一跳；`~/.ssh/config` 里的 HostName 改写会让 host 对不上，
+    /// 这时宁可让用户自己输，也不猜
+    /// 私钥口令（带 key_path 的）永远不拿来回答密码提示：服务器退回密码认证时不能把本地口令发过去
+    fn match_hop(&self, user: Option<&[u8]>, host: Option<&[u8]>) -> Option<usize> {
+        self.secrets.iter().position(|(s, used)| {
+            !used
+                && s.key_path.is_none()
+                && Some(s.user.as_bytes()) == user
+                && host.is_some_and(|h| h.eq_ignore_ascii_case(s.host.as_bytes()))
+        })
+    }
+}
+
+/// 在 PTY 里运行 `program args...`，遇到认证提示时从 `secrets` 里应答，
+/// 其余输入输出直接透传给当前终端。返回子进程的退出状态。
+pub fn run_with_autofill(
+    program: &str,
+    args: &[String],
+    secrets: Vec<HopSecret>,
+) -> AppResult<ExitStatus> {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+
+    let mut session = Session::spawn(cmd).map_err(|e| match e {
+        expectrl::Error::IO(io) => AppError::Io(io),
+        other => AppError::IoError(format!("failed to spawn {program} in a pty: {other}")),
+    })?;
+
+    let mut input = TermInput(File::from(io::stdin().as_fd().try_clone_to_owned()?));
+    let mut state = PromptState::new(secrets);
+
+    crossterm::terminal::enable_raw_mode()?;
+    let result = interact_until_exit(&mut session, &mut input, &mut state);
+    let restore = crossterm::terminal::disable_raw_mode();
+
+    let status = result?;
+    restore?;
+    Ok(status)
+}
+
+fn interact_until_exit(
+    session: &mut expectrl::session::OsSession,
+    input: &mut TermInput,
+    state: &mut PromptState,
+) -> AppResult<ExitStatus> {
+    sync_window_size(session, state);
+
+    loop {
+        let mut interact = session
+            .interact(&mut *input, io::stdout())
+            .set_escape_character(ESCAPE_DISABLED)
+            .with_state(&mut *state);
+
+        interact
+            .set_output_action(|ctx| {
+                ctx.state.on_output(ctx.buf, ctx.session)?;
+                Ok(false)
+            })
+            .set_idle_action(|ctx| {
+                on_idle(ctx);
+                Ok(false)
+            });
+
+        let spawned = interact.spawn();
+        let status = interact.get_status();
+        drop(interact);
+
+        let status = match (spawned, status) {
+            (_, Some(status)) => status,
+            (Ok(true), None) => continue,
+            // 子进程退出后读 PTY master 会得到 EIO，这里统一以 wait 的结果为准
+            (Ok(false), None) | (Err(_), None) => session
+                .get_process()
+                .wait()
+                .map_err(|e| AppError::IoError(format!("failed to wait for ssh process: {e}")))?,
+        };
+
+        drain_output(session);
+        return Ok(to_exit_status(status));
+    }
+}
+
+/// interact 先检查进程状态再读输出，子进程退出前最后一段输出可能还留在 PTY 里，补转发一次
+fn drain_output(session: &mut expectrl::session::OsSession) {
+    let mut buf = [0u8; 4096];
+    let mut stdout = io::stdout();
+    while let Ok(n) = session.try_read(&mut buf) {
+        if n == 0 || stdout.write_all(&buf[..n]).is_err() {
+            break;
+        }
+    }
+    let _ = stdout.flush();
+}
+
+fn on_idle<I, O>(ctx: Context<'_, &mut expectrl::session::OsSession, I, O, &mut PromptState>) {
+    sync_window_size(ctx.session, ctx.state);
+}
+
+/// 把本地终端尺寸同步给 PTY（进入时一次，之后在 idle 回调里检测变化）
+fn sync_window_size(session: &mut expectrl::session::OsSession, state: &mut PromptState) {
+    let Ok(size) = crossterm::terminal::size() else {
+        return;
+    };
+    if state.term_size == Some(size) {
+        return;
+    }
+    state.term_size = Some(size);
+    let (cols, rows) = size;
+    let _ = session.get_process_mut().set_window_size(cols, rows);
+}
+
+/// 终端处于 raw 模式，换行要自己带 `\r`
+fn notify(msg: &str) {
+    eprint!("\r\n{} {}\r\n", c_prefix("[jmssh]"), c_warning(msg));
+}
+
+/// 没拿到退出码（被暂停之类）也算失败，不能当成 0
+fn to_exit_status(status: WaitStatus) -> ExitStatus {
+    match status {
+        WaitStatus::Exited(_, code) => ExitStatus::from_raw((code & 0xff) << 8),
+        WaitStatus::Signaled(_, sig, _) => ExitStatus::from_raw(sig as i32),
+        _ => ExitStatus::from_raw(1 << 8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret(user: &str, host: &str, pwd: &str) -> HopSecret {
+        HopSecret {
+            user: user.to_string(),
+            host: host.to_string(),
+            key_path: None,
+            secret: pwd.to_string(),
+        }
+    }
+
+    #[test]
+    fn host_key_prompt_is_left_to_the_user() {
+        let mut state = PromptState::new(vec![secret("u", "web", "pw")]);
+        state.push_output(
+            b"ED25519 key fingerprint is SHA256:abc.\r\n\
+              Are you sure you want to continue connecting (yes/no/[fingerprint])? ",
+        );
+        assert_eq!(state.take_prompt(), Some(Prompt::HostKey));
+        assert!(state.armed());
+
+        // 用户答完之后的密码提示照常填
+        state.push_output(b"yes\r\nu@web's password: ");
+        assert_eq!(state.take_prompt(), Some(Prompt::Answer("pw".to_string())));
+    }
+
+    #[test]
+    fn password_for_another_host_is_never_sent() {
+        let mut state = PromptState::new(vec![secret("u", "web", "pw")]);
+        state.push_output(b"u@db's password: ");
+        assert_eq!(
+            state.take_prompt(),
+            Some(Prompt::Unmatched("u@db".to_string()))
+        );
+        assert!(state.armed());
+    }
+
+    /// 假的 ssh：依次问跳板密码、目标密码和私钥口令，最后把收到的回答打印出来
+    #[test]
+    fn fake_ssh_gets_every_hop_answered() {
+        let script = r#"
+            printf "jump@bastion's password: "; read a
+            printf "Permission granted\r\n(app@10.0.0.5) Password: "; read b
+            printf "Enter passphrase for key '/keys/id_app': "; read c
+            echo "got:$a,$b,$c"
+        "#;
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", script]);
+        let mut session = Session::spawn(cmd).expect("spawn fake ssh");
+
+        // 口令排在同一 user@host 的密码前面，密码提示也不能拿到它
+        let mut state = PromptState::new(vec![
+            HopSecret {
+                key_path: Some("/keys/id_app".to_string()),
+                ..secret("app", "10.0.0.5", "p3")
+            },
+            secret("jump", "bastion", "p1"),
+            secret("app", "10.0.0.5", "p2"),
+        ]);
+
+        let mut output = Vec::new();
+        let mut buf = [0u8; 512];
+        // 子进程退出后读 PTY master 会得到 EIO，就此结束
+        while let Ok(n) = session.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..n]);
+            state
+                .on_output(&buf[..n], &mut session)
+                .expect("answer prompt");
+        }
+
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.contains("got:p1,p2,p3"), "{output}");
+        assert!(!state.armed());
+    }
+
+    #[test]
+    fn key_passphrase_never_answers_a_password_prompt() {
+        let passphrase = HopSecret {
+            key_path: Some("/keys/id_web".to_string()),
+            ..secret("u", "web", "local-passphrase")
+        };
+        let mut state = PromptState::new(vec![passphrase.clone(), secret("u", "web", "pw")]);
+        state.push_output(b"u@web's password: ");
+        assert_eq!(state.take_prompt(), Some(Prompt::Answer("pw".to_string())));
+
+        // 只有口令、没有密码时，密码提示交给用户
+        let mut state = PromptState::new(vec![passphrase]);
+        state.push_output(b"u@web's password: ");
+        assert_eq!(
+            state.take_prompt(),
+            Some(Prompt::Unmatched("u@web".to_string()))
+        );
+    }
+
+    #[test]
+    fn unknown_wait_status_is_a_failure() {
+        assert!(!to_exit_status(WaitStatus::StillAlive).success());
+    }
+}
@@ -0,0 +1,57 @@
+//! POSIX shell 引号：给 `--print` / `jmssh cmd` 输出可以直接 `eval` 或复制粘贴的命令，
+//! 反过来也能把别的工具里存的参数串（比如 Ansible 的 `ansible_ssh_common_args`）拆开。
+
+use std::borrow::Cow;
+
+/// 只含安全字符时原样返回，否则整体用单引号包起来（内部的 `'` 写成 `'\''`）
+pub fn quote(arg: &str) -> Cow<'_, str> {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./-_".contains(c));
+    if safe {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', r"'\''")))
+    }
+}
+
+pub fn join<'a>(args: impl IntoIterator<Item = &'a str>) -> String {
+    args.into_iter().map(quote).collect::<Vec<_>>().join(" ")
+}
+
+/// `join` 的反方向：按空白切词，支持单引号、双引号和反斜杠转义；词首的 `#` 之后是注释
+pub fn split(s: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.peek() {
+            None | Some('#') => break,
+            _ => {}
+        }
+
+        let mut word = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => word.extend(chars.by_ref().take_while(|&q| q != '\'')),
+                '"' => {
+                    while let Some(q) = chars.next() {
+                        match q {
+                            '"' => break,
+                            '\\' => word.extend(chars.next()),
+                            q => word.push(q),
+                        }
+                    }
+                }
+                '\\' => word.extend(chars.next()),
+                c if c.is_whitespace() => break,
+                c => word.push(c),
+            }
+        }
+        words.push(word);
+    }
+
+    words
+}
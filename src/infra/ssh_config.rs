@@ -0,0 +1,267 @@
+//! 为跳板链生成临时 ssh_config：`-J a,b` 没法给每一跳单独指定 IdentityFile，
+//! 这里把每个 hop 渲染成一个 Host 别名，用 ProxyJump 串起来，再 `ssh -F` 指过去。
+//!
+//! `jmssh export ssh-config` 也在这里渲染：每个 profile 一个 `Host <label>` 块。
+
+use crate::entity::profiles::AuthMode;
+use crate::error::AppResult;
+use crate::infra::files::write_private;
+use crate::usecase::{ConnectHop, ProfileExport};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 生成的 Host 别名前缀，最终目标是 `jmssh-hop-{N-1}`
+const HOP_ALIAS_PREFIX: &str = "jmssh-hop-";
+
+pub fn hop_alias(idx: usize) -> String {
+    format!("{HOP_ALIAS_PREFIX}{idx}")
+}
+
+/// 是否需要走生成的 ssh_config：只要有跳板需要自己的 key 或 ssh 选项，`-J` 就表达不了
+pub fn needs_hop_config(hops: &[ConnectHop]) -> bool {
+    match hops.split_last() {
+        Some((_, jumps)) => jumps
+            .iter()
+            .any(|h| hop_identity_file_is_set(h) || !h.options.is_empty()),
+        None => false,
+    }
+}
+
+fn hop_identity_file_is_set(hop: &ConnectHop) -> bool {
+    hop.auth_mode == AuthMode::Key && hop.key_path_local.is_some()
+}
+
+/// 渲染整条链。用户自己的 `~/.ssh/config` 和系统配置放在最后 Include，
+/// 这样全局设置照常生效，但每一跳的 HostName/User/Port/IdentityFile 以这里为准（先出现的值优先）。
+pub fn render_hop_config(hops: &[ConnectHop]) -> String {
+    let mut out = String::from("# generated by jmssh for a single session; safe to delete\n");
+
+    for (idx, hop) in hops.iter().enumerate() {
+        out.push_str(&format!("\nHost {}\n", hop_alias(idx)));
+        out.push_str(&format!("    HostName {}\n", quote_value(&hop.host)));
+        out.push_str(&format!("    User {}\n", quote_value(&hop.user)));
+        out.push_str(&format!("    Port {}\n", hop.port));
+
+        if hop_identity_file_is_set(hop) {
+            // safe unwrap: hop_identity_file_is_set 已经保证是 Some
+            let key = hop.key_path_local.as_deref().unwrap();
+            out.push_str(&format!(
+                "    IdentityFile {}\n",
+                quote_value(&key.replace('%', "%%"))
+            ));
+            out.push_str("    IdentitiesOnly yes\n");
+        }
+
+        // 和 export 一样原样写，`LocalForward 8080 host:80` 加了引号 ssh 就不认
+        for (key, value) in &hop.options {
+            out.push_str(&format!("    {key} {value}\n"));
+        }
+
+        if idx > 0 {
+            out.push_str(&format!("    ProxyJump {}\n", hop_alias(idx - 1)));
+        }
+    }
+
+    out.push_str("\nHost *\n");
+    out.push_str("    Include ~/.ssh/config\n");
+    if Path::new("/etc/ssh/ssh_config").exists() {
+        out.push_str("    Include /etc/ssh/ssh_config\n");
+    }
+
+    out
+}
+
+/// 所有 profile 渲染成可以被 `~/.ssh/config` Include 的配置，返回内容和被跳过的 profile（每个一条提示）。
+/// label 当不了 Host 别名（带空白、通配符等）的 profile 跳过，经过它跳转的 profile 也一起跳过。
+/// `defaults` 是 config.toml 的 `[ssh.options]`，放在最后一个块里，profile 自己的同名选项先出现所以优先。
+pub fn render_profiles_config(
+    profiles: &[ProfileExport],
+    defaults: &BTreeMap<String, String>,
+) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut skipped = HashSet::new();
+    for p in profiles {
+        if !is_valid_alias(&p.profile.label) {
+            warnings.push(format!(
+                "{:?}: label can't be used as a Host alias; skipped",
+                p.profile.label
+            ));
+            skipped.insert(p.profile.label.as_str());
+        }
+    }
+    // 跳板被跳过的也得跳过，直到不再变化
+    loop {
+        let before = skipped.len();
+        for p in profiles {
+            let label = p.profile.label.as_str();
+            if !skipped.contains(label) && p.jumps.iter().any(|j| skipped.contains(j.as_str())) {
+                warnings.push(format!("{label}: jumps through a skipped profile; skipped"));
+                skipped.insert(label);
+            }
+        }
+        if skipped.len() == before {
+            break;
+        }
+    }
+
+    let mut out = String::from(
+        "# generated by jmssh from its profile database; regenerate with\n\
+         # `jmssh export ssh-config --write` instead of editing this file\n",
+    );
+    let mut exported = Vec::new();
+
+    for p in profiles
+        .iter()
+        .filter(|p| !skipped.contains(p.profile.label.as_str()))
+    {
+        let v = &p.profile;
+        out.push_str(&format!("\nHost {}\n", v.label));
+        out.push_str(&format!("    HostName {}\n", quote_value(&v.host)));
+        out.push_str(&format!("    User {}\n", quote_value(&v.user)));
+        out.push_str(&format!("    Port {}\n", v.port));
+
+        if let Some(key) = v
+            .key_path
+            .as_deref()
+            .filter(|_| v.mode == AuthMode::Key.as_str())
+        {
+            out.push_str(&format!(
+                "    IdentityFile {}\n",
+                quote_value(&key.replace('%', "%%"))
+            ));
+            out.push_str("    IdentitiesOnly yes\n");
+        }
+        if !p.jumps.is_empty() {
+            out.push_str(&format!("    ProxyJump {}\n", p.jumps.join(",")));
+        }
+        // 和 `-o Key=Value` 一样原样写：ssh 对两者按同样的规则分词，`LocalForward 8080 host:80` 不能加引号
+        for (key, value) in &v.options {
+            out.push_str(&format!("    {key} {value}\n"));
+        }
+
+        exported.push(v.label.as_str());
+    }
+
+    if !defaults.is_empty() && !exported.is_empty() {
+        out.push_str("\n# [ssh.options] from config.toml\n");
+        out.push_str(&format!("Host {}\n", exported.join(" ")));
+        for (key, value) in defaults {
+            out.push_str(&format!("    {key} {value}\n"));
+        }
+    }
+
+    (out, warnings)
+}
+
+/// Host 行按空白分词，`*` `?` `!` 是模式语法，`#` 开始注释
+fn is_valid_alias(label: &str) -> bool {
+    !label.is_empty()
+        && !label.chars().any(|c| {
+            c.is_whitespace() || c.is_control() || matches!(c, '*' | '?' | '!' | '#' | '"' | ',')
+        })
+}
+
+fn quote_value(v: &str) -> String {
+    if v.chars().any(char::is_whitespace) {
+        format!("\"{v}\"")
+    } else {
+        v.to_string()
+    }
+}
+
+/// 会话级临时配置文件：0600 创建，Drop 时删除
+pub struct TempSshConfig {
+    path: PathBuf,
+}
+
+/// 本进程的临时配置会写到哪里（`--dry-run` 只展示路径，不落盘）
+pub fn temp_config_path(dir: &Path) -> PathBuf {
+    dir.join(format!("ssh_config.{}", std::process::id()))
+}
+
+impl TempSshConfig {
+    pub fn write(dir: &Path, contents: &str) -> AppResult<Self> {
+        let path = temp_config_path(dir);
+        write_private(&path, contents)?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// `--print` 打印出的命令之后还要用，配置不能随进程删掉：按内容取名，
+/// 同一条跳板链反复 print 复用同一个文件，不会每次多留一个
+pub fn write_printed_config(dir: &Path, contents: &str) -> AppResult<PathBuf> {
+    // FNV-1a：只要求同样的内容得到同样的名字，跨版本稳定
+    let hash = contents.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    let path = dir.join(format!("ssh_config.{hash:016x}"));
+    write_private(&path, contents)?;
+    Ok(path)
+}
+
+impl Drop for TempSshConfig {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hop(label: &str, key: Option<&str>, options: &[(&str, &str)]) -> ConnectHop {
+        ConnectHop {
+            id: None,
+            label: label.to_string(),
+            host: format!("{label}.example.com"),
+            user: "ops".to_string(),
+            port: 22,
+            auth_mode: if key.is_some() {
+                AuthMode::Key
+            } else {
+                AuthMode::Agent
+            },
+            key_path_local: key.map(str::to_string),
+            options: options
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn hop_config_writes_options_unquoted() {
+        let hops = [
+            hop(
+                "bastion",
+                Some("/keys/my key"),
+                &[("LocalForward", "8080 db:80")],
+            ),
+            hop("web", None, &[("ServerAliveInterval", "30")]),
+        ];
+        assert!(needs_hop_config(&hops));
+
+        let out = render_hop_config(&hops);
+        let block = |alias: &str| {
+            let start = out.find(&format!("Host {alias}\n")).unwrap();
+            let rest = &out[start + 1..];
+            rest[..rest.find("\nHost ").unwrap_or(rest.len())].to_string()
+        };
+
+        let first = block("jmssh-hop-0");
+        assert!(first.contains("    HostName bastion.example.com\n"));
+        assert!(first.contains("    IdentityFile \"/keys/my key\"\n"));
+        assert!(first.contains("    LocalForward 8080 db:80\n"));
+        assert!(!first.contains("ProxyJump"));
+
+        let second = block("jmssh-hop-1");
+        assert!(second.contains("    ServerAliveInterval 30\n"));
+        assert!(second.contains("    ProxyJump jmssh-hop-0\n"));
+        assert!(!second.contains("IdentityFile"));
+    }
+}
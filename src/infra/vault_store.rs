@@ -0,0 +1,634 @@
+use crate::error::{AppError, AppResult};
+use crate::infra::files::write_private;
+use crate::infra::password_store::PasswordStore;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as B64;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+const VAULT_VERSION: u32 = 1;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+
+/// 新建 / rekey 时用的 argon2id 参数；读的时候以文件里记录的为准
+#[derive(Debug, Clone, Copy)]
+struct KdfCost {
+    m: u32,
+    t: u32,
+    p: u32,
+}
+
+// argon2id 默认参数：64 MiB / 3 轮 / 单线程，跳板机上也能接受的解锁耗时
+const DEFAULT_KDF_COST: KdfCost = KdfCost {
+    m: 64 * 1024,
+    t: 3,
+    p: 1,
+};
+
+/// 会话目录里的 key 缓存最多活这么久，登出没清空的话也会自己过期
+const KEY_CACHE_TTL: Duration = Duration::from_secs(8 * 60 * 60);
+
+type VaultKey = Zeroizing<[u8; KEY_LEN]>;
+type Secrets = BTreeMap<String, String>;
+
+#[derive(Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+}
+
+/// 落盘格式：整个 secrets map 作为一个密文块，文件里只有 KDF 参数和 nonce 是明文
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    kdf: KdfParams,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// 解锁后缓存在会话目录里的派生 key；salt 用来识别 rekey 之后的过期缓存，
+/// expires_at（unix 秒）之后当作已锁
+#[derive(Serialize, Deserialize)]
+struct KeyCache {
+    salt: String,
+    key: String,
+    expires_at: u64,
+}
+
+/// 没有地方缓存 key 时，用到 vault 的那一刻现场问口令
+pub type PassphrasePrompt = fn() -> AppResult<String>;
+
+/// 基于口令加密文件的 PasswordStore，给没有 Secret Service 的无头机器用。
+pub struct VaultPasswordStore {
+    vault_path: PathBuf,
+    /// 平台没有会话目录时为 None：key 只留在本进程内存里
+    key_cache_path: Option<PathBuf>,
+    prompt: Option<PassphrasePrompt>,
+    session_key: Mutex<Option<(String, VaultKey)>>,
+    kdf_cost: KdfCost,
+}
+
+impl VaultPasswordStore {
+    pub fn new(vault_path: PathBuf, key_cache_path: Option<PathBuf>) -> Self {
+        Self {
+            vault_path,
+            key_cache_path,
+            prompt: None,
+            session_key: Mutex::new(None),
+            kdf_cost: DEFAULT_KDF_COST,
+        }
+    }
+
+    pub fn with_prompt(mut self, prompt: PassphrasePrompt) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+
+    /// 解锁后的 key 能不能跨命令缓存（有没有会话目录）
+    pub fn caches_key(&self) -> bool {
+        self.key_cache_path.is_some()
+    }
+
+    pub fn exists(&self) -> bool {
+        self.vault_path.exists()
+    }
+
+    pub fn is_unlocked(&self) -> AppResult<bool> {
+        if !self.exists() {
+            return Ok(false);
+        }
+        let file = self.read_vault()?;
+        Ok(self.cached_key(&file)?.is_some())
+    }
+
+    /// 新建一个空 vault，并顺手把 key 缓存到当前会话
+    pub fn create(&self, passphrase: &str) -> AppResult<()> {
+        let _guard = self.lock_vault_file()?;
+        if self.exists() {
+            return Err(AppError::VaultError(format!(
+                "vault already exists at {}",
+                self.vault_path.display()
+            )));
+        }
+
+        let salt = random_salt();
+        let key = derive_key(
+            passphrase,
+            &salt,
+            self.kdf_cost.m,
+            self.kdf_cost.t,
+            self.kdf_cost.p,
+        )?;
+        self.write_secrets(&Secrets::new(), &key, &salt)?;
+        self.write_key_cache(&key, &salt)
+    }
+
+    /// 校验口令（能解开密文才算对），成功后缓存派生 key
+    pub fn unlock(&self, passphrase: &str) -> AppResult<()> {
+        let file = self.read_vault()?;
+        let salt = decode_b64(&file.kdf.salt)?;
+        let key = derive_key(
+            passphrase,
+            &salt,
+            file.kdf.m_cost,
+            file.kdf.t_cost,
+            file.kdf.p_cost,
+        )?;
+        decrypt_secrets(&file, &key)?;
+        self.write_key_cache(&key, &salt)
+    }
+
+    pub fn lock(&self) -> AppResult<()> {
+        *self.session_key.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        let Some(path) = &self.key_cache_path else {
+            return Ok(());
+        };
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 用旧口令解开，换新 salt + 新口令重新加密；缓存同步更新
+    pub fn rekey(&self, old_passphrase: &str, new_passphrase: &str) -> AppResult<()> {
+        let _guard = self.lock_vault_file()?;
+        let file = self.read_vault()?;
+        let old_salt = decode_b64(&file.kdf.salt)?;
+        let old_key = derive_key(
+            old_passphrase,
+            &old_salt,
+            file.kdf.m_cost,
+            file.kdf.t_cost,
+            file.kdf.p_cost,
+        )?;
+        let secrets = decrypt_secrets(&file, &old_key)?;
+
+        let salt = random_salt();
+        let key = derive_key(
+            new_passphrase,
+            &salt,
+            self.kdf_cost.m,
+            self.kdf_cost.t,
+            self.kdf_cost.p,
+        )?;
+        self.write_secrets(&secrets, &key, &salt)?;
+        self.write_key_cache(&key, &salt)
+    }
+
+    /// vault 本身每次都被 rename 替换，advisory 锁加在旁边不会被替换的 `<vault>.lock` 上；
+    /// 返回的文件 drop 时释放
+    fn lock_vault_file(&self) -> AppResult<fs::File> {
+        let mut path = self.vault_path.as_os_str().to_owned();
+        path.push(".lock");
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(PathBuf::from(path))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    fn read_vault(&self) -> AppResult<VaultFile> {
+        let raw = match fs::read_to_string(&self.vault_path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(AppError::VaultError(format!(
+                    "no vault at {}; run `jmssh vault unlock` to create one",
+                    self.vault_path.display()
+                )));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let file: VaultFile = serde_json::from_str(&raw)
+            .map_err(|e| AppError::VaultError(format!("malformed vault file: {e}")))?;
+
+        if file.version != VAULT_VERSION {
+            return Err(AppError::VaultError(format!(
+                "unsupported vault version {}",
+                file.version
+            )));
+        }
+
+        if file.kdf.algorithm != "argon2id" || file.cipher != "xchacha20poly1305" {
+            return Err(AppError::VaultError(format!(
+                "unsupported vault scheme {}/{}",
+                file.kdf.algorithm, file.cipher
+            )));
+        }
+
+        Ok(file)
+    }
+
+    fn cached_key(&self, file: &VaultFile) -> AppResult<Option<VaultKey>> {
+        if let Some((salt, key)) = &*self.session_key.lock().unwrap_or_else(|e| e.into_inner())
+            && *salt == file.kdf.salt
+        {
+            return Ok(Some(key.clone()));
+        }
+
+        let Some(path) = &self.key_cache_path else {
+            return Ok(None);
+        };
+        let raw = match fs::read_to_string(path) {
+            Ok(raw) => Zeroizing::new(raw),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // 旧格式（没有 expires_at）也当作过期
+        let cache = match serde_json::from_str::<KeyCache>(&raw) {
+            Ok(cache) if cache.expires_at > unix_now() => cache,
+            _ => {
+                self.lock()?;
+                return Ok(None);
+            }
+        };
+
+        // rekey 之后旧缓存作废
+        if cache.salt != file.kdf.salt {
+            return Ok(None);
+        }
+
+        let bytes = Zeroizing::new(decode_b64(&cache.key)?);
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        if bytes.len() != KEY_LEN {
+            return Ok(None);
+        }
+        key.copy_from_slice(&bytes);
+        Ok(Some(key))
+    }
+
+    fn unlocked_key(&self, file: &VaultFile) -> AppResult<VaultKey> {
+        if let Some(key) = self.cached_key(file)? {
+            return Ok(key);
+        }
+        // 没有会话目录：`vault unlock` 缓存不下来，只能这次现场问
+        match (self.prompt, self.caches_key()) {
+            (Some(prompt), false) => {
+                let passphrase = Zeroizing::new(prompt()?);
+                self.unlock(&passphrase)?;
+                self.cached_key(file)?.ok_or(AppError::VaultLocked)
+            }
+            _ => Err(AppError::VaultLocked),
+        }
+    }
+
+    fn write_key_cache(&self, key: &VaultKey, salt: &[u8]) -> AppResult<()> {
+        let salt = B64.encode(salt);
+        *self.session_key.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some((salt.clone(), key.clone()));
+
+        let Some(path) = &self.key_cache_path else {
+            return Ok(());
+        };
+        let cache = KeyCache {
+            salt,
+            key: B64.encode(key.as_slice()),
+            expires_at: unix_now() + KEY_CACHE_TTL.as_secs(),
+        };
+        let raw = Zeroizing::new(serde_json::to_string(&cache).map_err(json_err)?);
+        write_private(path, raw.as_bytes())
+    }
+
+    fn write_secrets(&self, secrets: &Secrets, key: &VaultKey, salt: &[u8]) -> AppResult<()> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(secrets).map_err(json_err)?);
+        let cipher = XChaCha20Poly1305::new(key.as_slice().into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| AppError::VaultError("failed to encrypt vault".to_string()))?;
+
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            kdf: KdfParams {
+                algorithm: "argon2id".to_string(),
+                m_cost: self.kdf_cost.m,
+                t_cost: self.kdf_cost.t,
+                p_cost: self.kdf_cost.p,
+                salt: B64.encode(salt),
+            },
+            cipher: "xchacha20poly1305".to_string(),
+            nonce: B64.encode(nonce),
+            ciphertext: B64.encode(ciphertext),
+        };
+
+        let raw = serde_json::to_vec_pretty(&file).map_err(json_err)?;
+        write_private(&self.vault_path, &raw)
+    }
+
+    /// 读-改-写期间持有 vault 的独占锁，并发的 `password set` 不会互相覆盖
+    fn update<F>(&self, f: F) -> AppResult<()>
+    where
+        F: FnOnce(&mut Secrets),
+    {
+        let _guard = self.lock_vault_file()?;
+        let file = self.read_vault()?;
+        let key = self.unlocked_key(&file)?;
+        let salt = decode_b64(&file.kdf.salt)?;
+        let mut secrets = decrypt_secrets(&file, &key)?;
+        f(&mut secrets);
+        self.write_secrets(&secrets, &key, &salt)
+    }
+}
+
+impl PasswordStore for VaultPasswordStore {
+    fn backend_name(&self) -> &'static str {
+        "vault"
+    }
+
+    fn set_profile_password(&self, profile_id: u32, password: Option<String>) -> AppResult<()> {
+        let entry = format!("profile:{profile_id}");
+        match password {
+            Some(pwd) => self.update(|s| {
+                s.insert(entry, pwd);
+            }),
+            None => {
+                // 没有 vault 时删除是 no-op，和 keyring 的 NoEntry 语义一致
+                if !self.exists() {
+                    return Ok(());
+                }
+                self.update(|s| {
+                    s.remove(&entry);
+                })
+            }
+        }
+    }
+
+    fn get_profile_password(&self, profile_id: u32) -> AppResult<Option<String>> {
+        if !self.exists() {
+            return Ok(None);
+        }
+        let file = self.read_vault()?;
+        let key = self.unlocked_key(&file)?;
+        let mut secrets = decrypt_secrets(&file, &key)?;
+        Ok(secrets.remove(&format!("profile:{profile_id}")))
+    }
+
+    fn peek_profile_password(&self, profile_id: u32) -> Option<String> {
+        if !self.exists() {
+            return None;
+        }
+        let file = self.read_vault().ok()?;
+        let key = self.cached_key(&file).ok()??;
+        let mut secrets = decrypt_secrets(&file, &key).ok()?;
+        secrets.remove(&format!("profile:{profile_id}"))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn random_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], m: u32, t: u32, p: u32) -> AppResult<VaultKey> {
+    let params = Params::new(m, t, p, Some(KEY_LEN))
+        .map_err(|e| AppError::VaultError(format!("invalid kdf parameters: {e}")))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut_slice())
+        .map_err(|e| AppError::VaultError(format!("key derivation failed: {e}")))?;
+    Ok(key)
+}
+
+fn decrypt_secrets(file: &VaultFile, key: &VaultKey) -> AppResult<Secrets> {
+    let nonce = decode_b64(&file.nonce)?;
+    let ciphertext = decode_b64(&file.ciphertext)?;
+    if nonce.len() != 24 {
+        return Err(AppError::VaultError("malformed vault nonce".to_string()));
+    }
+
+    let cipher = XChaCha20Poly1305::new(key.as_slice().into());
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+        .map(Zeroizing::new)
+        .map_err(|_| {
+            AppError::VaultError("wrong passphrase or corrupted vault file".to_string())
+        })?;
+
+    serde_json::from_slice(&plaintext).map_err(json_err)
+}
+
+fn decode_b64(s: &str) -> AppResult<Vec<u8>> {
+    B64.decode(s)
+        .map_err(|e| AppError::VaultError(format!("malformed vault data: {e}")))
+}
+
+fn json_err(e: serde_json::Error) -> AppError {
+    AppError::VaultError(format!("vault serialization failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// 每个测试一个独立目录，结束时删掉
+    struct TestDir(TempDir);
+
+    impl TestDir {
+        fn new() -> Self {
+            Self(TempDir::new("vault"))
+        }
+
+        /// 共用同一个 vault 和 key 缓存的新实例（相当于另一条 jmssh 命令）
+        fn store(&self) -> VaultPasswordStore {
+            self.store_with_cache(Some(self.0.join("vault.key")))
+        }
+
+        fn store_with_cache(&self, cache: Option<PathBuf>) -> VaultPasswordStore {
+            let mut store = VaultPasswordStore::new(self.0.join("jmssh.vault"), cache);
+            // 测试里用最低的 argon2 参数，免得 debug 构建跑很久
+            store.kdf_cost = KdfCost { m: 8, t: 1, p: 1 };
+            store
+        }
+
+        fn edit_json(&self, name: &str, f: impl FnOnce(&mut serde_json::Value)) {
+            let path = self.0.join(name);
+            let mut value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            f(&mut value);
+            fs::write(&path, value.to_string()).unwrap();
+        }
+    }
+
+    #[test]
+    fn create_then_unlock_round_trips() {
+        let dir = TestDir::new();
+        let store = dir.store();
+        store.create("pass").unwrap();
+        store
+            .set_profile_password(1, Some("s3cret".into()))
+            .unwrap();
+
+        // 另一条命令读会话缓存
+        assert_eq!(
+            dir.store().get_profile_password(1).unwrap().as_deref(),
+            Some("s3cret")
+        );
+
+        dir.store().lock().unwrap();
+        let store = dir.store();
+        assert!(matches!(
+            store.get_profile_password(1),
+            Err(AppError::VaultLocked)
+        ));
+        store.unlock("pass").unwrap();
+        assert_eq!(
+            dir.store().get_profile_password(1).unwrap().as_deref(),
+            Some("s3cret")
+        );
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let dir = TestDir::new();
+        dir.store().create("pass").unwrap();
+        dir.store().lock().unwrap();
+
+        let store = dir.store();
+        assert!(matches!(store.unlock("nope"), Err(AppError::VaultError(_))));
+        assert!(!store.is_unlocked().unwrap());
+    }
+
+    #[test]
+    fn tampered_ciphertext_is_rejected() {
+        let dir = TestDir::new();
+        let store = dir.store();
+        store.create("pass").unwrap();
+        store
+            .set_profile_password(1, Some("s3cret".into()))
+            .unwrap();
+
+        dir.edit_json("jmssh.vault", |v| {
+            let mut bytes = B64.decode(v["ciphertext"].as_str().unwrap()).unwrap();
+            bytes[0] ^= 1;
+            v["ciphertext"] = B64.encode(bytes).into();
+        });
+        assert!(matches!(
+            dir.store().get_profile_password(1),
+            Err(AppError::VaultError(_))
+        ));
+    }
+
+    #[test]
+    fn tampered_header_is_rejected() {
+        let dir = TestDir::new();
+        dir.store().create("pass").unwrap();
+
+        // 换了 salt / 成本参数，派生出来的 key 就解不开密文
+        dir.edit_json("jmssh.vault", |v| {
+            v["kdf"]["salt"] = B64.encode([7u8; SALT_LEN]).into();
+        });
+        assert!(dir.store().unlock("pass").is_err());
+
+        dir.edit_json("jmssh.vault", |v| v["version"] = 99.into());
+        assert!(dir.store().unlock("pass").is_err());
+    }
+
+    #[test]
+    fn rekey_invalidates_the_old_cached_key() {
+        let dir = TestDir::new();
+        dir.store().create("old").unwrap();
+        let stale = dir.0.join("stale.key");
+        fs::copy(dir.0.join("vault.key"), &stale).unwrap();
+
+        dir.store().rekey("old", "new").unwrap();
+
+        assert!(!dir.store_with_cache(Some(stale)).is_unlocked().unwrap());
+        assert!(dir.store().is_unlocked().unwrap());
+        assert!(dir.store().unlock("old").is_err());
+        dir.store().unlock("new").unwrap();
+    }
+
+    #[test]
+    fn expired_session_cache_counts_as_locked() {
+        let dir = TestDir::new();
+        dir.store().create("pass").unwrap();
+        dir.edit_json("vault.key", |v| v["expires_at"] = 1.into());
+
+        assert!(!dir.store().is_unlocked().unwrap());
+        assert!(!dir.0.join("vault.key").exists());
+    }
+
+    #[test]
+    fn without_a_session_dir_the_key_is_never_written() {
+        let dir = TestDir::new();
+        let store = dir
+            .store_with_cache(None)
+            .with_prompt(|| Ok("pass".to_string()));
+        store.create("pass").unwrap();
+        store
+            .set_profile_password(1, Some("s3cret".into()))
+            .unwrap();
+        assert!(!dir.0.join("vault.key").exists());
+
+        // 新进程：没有缓存，现场问口令
+        let store = dir
+            .store_with_cache(None)
+            .with_prompt(|| Ok("pass".to_string()));
+        assert_eq!(
+            store.get_profile_password(1).unwrap().as_deref(),
+            Some("s3cret")
+        );
+        // 不问口令的读取在锁着时拿不到
+        assert_eq!(dir.store_with_cache(None).peek_profile_password(1), None);
+    }
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let dir = TestDir::new();
+        dir.store().create("pass").unwrap();
+
+        std::thread::scope(|s| {
+            for t in 0..4u32 {
+                let dir = &dir;
+                s.spawn(move || {
+                    let store = dir.store();
+                    for i in 0..10 {
+                        let id = t * 100 + i;
+                        store
+                            .set_profile_password(id, Some(format!("pw{id}")))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let store = dir.store();
+        for t in 0..4u32 {
+            for i in 0..10 {
+                let id = t * 100 + i;
+                assert_eq!(
+                    store.get_profile_password(id).unwrap(),
+                    Some(format!("pw{id}"))
+                );
+            }
+        }
+    }
+}
@@ -1,40 +1,74 @@
 use app::AppContext;
 use clap::Parser;
-use cli::Command;
+use cli::{Command, DbCommand};
 use std::io::IsTerminal;
+use std::process::ExitCode;
 
 mod app;
 mod cli;
+mod config;
 mod db;
 mod entity;
 mod error;
 mod handlers;
 mod infra;
 mod term;
+#[cfg(test)]
+mod test_support;
 mod ui;
 mod usecase;
 
 use crate::cli::Cli;
-use crate::db::init_schema;
-use crate::term::{c_accent, log_error, log_info, log_warn};
+use crate::config::Config;
+use crate::error::{AppError, exit_code};
+use crate::term::{c_accent, c_error, log_error, log_info, log_warn};
 use crate::ui::tui::connect::pick_profile_for_connect;
 use anyhow::Result;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let cli = cli::Cli::parse();
 
+    match run(cli).await {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            log_error(format!("{} {e:#}", c_error("error:")));
+            // AppError 有各自的退出码，其余（比如找不到数据目录）统一记为 1
+            ExitCode::from(
+                e.downcast_ref::<AppError>()
+                    .map(AppError::exit_code)
+                    .unwrap_or(exit_code::FAILURE),
+            )
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<u8> {
+    // `jmssh config ...` 不碰数据库，config.toml 坏了也得能用它来修
+    if let Some(Command::Config(args)) = cli.command {
+        handlers::config::handle_config(args)?;
+        return Ok(0);
+    }
+
+    let config = Config::load()?;
+    term::set_color_mode(config.ui.color);
+
     let db = db::connect_db().await?;
-    let ctx = AppContext::new(db);
+    // `jmssh db ...` 自己决定要不要迁移，其余命令启动时自动升级
+    if !matches!(cli.command, Some(Command::Db(_))) {
+        handlers::db::auto_migrate(&db).await?;
+    }
+    let ctx = AppContext::new(db, config)?;
     dispatch(&ctx, cli).await
 }
 
-async fn dispatch(ctx: &AppContext, cli: Cli) -> Result<()> {
+/// 返回进程退出码；`connect` 路径上是 ssh 自己的退出码
+async fn dispatch(ctx: &AppContext, cli: Cli) -> Result<u8> {
     let is_tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
 
     if cli.interactive && !is_tty {
         log_error(c_accent("Error: --interactive requires a TTY."));
-        std::process::exit(1);
+        return Ok(exit_code::USAGE);
     }
 
     let can_interactive = !cli.no_interactive && is_tty;
@@ -46,60 +80,113 @@ async fn dispatch(ctx: &AppContext, cli: Cli) -> Result<()> {
                     "Error: Missing subcommand in non-interactive mode.",
                 ));
                 log_info(c_accent("Try 'jmssh --help' for usage."));
-                std::process::exit(1);
+                return Ok(exit_code::USAGE);
             }
 
-            if let Some(p) = pick_profile_for_connect(ctx).await? {
-                handlers::connect::handle_connect(
+            // 在 picker 里取消不算失败
+            match pick_profile_for_connect(ctx).await? {
+                Some(p) => Ok(handlers::connect::handle_connect(
                     ctx,
                     cli::ConnectArgs {
                         target: Some(p.label),
                         id: Some(p.id),
+                        ..Default::default()
                     },
                 )
-                .await?;
+                .await?),
+                None => Ok(0),
             }
-            Ok(())
         }
         Some(Command::Init) => {
-            init_schema(&ctx.db).await?;
-            Ok(())
+            // 启动时已经迁移过，这里只报告一下结果
+            handlers::db::handle_db(
+                ctx,
+                cli::DbArgs {
+                    cmd: DbCommand::Status,
+                },
+            )
+            .await?;
+            Ok(0)
         }
         Some(Command::Gui(_)) => {
             log_warn("gui command is not implemented yet");
-            Ok(())
+            Ok(0)
         }
         Some(Command::Password(args)) => {
             handlers::password::handle_password(ctx, args).await?;
-            Ok(())
+            Ok(0)
+        }
+        Some(Command::Vault(args)) => {
+            handlers::vault::handle_vault(args).await?;
+            Ok(0)
+        }
+        Some(Command::Db(args)) => {
+            handlers::db::handle_db(ctx, args).await?;
+            Ok(0)
+        }
+        Some(Command::History(args)) => {
+            handlers::history::handle_history(ctx, args).await?;
+            Ok(0)
+        }
+        Some(Command::Last) => Ok(handlers::history::handle_last(ctx).await?),
+        Some(Command::Import(args)) => {
+            handlers::import::handle_import(ctx, args).await?;
+            Ok(0)
+        }
+        Some(Command::Export(args)) => {
+            handlers::export::handle_export(ctx, args).await?;
+            Ok(0)
+        }
+        Some(Command::Tags) => {
+            handlers::tag::handle_tags(ctx).await?;
+            Ok(0)
         }
         Some(Command::Profile(args)) => {
             handlers::profile::handle_profile(ctx, args).await?;
-            Ok(())
+            Ok(0)
         }
         Some(Command::Connect(args)) => {
             if args.target.is_none() {
                 if !can_interactive {
                     log_error(c_accent("Error: Missing target in non-interactive mode."));
                     log_info(c_accent("Try 'jmssh connect --help' for usage."));
-                    std::process::exit(1);
+                    return Ok(exit_code::USAGE);
                 }
 
-                if let Some(p) = pick_profile_for_connect(ctx).await? {
-                    handlers::connect::handle_connect(
+                match pick_profile_for_connect(ctx).await? {
+                    Some(p) => Ok(handlers::connect::handle_connect(
                         ctx,
                         cli::ConnectArgs {
                             target: Some(p.label),
                             id: Some(p.id),
+                            ..Default::default()
                         },
                     )
-                    .await?;
+                    .await?),
+                    None => Ok(0),
                 }
             } else {
-                handlers::connect::handle_connect(ctx, args).await?;
+                Ok(handlers::connect::handle_connect(ctx, args).await?)
+            }
+        }
+        Some(Command::Cmd(args)) => {
+            // 输出是给 eval / 管道用的，不弹 picker
+            if args.target.is_none() {
+                log_error(c_accent("Error: Missing target."));
+                log_info(c_accent("Try 'jmssh cmd --help' for usage."));
+                return Ok(exit_code::USAGE);
             }
-            Ok(())
+            Ok(handlers::connect::handle_connect(
+                ctx,
+                cli::ConnectArgs {
+                    print: !args.dry_run,
+                    ..args
+                },
+            )
+            .await?)
         }
-        Some(Command::_Complete(_)) => Ok(()),
+        // run() 里已经处理掉了
+        Some(Command::Config(_)) => Ok(0),
+        Some(Command::_Complete(_)) => Ok(0),
     }
 }
@@ -1,7 +1,20 @@
+use crate::config::ColorMode;
 use atty::Stream;
+use std::sync::OnceLock;
+
+static COLOR_MODE: OnceLock<ColorMode> = OnceLock::new();
+
+/// 启动时按配置设置一次；没设置过就是 auto
+pub fn set_color_mode(mode: ColorMode) {
+    let _ = COLOR_MODE.set(mode);
+}
 
 pub fn use_color() -> bool {
-    atty::is(Stream::Stderr) && std::env::var_os("NO_COLOR").is_none()
+    match COLOR_MODE.get().copied().unwrap_or_default() {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => atty::is(Stream::Stderr) && std::env::var_os("NO_COLOR").is_none(),
+    }
 }
 
 pub fn color(code: &str, text: &str) -> String {
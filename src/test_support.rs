@@ -0,0 +1,115 @@
+//! 测试共用的小工具（只在 `cargo test` 时编译）
+
+use crate::app::AppContext;
+use crate::config::Config;
+use crate::db::migrations;
+use crate::error::{AppError, AppResult};
+use crate::infra::password_store::PasswordStore;
+use sea_orm::Database;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 每个测试一个独立的临时目录，Drop 时删掉
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static SEQ: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "jmssh-{name}-test-{}-{}",
+            std::process::id(),
+            SEQ.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    /// 写一个文件（需要的上级目录一并建好），返回完整路径
+    pub fn write(&self, rel: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.0.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 带 BOM 的 UTF-16LE，Windows 客户端导出的文件常见这种编码
+pub fn utf16le(text: &str) -> Vec<u8> {
+    let mut out = vec![0xFF, 0xFE];
+    out.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    out
+}
+
+/// 内存里的密码库；`fail_writes` 打开后写入一律失败（模拟 vault 锁着）
+#[derive(Default)]
+pub struct MemoryPasswordStore {
+    secrets: Mutex<HashMap<u32, String>>,
+    pub fail_writes: AtomicBool,
+}
+
+impl MemoryPasswordStore {
+    pub fn ids(&self) -> Vec<u32> {
+        let mut ids = self
+            .secrets
+            .lock()
+            .unwrap()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+}
+
+impl PasswordStore for MemoryPasswordStore {
+    fn set_profile_password(&self, profile_id: u32, password: Option<String>) -> AppResult<()> {
+        if self.fail_writes.load(Ordering::Relaxed) {
+            return Err(AppError::VaultLocked);
+        }
+        let mut secrets = self.secrets.lock().unwrap();
+        match password {
+            Some(p) => secrets.insert(profile_id, p),
+            None => secrets.remove(&profile_id),
+        };
+        Ok(())
+    }
+
+    fn get_profile_password(&self, profile_id: u32) -> AppResult<Option<String>> {
+        Ok(self.secrets.lock().unwrap().get(&profile_id).cloned())
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "memory"
+    }
+}
+
+/// 迁移好的内存数据库 + 内存密码库 + 默认配置
+pub async fn test_context() -> (AppContext, Arc<MemoryPasswordStore>) {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    migrations::migrate(&db).await.unwrap();
+    let store = Arc::new(MemoryPasswordStore::default());
+    let ctx = AppContext {
+        db,
+        password_store: store.clone(),
+        config: Config::default(),
+    };
+    (ctx, store)
+}
@@ -1,12 +1,16 @@
 use crate::app::AppContext;
+use crate::config::PickerLayout;
 use crate::error::AppResult;
 use crate::usecase;
 use crate::usecase::ProfileView;
+use crate::usecase::fuzzy::fuzzy_match;
+use crate::usecase::query::{Query, text_matches};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use crossterm::execute;
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
+use itertools::Itertools;
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Direction, Layout};
@@ -22,31 +26,45 @@ const MARQUEE_HOLD_TICKS: u64 = 8;
 const MARQUEE_GAP_CHARS: usize = 6;
 
 pub async fn pick_profile_for_connect(ctx: &AppContext) -> AppResult<Option<ProfileView>> {
-    let profiles = usecase::profile::list_profiles(ctx).await?;
+    let mut profiles = usecase::profile::list_profiles(ctx).await?;
     if profiles.is_empty() {
         return Ok(None);
     }
+    // 先按配置排好，模糊匹配同分时就保持这个顺序
+    usecase::profile::sort_profiles(ctx, &mut profiles, ctx.config.ui.picker_sort).await?;
 
-    let mut state = PickerState::new(profiles);
+    let mut state = PickerState::new(profiles, ctx.config.ui.picker_layout);
     run_picker(&mut state)
 }
 
+/// 过滤 + 排序后的一行：指回 profiles 的下标，带上模糊匹配命中的位置（用来高亮）
+struct RankedRow {
+    idx: usize,
+    score: i32,
+    label_hits: Vec<usize>,
+    dest_hits: Vec<usize>,
+}
+
 struct PickerState {
     profiles: Vec<ProfileView>,
-    filtered: Vec<usize>,
+    filtered: Vec<RankedRow>,
     selected: usize,
     query: String,
     marquee_tick: u64,
+    layout: PickerLayout,
+    query_error: Option<String>,
 }
 
 impl PickerState {
-    fn new(profiles: Vec<ProfileView>) -> Self {
+    fn new(profiles: Vec<ProfileView>, layout: PickerLayout) -> Self {
         let mut state = Self {
             profiles,
             filtered: Vec::new(),
             selected: 0,
             query: String::new(),
             marquee_tick: 0,
+            layout,
+            query_error: None,
         };
         state.refilter();
         state
@@ -61,41 +79,40 @@ impl PickerState {
     }
 
     fn refilter(&mut self) {
-        let q = self.query.trim().to_ascii_lowercase();
+        // 查询语法见 usecase::query；写到一半的端口区间之类解析失败时，先什么都不显示
+        let query = match Query::parse(&self.query) {
+            Ok(q) => {
+                self.query_error = None;
+                q
+            }
+            Err(e) => {
+                self.query_error = Some(e.to_string());
+                self.filtered.clear();
+                self.selected = 0;
+                return;
+            }
+        };
 
+        let terms = query.text_terms().collect_vec();
         self.filtered = self
             .profiles
             .iter()
             .enumerate()
-            .filter(|(_, p)| {
-                if q.is_empty() {
-                    return true;
-                }
-
-                let target = format!("{} {} {} {} {}", p.label, p.user, p.host, p.port, p.mode,)
-                    .to_ascii_lowercase();
-
-                target.contains(&q)
-            })
-            .map(|(idx, _)| idx)
+            .filter(|(_, p)| query.matches_structured(p))
+            .filter_map(|(idx, p)| rank_profile(idx, p, &terms))
+            // 分数高的在前；同分保持 ui.picker_sort 排好的顺序（默认最近连接的在前）
+            .sorted_by(|a, b| b.score.cmp(&a.score).then_with(|| a.idx.cmp(&b.idx)))
             .collect();
 
-        if self.filtered.is_empty() {
-            self.selected = 0;
-            return;
-        }
-
-        if self.selected >= self.filtered.len() {
-            self.selected = self.filtered.len() - 1;
-        }
-
+        // 排序变了，选中项回到最匹配的那一行
+        self.selected = 0;
         self.reset_marquee();
     }
 
     fn selected_profile(&self) -> Option<ProfileView> {
         self.filtered
             .get(self.selected)
-            .and_then(|idx| self.profiles.get(*idx))
+            .and_then(|row| self.profiles.get(row.idx))
             .cloned()
     }
 
@@ -252,10 +269,20 @@ fn draw_picker(f: &mut ratatui::Frame, state: &PickerState) {
     );
     f.render_widget(query_line, chunks[0]);
 
-    let body_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(68), Constraint::Percentage(32)])
-        .split(chunks[1]);
+    // split: 详情在右；stacked: 详情在下；list: 不要详情
+    let body_chunks = match state.layout {
+        PickerLayout::Split => Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(68), Constraint::Percentage(32)])
+            .split(chunks[1]),
+        PickerLayout::Stacked => Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(9)])
+            .split(chunks[1]),
+        PickerLayout::List => Layout::default()
+            .constraints([Constraint::Min(1)])
+            .split(chunks[1]),
+    };
 
     let profiles_block = Block::default().title("Profiles").borders(Borders::ALL);
     let profiles_inner = profiles_block.inner(body_chunks[0]);
@@ -279,11 +306,12 @@ fn draw_picker(f: &mut ratatui::Frame, state: &PickerState) {
         .filtered
         .iter()
         .enumerate()
-        .map(|(visible_idx, idx)| {
-            let p = &state.profiles[*idx];
+        .map(|(visible_idx, row)| {
+            let p = &state.profiles[row.idx];
             let selected = !state.filtered.is_empty() && state.selected == visible_idx;
             ListItem::new(Line::from(format_profile_row(
                 p,
+                row,
                 list_width,
                 selected,
                 state.marquee_tick,
@@ -292,8 +320,12 @@ fn draw_picker(f: &mut ratatui::Frame, state: &PickerState) {
         .collect();
 
     if items.is_empty() {
+        let msg = match &state.query_error {
+            Some(e) => e.clone(),
+            None => "No profiles match current query".to_string(),
+        };
         items.push(ListItem::new(Line::from(vec![Span::styled(
-            "No profiles match current query",
+            msg,
             Style::default().fg(Color::DarkGray),
         )])));
     }
@@ -318,7 +350,9 @@ fn draw_picker(f: &mut ratatui::Frame, state: &PickerState) {
                 .borders(Borders::ALL),
         )
         .wrap(Wrap { trim: false });
-    f.render_widget(detail_widget, body_chunks[1]);
+    if let Some(area) = body_chunks.get(1) {
+        f.render_widget(detail_widget, *area);
+    }
 
     let hint = Paragraph::new(
         "Enter confirm | Esc/Ctrl-C cancel | ↑/↓ or j/k move | Backspace edit | Ctrl-U clear",
@@ -354,8 +388,13 @@ fn detail_lines(state: &PickerState) -> Vec<Line<'static>> {
         ];
     };
 
-    let tags = p.tags.clone().unwrap_or_else(|| "-".to_string());
+    let tags = if p.tags.is_empty() {
+        "-".to_string()
+    } else {
+        p.tags.join(", ")
+    };
     let note = p.note.clone().unwrap_or_else(|| "-".to_string());
+    let key = p.key_path.clone().unwrap_or_else(|| "-".to_string());
     let endpoint = format!("{}@{}:{}", p.user, p.host, p.port);
 
     vec![
@@ -383,6 +422,10 @@ fn detail_lines(state: &PickerState) -> Vec<Line<'static>> {
             Span::styled("Dest : ", Style::default().fg(Color::Gray)),
             Span::raw(endpoint),
         ]),
+        Line::from(vec![
+            Span::styled("Key  : ", Style::default().fg(Color::Gray)),
+            Span::raw(key),
+        ]),
         Line::from(vec![
             Span::styled("Tags : ", Style::default().fg(Color::Gray)),
             Span::raw(tags),
@@ -394,29 +437,116 @@ fn detail_lines(state: &PickerState) -> Vec<Line<'static>> {
     ]
 }
 
+fn dest_text(p: &ProfileView) -> String {
+    format!("{}@{}:{}", p.user, p.host, p.port)
+}
+
+/// 每个普通词都要在 label 或 dest 里模糊命中，取两者中分高的那个；总分是各词之和。
+/// 都不中时退回到全字段子串匹配（tags、note、mode），命中不加分，排在模糊命中的后面
+fn rank_profile(idx: usize, p: &ProfileView, terms: &[&str]) -> Option<RankedRow> {
+    let dest = dest_text(p);
+    let mut row = RankedRow {
+        idx,
+        score: 0,
+        label_hits: Vec::new(),
+        dest_hits: Vec::new(),
+    };
+
+    for term in terms {
+        let on_label = fuzzy_match(term, &p.label);
+        let on_dest = fuzzy_match(term, &dest);
+        match (on_label, on_dest) {
+            (Some(l), Some(d)) if d.score > l.score => {
+                row.score += d.score;
+                row.dest_hits.extend(d.positions);
+            }
+            (Some(l), _) => {
+                row.score += l.score;
+                row.label_hits.extend(l.positions);
+            }
+            (None, Some(d)) => {
+                row.score += d.score;
+                row.dest_hits.extend(d.positions);
+            }
+            (None, None) if text_matches(term, p) => {}
+            (None, None) => return None,
+        }
+    }
+
+    Some(row)
+}
+
 fn format_profile_row(
     p: &ProfileView,
+    row: &RankedRow,
     max_width: usize,
     selected: bool,
     marquee_tick: u64,
-) -> String {
+) -> Vec<Span<'static>> {
     if max_width == 0 {
-        return String::new();
+        return Vec::new();
     }
 
     let sep = "  ";
     let sep_total = sep.len() * 2;
     let Some((label_w, dest_w, mode_w)) = compute_col_widths(max_width, sep_total) else {
         let compact = format!("{}@{}:{} ({})", p.user, p.host, p.port, p.mode);
-        return truncate_with_ellipsis(&compact, max_width);
+        return vec![Span::raw(truncate_with_ellipsis(&compact, max_width))];
     };
 
     let label = fit_col(&p.label, label_w);
-    let dest_raw = format!("{}@{}:{}", p.user, p.host, p.port);
+    let dest_raw = dest_text(p);
     let dest = fit_dest_col(&dest_raw, dest_w, selected, marquee_tick);
     let mode = fit_col(&p.mode, mode_w);
 
-    format!("{label}{sep}{dest}{sep}{mode}")
+    // 跑马灯滚动中的 dest 字符位置已经错开了，不高亮
+    let dest_scrolling = dest != fit_col(&dest_raw, dest_w);
+    let dest_hits: &[usize] = if dest_scrolling { &[] } else { &row.dest_hits };
+
+    let mut spans = highlight_spans(&label, &p.label, &row.label_hits);
+    spans.push(Span::raw(sep));
+    spans.extend(highlight_spans(&dest, &dest_raw, dest_hits));
+    spans.push(Span::raw(sep));
+    spans.push(Span::raw(mode));
+    spans
+}
+
+/// `fitted` 是 `original` 截断 / 补空格后的样子；只有和原文一致的前缀部分才能按位置高亮。
+/// 选中行会被 List 的 highlight_style 整行改色，所以命中字符额外加下划线
+fn highlight_spans(fitted: &str, original: &str, hits: &[usize]) -> Vec<Span<'static>> {
+    if hits.is_empty() {
+        return vec![Span::raw(fitted.to_string())];
+    }
+
+    let shared = fitted
+        .chars()
+        .zip(original.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let truncated = fitted.ends_with("...") && fitted.trim_end() != original;
+    let visible = if truncated {
+        shared.min(fitted.chars().count().saturating_sub(3))
+    } else {
+        shared
+    };
+
+    let hit_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+    fitted
+        .chars()
+        .enumerate()
+        .chunk_by(|(i, _)| *i < visible && hits.contains(i))
+        .into_iter()
+        .map(|(hit, group)| {
+            let text = group.map(|(_, c)| c).collect::<String>();
+            match hit {
+                true => Span::styled(text, hit_style),
+                false => Span::raw(text),
+            }
+        })
+        .collect()
 }
 
 fn compute_col_widths(max_width: usize, sep_total: usize) -> Option<(usize, usize, usize)> {
@@ -516,3 +646,29 @@ fn truncate_with_ellipsis(s: &str, width: usize) -> String {
     out.push_str("...");
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_terms_also_match_tags_note_and_mode() {
+        let p = ProfileView {
+            note: Some("billing api".to_string()),
+            ..ProfileView::fixture("web", "password", &["staging"])
+        };
+        for term in ["staging", "billing", "password"] {
+            assert!(rank_profile(0, &p, &[term]).is_some(), "{term}");
+        }
+        assert!(rank_profile(0, &p, &["nomatch"]).is_none());
+    }
+
+    #[test]
+    fn fuzzy_hits_rank_above_field_hits() {
+        let by_label = ProfileView::fixture("staging-web", "agent", &[]);
+        let by_tag = ProfileView::fixture("web", "agent", &["staging"]);
+        let label_score = rank_profile(0, &by_label, &["staging"]).unwrap().score;
+        let tag_score = rank_profile(1, &by_tag, &["staging"]).unwrap().score;
+        assert!(label_score > tag_score);
+    }
+}
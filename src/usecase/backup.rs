@@ -0,0 +1,145 @@
+//! jmssh 自己的备份格式：`jmssh export --format json|toml` 写出，`jmssh import <file>` 读回。
+//!
+//! 文档带版本号；跳板按 label 引用，不依赖数据库 id，换一台机器导入也能连上。
+//! 密码默认不导出，`--include-secrets` 才带上。导出 -> 导入 -> 再导出，内容不变。
+
+use crate::app::AppContext;
+use crate::entity::profiles::AuthMode;
+use crate::error::{AppError, AppResult};
+use crate::usecase::export::export_profiles;
+use crate::usecase::{ImportBatch, ImportedProfile};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// 改了字段含义就加一；读的时候拒绝比自己新的版本
+pub const BACKUP_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BackupFormat {
+    Json,
+    Toml,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupDocument {
+    pub version: u32,
+    #[serde(default)]
+    pub profiles: Vec<BackupProfile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupProfile {
+    pub label: String,
+    pub host: String,
+    pub user: String,
+    pub port: u16,
+    /// agent | password | key
+    pub mode: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_path: Option<String>,
+    /// 直接跳板的 label，按顺序
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jumps: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// TOML 里表要放在普通字段后面
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
+}
+
+pub async fn build_document(ctx: &AppContext, include_secrets: bool) -> AppResult<BackupDocument> {
+    let mut profiles = Vec::new();
+
+    for e in export_profiles(ctx).await? {
+        let p = e.profile;
+        let password = if include_secrets {
+            ctx.password_store.get_profile_password(p.id)?
+        } else {
+            None
+        };
+        profiles.push(BackupProfile {
+            label: p.label,
+            host: p.host,
+            user: p.user,
+            port: p.port,
+            mode: p.mode,
+            tags: p.tags,
+            note: p.note,
+            key_path: p.key_path,
+            jumps: e.jumps,
+            password,
+            options: p.options.into_iter().collect(),
+        });
+    }
+
+    Ok(BackupDocument {
+        version: BACKUP_VERSION,
+        profiles,
+    })
+}
+
+pub fn encode(doc: &BackupDocument, format: BackupFormat) -> AppResult<String> {
+    match format {
+        BackupFormat::Json => serde_json::to_string_pretty(doc)
+            .map(|s| s + "\n")
+            .map_err(|e| AppError::IoError(e.to_string())),
+        BackupFormat::Toml => {
+            toml::to_string_pretty(doc).map_err(|e| AppError::IoError(e.to_string()))
+        }
+    }
+}
+
+pub fn decode(text: &str, format: BackupFormat) -> AppResult<BackupDocument> {
+    let doc = match format {
+        BackupFormat::Json => serde_json::from_str::<BackupDocument>(text)
+            .map_err(|e| AppError::InvalidImport(e.to_string()))?,
+        BackupFormat::Toml => toml::from_str::<BackupDocument>(text)
+            .map_err(|e| AppError::InvalidImport(e.to_string()))?,
+    };
+
+    if doc.version == 0 || doc.version > BACKUP_VERSION {
+        return Err(AppError::InvalidImport(format!(
+            "document version {} is not supported (this jmssh reads up to {BACKUP_VERSION})",
+            doc.version
+        )));
+    }
+    Ok(doc)
+}
+
+/// 备份里的 profile 都是完整描述，覆盖时按原样替换
+pub fn document_to_batch(doc: BackupDocument) -> AppResult<ImportBatch> {
+    let mut seen = HashSet::new();
+    let mut profiles = Vec::new();
+
+    for p in doc.profiles {
+        if !seen.insert(p.label.clone()) {
+            return Err(AppError::InvalidImport(format!(
+                "label {} appears more than once",
+                p.label
+            )));
+        }
+        profiles.push(ImportedProfile {
+            auth_mode: Some(AuthMode::from_str(Some(&p.mode))?),
+            label: p.label,
+            host: p.host,
+            user: Some(p.user),
+            port: Some(p.port),
+            key_path: p.key_path,
+            jumps: p.jumps,
+            tags: p.tags,
+            note: p.note,
+            options: p.options.into_iter().collect(),
+            password: p.password,
+        });
+    }
+
+    Ok(ImportBatch {
+        profiles,
+        warnings: Vec::new(),
+        complete: true,
+    })
+}
@@ -1,41 +1,70 @@
 use crate::app::AppContext;
 use crate::entity;
-use crate::entity::{profiles, routes};
+use crate::entity::profiles;
+use crate::entity::profiles::AuthMode;
 use crate::error::{AppError, AppResult};
-use crate::usecase::ConnectPlan;
-use crate::usecase::{ConnectHop, ConnectInput};
-use itertools::Itertools;
+use crate::usecase::{ConnectHop, ConnectInput, ConnectPlan, EditProfileInput, ProfileView};
+use crate::usecase::{profile, route};
+use sea_orm::ColumnTrait;
 use sea_orm::EntityTrait;
 use sea_orm::QueryFilter;
-use sea_orm::{ColumnTrait, QueryOrder};
 use std::collections::HashMap;
 
 pub async fn build_connect_plan(ctx: &AppContext, input: ConnectInput) -> AppResult<ConnectPlan> {
+    let ad_hoc_flags = !input.jumps.is_empty() || input.mode.is_some() || input.save_as.is_some();
+
     // 1) 解析主 profile：优先 id，其次 label
     let base_profile = if let Some(id) = input.id {
-        profiles::Entity::find_by_id(id)
-            .one(&ctx.db)
-            .await?
-            .ok_or(AppError::ProfileNotFound(format!("#{id}")))? // 复用现有错误
+        Some(
+            profiles::Entity::find_by_id(id)
+                .one(&ctx.db)
+                .await?
+                .ok_or(AppError::ProfileNotFound(format!("#{id}")))?, // 复用现有错误
+        )
     } else {
-        profiles::Entity::find()
-            .filter(profiles::Column::Label.eq(input.target.clone()))
-            .one(&ctx.db)
-            .await?
-            .ok_or(AppError::ProfileNotFound(input.target.clone()))?
+        find_profile_by_label(ctx, &input.target).await?
     };
 
-    let base_id = base_profile.id;
+    match base_profile {
+        Some(_) if ad_hoc_flags => Err(AppError::InvalidDestination(format!(
+            "{} is a saved profile; --jump/--mode/--save-as only apply to raw destinations \
+             (use `jmssh profile set` instead)",
+            input.target
+        ))),
+        Some(p) => Ok(ConnectPlan {
+            hops: profile_hops(ctx, p).await?,
+        }),
+        // 找不到 label 时，只有明确是地址（带 `@` / `:` / `[`）或给了 --jump / --mode 的才当作临时目标，
+        // 免得拼错的 label（`prod.web` 这种带点的也一样）变成一次 DNS 查询
+        None if !input.jumps.is_empty()
+            || input.mode.is_some()
+            || looks_like_destination(&input.target) =>
+        {
+            ad_hoc_plan(ctx, &input).await
+        }
+        None => Err(AppError::ProfileNotFound(input.target.clone())),
+    }
+}
 
-    // 2) 查出这条 profile 的 routes（按 seq 排序）
-    let route_rows = routes::Entity::find()
-        .filter(routes::Column::ProfileId.eq(base_id))
-        .order_by_asc(routes::Column::Seq)
-        .all(&ctx.db)
-        .await?;
+async fn find_profile_by_label(
+    ctx: &AppContext,
+    label: &str,
+) -> AppResult<Option<profiles::Model>> {
+    Ok(profiles::Entity::find()
+        .filter(profiles::Column::Label.eq(label))
+        .one(&ctx.db)
+        .await?)
+}
 
-    // route 里的 via_profile_id 顺序就是 jump 链
-    let via_ids = route_rows.iter().map(|r| r.via_profile_id).collect_vec();
+/// 已保存 profile 的完整 hop 列表：展开后的跳板链 + 自己
+async fn profile_hops(
+    ctx: &AppContext,
+    base_profile: profiles::Model,
+) -> AppResult<Vec<ConnectHop>> {
+    let base_id = base_profile.id;
+
+    // 2) 递归展开跳板链（跳板自己的 jump 也算进来），顺带做环 / 深度校验
+    let via_ids = route::resolve_jump_chain(&ctx.db, base_id).await?;
 
     // 3) 需要涉及的全部 profile id = 所有 jump + 自己
     let all_profile_ids = [via_ids.clone(), vec![base_id]].concat();
@@ -67,6 +96,9 @@ pub async fn build_connect_plan(ctx: &AppContext, input: ConnectInput) -> AppRes
         .map(|r| (r.profile_id, r.key_path_local))
         .collect::<HashMap<_, _>>();
 
+    // 5.1 每一跳自己的 ssh 选项
+    let option_map = profile::options_for_profiles(&ctx.db, &all_profile_ids).await?;
+
     // 6) 按顺序组装 hops：先 jumps，再最终目标
     let hops = via_ids
         .into_iter()
@@ -79,26 +111,169 @@ pub async fn build_connect_plan(ctx: &AppContext, input: ConnectInput) -> AppRes
             Ok::<_, AppError>(build_connect_hop(
                 p,
                 key_map.get(&via_id).cloned().unwrap_or(None),
+                option_map.get(&via_id).cloned().unwrap_or_default(),
             ))
         })
         // 6.2 最终目标
         .chain(std::iter::once(Ok(build_connect_hop(
             &base_profile,
             key_map.get(&base_id).cloned().unwrap_or(None),
+            option_map.get(&base_id).cloned().unwrap_or_default(),
         ))))
         .collect::<Result<Vec<_>, AppError>>()?; // 这里再用 ? 往外抛 AppError
 
+    Ok(hops)
+}
+
+/// 不落库的临时连接：目标和 `--jump` 都可以是原始地址，`--jump` 里的 label 会带上它自己的跳板链
+async fn ad_hoc_plan(ctx: &AppContext, input: &ConnectInput) -> AppResult<ConnectPlan> {
+    let dest = parse_destination(&input.target)?;
+    let auth_mode = AuthMode::from_str(input.mode.as_deref())?;
+
+    if let Some(label) = &input.save_as
+        && find_profile_by_label(ctx, label).await?.is_some()
+    {
+        return Err(AppError::ProfileAlreadyExists(label.clone()));
+    }
+
+    let mut hops = Vec::new();
+    for jump in &input.jumps {
+        match find_profile_by_label(ctx, jump).await? {
+            Some(p) => hops.extend(profile_hops(ctx, p).await?),
+            // routes 只能指向 profile，原始地址的跳板没法存下来
+            None if input.save_as.is_some() => {
+                return Err(AppError::ProfileRouteTargetNotFound(jump.clone()));
+            }
+            None => hops.push(parse_destination(jump)?.into_hop(jump, AuthMode::Agent)),
+        }
+    }
+    hops.push(dest.into_hop(&input.target, auth_mode));
+
     Ok(ConnectPlan { hops })
 }
 
-fn build_connect_hop(p: &profiles::Model, key_path_local: Option<String>) -> ConnectHop {
+/// 把一次连上了的临时连接存成 profile（`--save-as`）
+pub async fn save_ad_hoc_profile(
+    ctx: &AppContext,
+    label: String,
+    input: &ConnectInput,
+) -> AppResult<ProfileView> {
+    let dest = parse_destination(&input.target)?;
+    profile::add_profile(
+        ctx,
+        EditProfileInput {
+            label,
+            host: Some(dest.host),
+            user: Some(dest.user),
+            port: dest.port,
+            mode: input.mode.clone(),
+            tags: None,
+            tag_changes: Vec::new(),
+            notes: None,
+            key_path: None,
+            clear_key: false,
+            jumps: input.jumps.clone(),
+            options: Vec::new(),
+            unset_options: Vec::new(),
+        },
+    )
+    .await
+}
+
+/// 原始目标 `[user@]host[:port]`，IPv6 写成 `[::1]:2222`
+#[derive(Debug, Clone)]
+pub struct Destination {
+    pub user: String,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl Destination {
+    fn into_hop(self, raw: &str, auth_mode: AuthMode) -> ConnectHop {
+        ConnectHop {
+            id: None,
+            label: raw.to_string(),
+            host: self.host,
+            user: self.user,
+            port: self.port.unwrap_or(22),
+            auth_mode,
+            key_path_local: None,
+            options: Vec::new(),
+        }
+    }
+}
+
+fn looks_like_destination(s: &str) -> bool {
+    s.contains(['@', ':', '['])
+}
+
+pub fn parse_destination(raw: &str) -> AppResult<Destination> {
+    let invalid = |why: &str| AppError::InvalidDestination(format!("{raw}: {why}"));
+
+    let (user, rest) = match raw.rsplit_once('@') {
+        Some((user, rest)) if !user.is_empty() => (user.to_string(), rest),
+        Some(_) => return Err(invalid("empty user")),
+        // 和 ssh 一样，没写 user 就用本地用户名
+        None => (local_username(), raw),
+    };
+
+    let (host, port) = if let Some(bracketed) = rest.strip_prefix('[') {
+        let (host, after) = bracketed
+            .split_once(']')
+            .ok_or_else(|| invalid("missing closing ']'"))?;
+        match after {
+            "" => (host, None),
+            _ => match after.strip_prefix(':') {
+                Some(port) => (host, Some(port)),
+                None => return Err(invalid("unexpected text after ']'")),
+            },
+        }
+    } else if rest.matches(':').count() > 1 {
+        // 不带方括号的 IPv6 字面量，没有端口
+        (rest, None)
+    } else {
+        match rest.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (rest, None),
+        }
+    };
+
+    if host.is_empty() {
+        return Err(invalid("empty host"));
+    }
+
+    let port = port
+        .map(|p| p.parse::<u16>().ok().filter(|p| *p != 0))
+        .map(|p| p.ok_or_else(|| invalid("invalid port")))
+        .transpose()?;
+
+    Ok(Destination {
+        user,
+        host: host.to_string(),
+        port,
+    })
+}
+
+pub fn local_username() -> String {
+    ["USER", "USERNAME"]
+        .iter()
+        .find_map(|k| std::env::var(k).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| "root".to_string())
+}
+
+fn build_connect_hop(
+    p: &profiles::Model,
+    key_path_local: Option<String>,
+    options: Vec<(String, String)>,
+) -> ConnectHop {
     ConnectHop {
-        id: p.id,
+        id: Some(p.id),
         label: p.label.clone().unwrap_or_else(|| format!("#{}", p.id)),
         host: p.hostname.clone(),
         user: p.username.clone(),
         port: p.port.unwrap_or(22),
         auth_mode: p.auth_mode.clone(),
         key_path_local,
+        options,
     }
 }
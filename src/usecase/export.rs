@@ -0,0 +1,46 @@
+//! 导出：把库里的 profile 整理成完整快照（跳板用 label 表示），具体格式的渲染在 infra 里
+
+use crate::app::AppContext;
+use crate::entity;
+use crate::error::AppResult;
+use crate::usecase::ProfileExport;
+use crate::usecase::profile::{list_profiles, options_for_profiles};
+use itertools::Itertools;
+use sea_orm::{EntityTrait, QueryOrder};
+use std::collections::HashMap;
+
+/// 按 label 排序；每个 profile 只带自己的直接跳板，间接的由跳板自己的链表示
+pub async fn export_profiles(ctx: &AppContext) -> AppResult<Vec<ProfileExport>> {
+    let profiles = list_profiles(ctx).await?;
+
+    let ids = profiles.iter().map(|p| p.id).collect_vec();
+    let mut options = options_for_profiles(&ctx.db, &ids).await?;
+
+    let labels = profiles
+        .iter()
+        .map(|p| (p.id, p.label.clone()))
+        .collect::<HashMap<_, _>>();
+    let mut jumps = entity::routes::Entity::find()
+        .order_by_asc(entity::routes::Column::ProfileId)
+        .order_by_asc(entity::routes::Column::Seq)
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .filter_map(|r| {
+            labels
+                .get(&r.via_profile_id)
+                .map(|l| (r.profile_id, l.clone()))
+        })
+        .into_group_map();
+
+    Ok(profiles
+        .into_iter()
+        .map(|mut p| {
+            p.options = options.remove(&p.id).unwrap_or_default();
+            ProfileExport {
+                jumps: jumps.remove(&p.id).unwrap_or_default(),
+                profile: p,
+            }
+        })
+        .collect())
+}
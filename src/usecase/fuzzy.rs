@@ -0,0 +1,110 @@
+//! fzf 风格的子序列匹配：`pw1` 能命中 `prod.web-1`，分数越高越靠前。
+//!
+//! 每个命中字符有基础分；紧挨着上一个命中、落在词首（开头、`.-_@:/` 之后、
+//! 小写转大写处）有额外加分；中间跳过的字符按距离扣分。
+//! 取分数最高的那一种对齐方式，顺带给出命中位置（按 char 下标）用来高亮。
+
+const SCORE_MATCH: i32 = 16;
+const BONUS_CONSECUTIVE: i32 = 12;
+const BONUS_BOUNDARY: i32 = 10;
+const BONUS_FIRST_CHAR: i32 = 6;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTEND: i32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// 命中字符在 text 里的位置（char 下标，升序）
+    pub positions: Vec<usize>,
+}
+
+/// pattern 为空时视为命中、0 分；不区分大小写
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pat = pattern
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    if pat.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    let chars = text.chars().collect::<Vec<_>>();
+    let lower = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<_>>();
+    let (m, n) = (pat.len(), chars.len());
+    if m > n {
+        return None;
+    }
+
+    let bonus = (0..n).map(|j| char_bonus(&chars, j)).collect::<Vec<_>>();
+
+    // best[i][j]：pattern[..=i] 对齐完、pattern[i] 落在 j 时的最高分；from[i][j] 记上一个位置
+    let mut best = vec![vec![None::<i32>; n]; m];
+    let mut from = vec![vec![0usize; n]; m];
+
+    for i in 0..m {
+        for j in i..n {
+            if lower[j] != pat[i] {
+                continue;
+            }
+            let here = SCORE_MATCH + bonus[j];
+
+            if i == 0 {
+                best[0][j] = Some(here);
+                continue;
+            }
+
+            let prev = (i - 1..j)
+                .filter_map(|k| best[i - 1][k].map(|s| (k, s + transition(k, j))))
+                .max_by_key(|(k, s)| (*s, std::cmp::Reverse(*k)));
+            if let Some((k, s)) = prev {
+                best[i][j] = Some(s + here);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = best[m - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (j, s)))
+        .max_by_key(|(j, s)| (*s, std::cmp::Reverse(*j)))?;
+
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// 从位置 k 的命中跳到 j 的命中：相邻加分，否则按跳过的字符数扣分
+fn transition(k: usize, j: usize) -> i32 {
+    let gap = (j - k - 1) as i32;
+    if gap == 0 {
+        BONUS_CONSECUTIVE
+    } else {
+        -(PENALTY_GAP_START + PENALTY_GAP_EXTEND * (gap - 1))
+    }
+}
+
+fn char_bonus(chars: &[char], j: usize) -> i32 {
+    if j == 0 {
+        return BONUS_BOUNDARY + BONUS_FIRST_CHAR;
+    }
+    let (prev, cur) = (chars[j - 1], chars[j]);
+    let after_separator = matches!(prev, '.' | '-' | '_' | '@' | ':' | '/' | ' ');
+    let camel = prev.is_lowercase() && cur.is_uppercase();
+    let digit_start = !prev.is_ascii_digit() && cur.is_ascii_digit();
+    if after_separator || camel || digit_start {
+        BONUS_BOUNDARY
+    } else {
+        0
+    }
+}
@@ -0,0 +1,174 @@
+//! 连接历史：`handle_connect` 每次真正启动 ssh 记一条，结束时补上退出码。
+//! `jmssh history` / `jmssh last` 和 frecency 排序都从这里取数据。
+
+use crate::app::AppContext;
+use crate::entity;
+use crate::entity::profiles;
+use crate::error::AppResult;
+use crate::usecase::{ConnectPlan, HistoryEntry};
+use itertools::Itertools;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, QueryFilter, Set,
+    Statement,
+};
+use std::collections::HashMap;
+
+/// 记下一次连接的开始，返回这条记录的 id；profile 的 last_connected_at 一并更新
+pub async fn start_connection(
+    ctx: &AppContext,
+    target: &str,
+    plan: &ConnectPlan,
+) -> AppResult<u32> {
+    let profile_id = plan.hops.last().and_then(|h| h.id);
+    let hops = plan
+        .hops
+        .iter()
+        .map(|h| format!("{}@{}:{}", h.user, h.host, h.port))
+        .join(" -> ");
+
+    let row = entity::connections::ActiveModel {
+        profile_id: Set(profile_id),
+        target: Set(target.to_string()),
+        hops: Set(hops),
+        ..Default::default()
+    }
+    .insert(&ctx.db)
+    .await?;
+
+    if let Some(id) = profile_id {
+        profiles::Entity::update_many()
+            .col_expr(
+                profiles::Column::LastConnectedAt,
+                Expr::cust("datetime('now')"),
+            )
+            .filter(profiles::Column::Id.eq(id))
+            .exec(&ctx.db)
+            .await?;
+    }
+
+    Ok(row.id)
+}
+
+/// ssh 退出后补上结束时间和退出码；ssh 根本没跑起来时 exit_code 为 None
+pub async fn finish_connection(
+    ctx: &AppContext,
+    connection_id: u32,
+    exit_code: Option<u8>,
+) -> AppResult<()> {
+    entity::connections::Entity::update_many()
+        .col_expr(
+            entity::connections::Column::EndedAt,
+            Expr::cust("datetime('now')"),
+        )
+        .col_expr(
+            entity::connections::Column::ExitCode,
+            Expr::value(exit_code.map(i32::from)),
+        )
+        .filter(entity::connections::Column::Id.eq(connection_id))
+        .exec(&ctx.db)
+        .await?;
+    Ok(())
+}
+
+/// 最近的连接在前；`profile_id` 给了就只看这个 profile 的
+pub async fn list_history(
+    ctx: &AppContext,
+    profile_id: Option<u32>,
+    limit: u32,
+) -> AppResult<Vec<HistoryEntry>> {
+    // 时间换成本地时区、时长直接让 SQLite 算
+    let rows = ctx
+        .db
+        .query_all(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            r#"SELECT "target", "hops",
+                      datetime("started_at", 'localtime') AS "started_at",
+                      CAST(round((julianday("ended_at") - julianday("started_at")) * 86400) AS integer) AS "duration_secs",
+                      "exit_code"
+               FROM "connections"
+               WHERE ?1 IS NULL OR "profile_id" = ?1
+               ORDER BY "id" DESC
+               LIMIT ?2"#,
+            [profile_id.into(), limit.into()],
+        ))
+        .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(HistoryEntry {
+                target: row.try_get("", "target")?,
+                hops: row.try_get("", "hops")?,
+                started_at: row.try_get("", "started_at")?,
+                duration_secs: row.try_get("", "duration_secs")?,
+                exit_code: row.try_get("", "exit_code")?,
+            })
+        })
+        .collect()
+}
+
+/// 上一次连接过、而且现在还在的 profile
+pub async fn last_profile_id(ctx: &AppContext) -> AppResult<Option<u32>> {
+    let row = ctx
+        .db
+        .query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            r#"SELECT "c"."profile_id" AS "profile_id"
+               FROM "connections" "c" JOIN "profiles" "p" ON "p"."id" = "c"."profile_id"
+               ORDER BY "c"."id" DESC
+               LIMIT 1"#,
+        ))
+        .await?;
+
+    Ok(row
+        .map(|r| r.try_get::<u32>("", "profile_id"))
+        .transpose()?)
+}
+
+/// frecency：每次连接按距今多久给权重再求和，越近、越频繁分越高（思路同 Firefox 地址栏）
+pub async fn frecency_by_profile<C>(db: &C) -> AppResult<HashMap<u32, f64>>
+where
+    C: ConnectionTrait,
+{
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            r#"SELECT "profile_id", julianday('now') - julianday("started_at") AS "age_days"
+               FROM "connections" WHERE "profile_id" IS NOT NULL"#,
+        ))
+        .await?;
+
+    let mut scores = HashMap::new();
+    for row in rows {
+        let id: u32 = row.try_get("", "profile_id")?;
+        let age: f64 = row.try_get("", "age_days")?;
+        *scores.entry(id).or_insert(0.0) += age_weight(age);
+    }
+    Ok(scores)
+}
+
+fn age_weight(age_days: f64) -> f64 {
+    match age_days {
+        d if d < 1.0 => 100.0,
+        d if d < 7.0 => 70.0,
+        d if d < 30.0 => 50.0,
+        d if d < 90.0 => 30.0,
+        _ => 10.0,
+    }
+}
+
+/// 删 profile 时把历史里的引用断开，记录本身留着
+pub async fn detach_profiles<C>(db: &C, profile_ids: &[u32]) -> AppResult<()>
+where
+    C: ConnectionTrait,
+{
+    entity::connections::Entity::update_many()
+        .col_expr(
+            entity::connections::Column::ProfileId,
+            Expr::value(Option::<u32>::None),
+        )
+        .filter(entity::connections::Column::ProfileId.is_in(profile_ids.to_vec()))
+        .exec(db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,495 @@
+//! 把外部来源翻译好的 profile（`ImportBatch`）写进库里。
+//!
+//! 整批在一个事务里：先建 / 改 profile，再按 label 连 routes（同一批里互相引用也能连上），
+//! 跳板链成环之类的错误会让整批回滚。`dry_run` 完整走一遍再回滚，预览和真正导入的结果一致。
+//! 密码库不在事务里，提交之后才写，写不进去的只记提示。
+
+use crate::app::AppContext;
+use crate::entity;
+use crate::entity::profiles;
+use crate::entity::profiles::AuthMode;
+use crate::error::AppResult;
+use crate::usecase::profile::{
+    apply_option_changes, delete_profile_rows, parse_ssh_option, replace_jumps_for_profile,
+    replace_key_for_profile, validate_key_path,
+};
+use crate::usecase::tag::{self, TagChanges, normalize_tag};
+use crate::usecase::{
+    ConflictPolicy, ImportAction, ImportBatch, ImportEntry, ImportMode, ImportOutcome,
+    ImportedProfile,
+};
+use itertools::Itertools;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use std::collections::{HashMap, HashSet};
+
+pub async fn import_profiles(
+    ctx: &AppContext,
+    batch: ImportBatch,
+    mode: ImportMode,
+    dry_run: bool,
+) -> AppResult<ImportOutcome> {
+    let txn = ctx.db.begin().await?;
+
+    let mut existing = profiles::Entity::find()
+        .all(&txn)
+        .await?
+        .into_iter()
+        .filter_map(|m| m.label.clone().map(|l| (l, m)))
+        .collect::<HashMap<_, _>>();
+
+    let mut outcome = ImportOutcome {
+        warnings: batch.warnings,
+        ..Default::default()
+    };
+
+    // Replace：先删掉来源里没有的，剩下的一律覆盖
+    let mut removed = Vec::new();
+    let policy = match mode {
+        ImportMode::Merge(policy) => policy,
+        ImportMode::Replace => {
+            let incoming = batch
+                .profiles
+                .iter()
+                .map(|p| &p.label)
+                .collect::<HashSet<_>>();
+            let stale = existing
+                .values()
+                .filter(|m| !m.label.as_ref().is_some_and(|l| incoming.contains(l)))
+                .sorted_by(|a, b| a.label.cmp(&b.label))
+                .cloned()
+                .collect_vec();
+
+            for m in &stale {
+                outcome.entries.push(ImportEntry {
+                    label: m.label.clone().unwrap_or_else(|| format!("#{}", m.id)),
+                    action: ImportAction::Remove,
+                    dest: format!("{}@{}:{}", m.username, m.hostname, m.port.unwrap_or(22)),
+                    jumps: Vec::new(),
+                });
+            }
+            removed = stale
+                .iter()
+                .map(|m| {
+                    (
+                        m.id,
+                        m.label.clone().unwrap_or_else(|| format!("#{}", m.id)),
+                    )
+                })
+                .collect_vec();
+            let ids = removed.iter().map(|(id, _)| *id).collect_vec();
+            delete_profile_rows(&txn, &ids).await?;
+            existing.retain(|_, m| !ids.contains(&m.id));
+
+            ConflictPolicy::Overwrite
+        }
+    };
+
+    let mut taken = existing
+        .keys()
+        .chain(batch.profiles.iter().map(|p| &p.label))
+        .cloned()
+        .collect::<HashSet<_>>();
+
+    // 导入时的 label -> 落库的 label（rename 之后不一样）
+    let mut renamed = HashMap::new();
+    // 写进库里的 profile，等全部建好再连 routes、存密码
+    let mut written = Vec::new();
+
+    for p in batch.profiles {
+        let (label, action) = match (existing.get(&p.label), policy) {
+            (None, _) => (p.label.clone(), ImportAction::Create),
+            (Some(_), ConflictPolicy::Skip) => (p.label.clone(), ImportAction::Skip),
+            (Some(_), ConflictPolicy::Overwrite) => (p.label.clone(), ImportAction::Overwrite),
+            (Some(_), ConflictPolicy::Rename) => {
+                let label = free_label(&p.label, &taken);
+                taken.insert(label.clone());
+                renamed.insert(p.label.clone(), label.clone());
+                (
+                    label,
+                    ImportAction::Rename {
+                        from: p.label.clone(),
+                    },
+                )
+            }
+        };
+
+        // 预览里显示落库后的样子：没给的字段取默认值，覆盖时取库里原来的
+        let (user, port) = match (&action, existing.get(&p.label)) {
+            (ImportAction::Overwrite | ImportAction::Skip, Some(m)) => (
+                p.user.clone().unwrap_or_else(|| m.username.clone()),
+                p.port.or(m.port).unwrap_or(22),
+            ),
+            _ => (
+                p.user
+                    .clone()
+                    .unwrap_or_else(|| ctx.config.defaults.user.clone()),
+                p.port.unwrap_or(ctx.config.defaults.port),
+            ),
+        };
+        outcome.entries.push(ImportEntry {
+            label: label.clone(),
+            action: action.clone(),
+            dest: format!("{user}@{}:{port}", p.host),
+            jumps: p.jumps.clone(),
+        });
+
+        let id = match action {
+            ImportAction::Skip | ImportAction::Remove => continue,
+            ImportAction::Overwrite => {
+                let model = existing[&p.label].clone();
+                overwrite_profile(&txn, model, &p, batch.complete, &mut outcome.warnings).await?
+            }
+            ImportAction::Create | ImportAction::Rename { .. } => {
+                create_profile(ctx, &txn, &label, &p, batch.complete, &mut outcome.warnings).await?
+            }
+        };
+        written.push((id, label, p));
+    }
+
+    for (id, _, p) in &written {
+        let jumps = p
+            .jumps
+            .iter()
+            .map(|j| renamed.get(j).unwrap_or(j).clone())
+            .collect_vec();
+        if jumps.is_empty() && batch.complete {
+            entity::routes::Entity::delete_many()
+                .filter(entity::routes::Column::ProfileId.eq(*id))
+                .exec(&txn)
+                .await?;
+        }
+        replace_jumps_for_profile(&txn, *id, &jumps).await?;
+    }
+
+    // 跳板 label 也跟着 rename 显示
+    for e in &mut outcome.entries {
+        e.jumps = e
+            .jumps
+            .iter()
+            .map(|j| renamed.get(j).unwrap_or(j).clone())
+            .collect();
+    }
+
+    if dry_run {
+        txn.rollback().await?;
+        return Ok(outcome);
+    }
+
+    txn.commit().await?;
+
+    // 密码库不在事务里：和删除 profile 一样，库里的改动提交之后再写，
+    // 写不进去（没有可用后端、vault 锁着）只提示，profile 已经导入了，之后可以 `password set`
+    for (id, label) in &removed {
+        if let Err(e) = ctx.password_store.set_profile_password(*id, None) {
+            outcome
+                .warnings
+                .push(format!("{label}: stored password not removed: {e}"));
+        }
+    }
+    for (id, label, p) in &written {
+        if let Some(password) = &p.password
+            && let Err(e) = ctx
+                .password_store
+                .set_profile_password(*id, Some(password.clone()))
+        {
+            outcome
+                .warnings
+                .push(format!("{label}: password not stored: {e}"));
+        }
+    }
+    Ok(outcome)
+}
+
+/// `web` 被占用时依次试 `web-2`、`web-3`……
+fn free_label(label: &str, taken: &HashSet<String>) -> String {
+    (2..)
+        .map(|n| format!("{label}-{n}"))
+        .find(|l| !taken.contains(l))
+        .unwrap_or_else(|| label.to_string())
+}
+
+async fn create_profile<C>(
+    ctx: &AppContext,
+    db: &C,
+    label: &str,
+    p: &ImportedProfile,
+    complete: bool,
+    warnings: &mut Vec<String>,
+) -> AppResult<u32>
+where
+    C: ConnectionTrait,
+{
+    let key_path = checked_key_path(label, p, complete, warnings);
+    let auth_mode = p.auth_mode.clone().unwrap_or(match key_path {
+        Some(_) => AuthMode::Key,
+        None => AuthMode::Agent,
+    });
+
+    let model = profiles::ActiveModel {
+        label: Set(Some(label.to_string())),
+        hostname: Set(p.host.clone()),
+        username: Set(p
+            .user
+            .clone()
+            .unwrap_or_else(|| ctx.config.defaults.user.clone())),
+        port: Set(Some(p.port.unwrap_or(ctx.config.defaults.port))),
+        auth_mode: Set(auth_mode),
+        note: Set(p.note.clone()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    if key_path.is_some() {
+        replace_key_for_profile(db, model.id, key_path).await?;
+    }
+    apply_extras(db, model.id, label, p, complete, warnings).await?;
+    Ok(model.id)
+}
+
+/// 来源里有的字段覆盖库里的；没有的（比如 ssh_config 里没有的备注、标签）保持不变，
+/// 除非来源是完整描述（`complete`），那样就和来源完全一致
+async fn overwrite_profile<C>(
+    db: &C,
+    model: profiles::Model,
+    p: &ImportedProfile,
+    complete: bool,
+    warnings: &mut Vec<String>,
+) -> AppResult<u32>
+where
+    C: ConnectionTrait,
+{
+    let key_path = checked_key_path(&p.label, p, complete, warnings);
+
+    let mut active: profiles::ActiveModel = model.into();
+    active.hostname = Set(p.host.clone());
+    if let Some(user) = &p.user {
+        active.username = Set(user.clone());
+    }
+    if let Some(port) = p.port {
+        active.port = Set(Some(port));
+    }
+    match (&p.auth_mode, &key_path) {
+        (Some(mode), _) => active.auth_mode = Set(mode.clone()),
+        (None, Some(_)) => active.auth_mode = Set(AuthMode::Key),
+        (None, None) => {}
+    }
+    if p.note.is_some() || complete {
+        active.note = Set(p.note.clone());
+    }
+    let model = active.update(db).await?;
+
+    if key_path.is_some() || complete {
+        replace_key_for_profile(db, model.id, key_path).await?;
+    }
+    apply_extras(db, model.id, &p.label, p, complete, warnings).await?;
+    Ok(model.id)
+}
+
+/// key 文件不存在或权限太松时不导入这把 key，profile 照样导入；
+/// 完整描述（备份）里的路径照样保留，换了机器 key 可能还没拷过来
+fn checked_key_path(
+    label: &str,
+    p: &ImportedProfile,
+    complete: bool,
+    warnings: &mut Vec<String>,
+) -> Option<String> {
+    let raw = p.key_path.as_deref()?;
+    match validate_key_path(raw) {
+        Ok(path) => Some(path),
+        Err(e) if complete => {
+            warnings.push(format!("{label}: {e} (path kept)"));
+            Some(raw.to_string())
+        }
+        Err(e) => {
+            warnings.push(format!("{label}: key not imported: {e}"));
+            None
+        }
+    }
+}
+
+/// 标签和 ssh 选项：不合法的单项跳过并记一条提示；完整描述时整体替换，否则只加不减
+async fn apply_extras<C>(
+    db: &C,
+    profile_id: u32,
+    label: &str,
+    p: &ImportedProfile,
+    complete: bool,
+    warnings: &mut Vec<String>,
+) -> AppResult<()>
+where
+    C: ConnectionTrait,
+{
+    let mut tags = Vec::new();
+    for raw in &p.tags {
+        match normalize_tag(raw) {
+            Ok(t) => tags.push(t),
+            Err(e) => warnings.push(format!("{label}: tag skipped: {e}")),
+        }
+    }
+    let changes = if complete {
+        TagChanges {
+            replace: Some(tags),
+            ..Default::default()
+        }
+    } else {
+        TagChanges {
+            add: tags,
+            ..Default::default()
+        }
+    };
+    tag::apply_tag_changes(db, profile_id, &changes).await?;
+
+    let mut options = Vec::new();
+    for (key, value) in &p.options {
+        let raw = format!("{key}={value}");
+        match parse_ssh_option(&raw) {
+            Ok(_) => options.push(raw),
+            Err(e) => warnings.push(format!("{label}: option skipped: {e}")),
+        }
+    }
+    if complete {
+        entity::profile_options::Entity::delete_many()
+            .filter(entity::profile_options::Column::ProfileId.eq(profile_id))
+            .exec(db)
+            .await?;
+    }
+    apply_option_changes(db, profile_id, &options, &[]).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_context;
+    use crate::usecase::backup::{self, BackupFormat};
+    use std::sync::atomic::Ordering;
+
+    const SEED: &str = r#"{
+        "version": 1,
+        "profiles": [
+            {"label": "bastion", "host": "gw.example.com", "user": "ops", "port": 2222,
+             "mode": "password", "password": "gw-secret", "tags": ["infra"]},
+            {"label": "web", "host": "10.0.0.5", "user": "deploy", "port": 22, "mode": "key",
+             "key_path": "/nonexistent/id_web", "jumps": ["bastion"], "tags": ["prod", "web"],
+             "note": "frontend", "options": {"ServerAliveInterval": "15"}},
+            {"label": "db", "host": "10.0.0.6", "user": "dba", "port": 5022, "mode": "agent",
+             "jumps": ["bastion", "web"]}
+        ]
+    }"#;
+
+    fn batch(text: &str, format: BackupFormat) -> ImportBatch {
+        backup::document_to_batch(backup::decode(text, format).unwrap()).unwrap()
+    }
+
+    async fn export(ctx: &AppContext, format: BackupFormat) -> String {
+        let doc = backup::build_document(ctx, true).await.unwrap();
+        backup::encode(&doc, format).unwrap()
+    }
+
+    #[tokio::test]
+    async fn export_import_export_round_trips() {
+        let (source, _) = test_context().await;
+        import_profiles(
+            &source,
+            batch(SEED, BackupFormat::Json),
+            ImportMode::Merge(ConflictPolicy::Skip),
+            false,
+        )
+        .await
+        .unwrap();
+
+        for format in [BackupFormat::Json, BackupFormat::Toml] {
+            let first = export(&source, format).await;
+            assert!(first.contains("gw-secret"));
+
+            let (target, _) = test_context().await;
+            import_profiles(
+                &target,
+                batch(&first, format),
+                ImportMode::Merge(ConflictPolicy::Skip),
+                false,
+            )
+            .await
+            .unwrap();
+            assert_eq!(export(&target, format).await, first);
+        }
+    }
+
+    #[tokio::test]
+    async fn replace_removes_stale_profiles_and_their_passwords() {
+        let (ctx, store) = test_context().await;
+        let stale = r#"{"version": 1, "profiles": [
+            {"label": "old", "host": "old.example.com", "user": "root", "port": 22,
+             "mode": "password", "password": "old-secret"},
+            {"label": "web", "host": "192.168.1.1", "user": "root", "port": 22, "mode": "agent",
+             "tags": ["legacy"], "options": {"Compression": "no"}}
+        ]}"#;
+        import_profiles(
+            &ctx,
+            batch(stale, BackupFormat::Json),
+            ImportMode::Merge(ConflictPolicy::Skip),
+            false,
+        )
+        .await
+        .unwrap();
+        let old_ids = store.ids();
+        assert_eq!(old_ids.len(), 1);
+
+        let (source, _) = test_context().await;
+        import_profiles(
+            &source,
+            batch(SEED, BackupFormat::Json),
+            ImportMode::Merge(ConflictPolicy::Skip),
+            false,
+        )
+        .await
+        .unwrap();
+        let wanted = export(&source, BackupFormat::Json).await;
+
+        let outcome = import_profiles(
+            &ctx,
+            batch(&wanted, BackupFormat::Json),
+            ImportMode::Replace,
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(
+            outcome
+                .entries
+                .iter()
+                .any(|e| e.label == "old" && e.action == ImportAction::Remove)
+        );
+        assert_eq!(export(&ctx, BackupFormat::Json).await, wanted);
+        assert!(store.ids().iter().all(|id| !old_ids.contains(id)));
+    }
+
+    #[tokio::test]
+    async fn password_store_failures_do_not_undo_the_import() {
+        let (ctx, store) = test_context().await;
+        store.fail_writes.store(true, Ordering::Relaxed);
+
+        let outcome = import_profiles(
+            &ctx,
+            batch(SEED, BackupFormat::Json),
+            ImportMode::Merge(ConflictPolicy::Skip),
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert!(
+            outcome
+                .warnings
+                .iter()
+                .any(|w| w.starts_with("bastion: password not stored")),
+            "{:?}",
+            outcome.warnings
+        );
+        let doc = backup::build_document(&ctx, true).await.unwrap();
+        assert_eq!(doc.profiles.len(), 3);
+        assert!(doc.profiles.iter().all(|p| p.password.is_none()));
+    }
+}
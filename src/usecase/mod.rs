@@ -1,8 +1,17 @@
 use crate::entity::profiles::AuthMode;
 
+pub mod backup;
 pub mod connect;
+pub mod export;
+pub mod fuzzy;
+pub mod history;
+pub mod import;
 pub mod password;
 pub mod profile;
+pub mod query;
+pub mod route;
+pub mod tag;
+pub mod vault;
 
 #[derive(Debug, Clone)]
 pub struct EditProfileInput {
@@ -11,9 +20,39 @@ pub struct EditProfileInput {
     pub user: Option<String>,
     pub port: Option<u16>,
     pub mode: Option<String>,
+    /// `--tags a,b`：整体替换（空字符串清空）
     pub tags: Option<String>,
+    /// `--tag +x` / `--tag -x`：在现有标签上增减
+    pub tag_changes: Vec<String>,
     pub notes: Option<String>,
+    pub key_path: Option<String>,
+    pub clear_key: bool,
     pub jumps: Vec<String>,
+    /// 追加 / 覆盖的 ssh 选项，原始的 `Key=Value`
+    pub options: Vec<String>,
+    /// 要删掉的 ssh 选项名
+    pub unset_options: Vec<String>,
+}
+
+/// 删除时如何处理「被别的 profile 当作跳板」的情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteMode {
+    /// 有依赖就拒绝
+    Refuse,
+    /// 只删掉指向它的 jump，依赖方保留
+    Force,
+    /// 依赖方（递归）一起删
+    Cascade,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeleteProfileOutcome {
+    /// 实际删掉的 profile（第一个是目标本身）
+    pub removed: Vec<String>,
+    /// 被摘掉 jump 的 profile（--force）
+    pub detached: Vec<String>,
+    /// profile 已删，但密码库里的条目没清掉（keyring 不可用、vault 锁着……）
+    pub secret_warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -25,8 +64,132 @@ pub struct ProfileView {
     pub user: String,
     pub port: u16,
     pub mode: String,
-    pub tags: Option<String>,
+    pub tags: Vec<String>,
     pub note: Option<String>,
+    pub key_path: Option<String>,
+    /// profile 自己的 ssh 选项（`-o Key=Value`）
+    pub options: Vec<(String, String)>,
+    /// 最近一次连接的时间（SQLite `datetime('now')`，UTC），没连过为 None
+    pub last_connected_at: Option<String>,
+}
+
+#[cfg(test)]
+impl ProfileView {
+    /// 测试用：`{label}.example.com`、deploy、22 端口，没有 note / key / 选项
+    pub(crate) fn fixture(label: &str, mode: &str, tags: &[&str]) -> Self {
+        Self {
+            id: 1,
+            label: label.to_string(),
+            host: format!("{label}.example.com"),
+            user: "deploy".to_string(),
+            port: 22,
+            mode: mode.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            note: None,
+            key_path: None,
+            options: Vec::new(),
+            last_connected_at: None,
+        }
+    }
+}
+
+/// 导出用的完整快照：profile 本身加上按 label 表示的（直接）跳板
+#[derive(Debug, Clone)]
+pub struct ProfileExport {
+    pub profile: ProfileView,
+    pub jumps: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    /// 当时连接用的 label 或原始地址
+    pub target: String,
+    pub hops: String,
+    /// 本地时间
+    pub started_at: String,
+    /// 还没结束（或 jmssh 被杀掉）时为 None
+    pub duration_secs: Option<i64>,
+    pub exit_code: Option<i32>,
+}
+
+/// 外部来源（ssh_config 等）翻译出来的一条 profile，还没落库
+#[derive(Debug, Clone, Default)]
+pub struct ImportedProfile {
+    pub label: String,
+    pub host: String,
+    /// 没给时取 config.toml 的 [defaults]
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// 没给时有 key 就是 key，否则 agent
+    pub auth_mode: Option<AuthMode>,
+    /// 原始路径，导入时再校验
+    pub key_path: Option<String>,
+    /// 跳板 label，可以指向同一批里的 profile，也可以是库里已有的
+    pub jumps: Vec<String>,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+    pub options: Vec<(String, String)>,
+    /// 只有备份文件带了密码（`--include-secrets`）时才有
+    pub password: Option<String>,
+}
+
+/// 一次导入的全部内容，外加解析时没法翻译的东西
+#[derive(Debug, Clone, Default)]
+pub struct ImportBatch {
+    pub profiles: Vec<ImportedProfile>,
+    pub warnings: Vec<String>,
+    /// 每条都是 profile 的完整描述（jmssh 自己的备份）：覆盖时来源里没有的标签、选项、key、跳板也清掉，
+    /// key 文件在本机不存在时照样保留路径
+    pub complete: bool,
+}
+
+/// 合并进现有的库，还是让库和来源完全一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    Merge(ConflictPolicy),
+    /// 同名的覆盖，来源里没有的 profile 删掉
+    Replace,
+}
+
+/// 导入的 label 在库里已经存在时怎么办
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConflictPolicy {
+    // 下面的文档会出现在 `--help` 里
+    /// Keep the existing profile and ignore the imported one
+    #[default]
+    Skip,
+    /// Replace the existing profile's fields with the imported ones
+    Overwrite,
+    /// Import under a free label such as web-2
+    Rename,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportAction {
+    Create,
+    Overwrite,
+    Skip,
+    Rename {
+        from: String,
+    },
+    /// `ImportMode::Replace` 时库里有、来源里没有
+    Remove,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportEntry {
+    /// 最终落库的 label
+    pub label: String,
+    pub action: ImportAction,
+    /// user@host:port
+    pub dest: String,
+    pub jumps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOutcome {
+    pub entries: Vec<ImportEntry>,
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -35,17 +198,26 @@ pub struct ConnectInput {
     pub target: String,
     /// 可选：直接按 id 连接
     pub id: Option<u32>,
+    /// 临时目标的跳板（label 或原始地址）
+    pub jumps: Vec<String>,
+    /// 临时目标的认证方式
+    pub mode: Option<String>,
+    /// 连接成功后把临时目标存成这个 label
+    pub save_as: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConnectHop {
-    pub id: u32,
+    /// 临时（不在库里的）hop 没有 id
+    pub id: Option<u32>,
     pub label: String,
     pub host: String,
     pub user: String,
     pub port: u16,
     pub auth_mode: AuthMode,
     pub key_path_local: Option<String>,
+    /// 这一跳自己的 ssh 选项
+    pub options: Vec<(String, String)>,
 }
 
 #[derive(Debug, Clone)]
@@ -53,3 +225,13 @@ pub struct ConnectPlan {
     /// 从第一个 jump 到最终目标，按顺序排列
     pub hops: Vec<ConnectHop>,
 }
+
+/// 自动填充用的单跳密钥：按提示里的 `user@host` 或 key 路径匹配
+#[derive(Debug, Clone)]
+pub struct HopSecret {
+    pub user: String,
+    pub host: String,
+    /// Some：这是这把私钥的口令，只回答 passphrase 提示；None：登录密码，只回答密码提示
+    pub key_path: Option<String>,
+    pub secret: String,
+}
@@ -1,15 +1,20 @@
 use crate::app::AppContext;
+use crate::config::SortOrder;
 use crate::entity;
 use crate::entity::profiles;
 use crate::entity::profiles::AuthMode;
 use crate::error::{AppError, AppResult};
-use crate::usecase::{EditProfileInput, ProfileView};
+use crate::usecase::history;
+use crate::usecase::query::Query;
+use crate::usecase::tag::{self, TagChanges};
+use crate::usecase::{DeleteMode, DeleteProfileOutcome, EditProfileInput, ProfileView, route};
 use itertools::Itertools;
 use sea_orm::{ActiveModelTrait, QueryFilter, QueryOrder, TransactionTrait};
-use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, Set};
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, EntityTrait, Set};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-fn to_view(model: profiles::Model) -> ProfileView {
+fn to_view(model: profiles::Model, key_path: Option<String>) -> ProfileView {
     ProfileView {
         id: model.id,
         label: model.label.unwrap_or_default(),
@@ -17,12 +22,102 @@ fn to_view(model: profiles::Model) -> ProfileView {
         user: model.username,
         port: model.port.unwrap_or(22),
         mode: model.auth_mode.as_str().to_string(),
-        tags: model.tags,
+        tags: Vec::new(),
         note: model.note,
+        key_path,
+        options: Vec::new(),
+        last_connected_at: model.last_connected_at,
     }
 }
 
-async fn replace_jumps_for_profile<C>(db: &C, profile_id: u32, jumps: &[String]) -> AppResult<()>
+/// 校验私钥路径：展开 `~`、转绝对路径、必须是文件，Unix 上不能对 group/other 开放
+/// （和 ssh 自己的 "UNPROTECTED PRIVATE KEY FILE" 检查一致）
+pub fn validate_key_path(raw: &str) -> AppResult<String> {
+    let expanded = match raw.strip_prefix("~/") {
+        Some(rest) => directories::BaseDirs::new()
+            .map(|b| b.home_dir().join(rest))
+            .ok_or_else(|| AppError::InvalidKeyPath(format!("{raw}: cannot resolve home dir")))?,
+        None => PathBuf::from(raw),
+    };
+    let path = std::path::absolute(&expanded)
+        .map_err(|e| AppError::InvalidKeyPath(format!("{raw}: {e}")))?;
+
+    let meta = std::fs::metadata(&path)
+        .map_err(|e| AppError::InvalidKeyPath(format!("{}: {e}", path.display())))?;
+    if !meta.is_file() {
+        return Err(AppError::InvalidKeyPath(format!(
+            "{}: not a regular file",
+            path.display()
+        )));
+    }
+
+    check_key_permissions(&path, &meta)?;
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[cfg(unix)]
+fn check_key_permissions(path: &Path, meta: &std::fs::Metadata) -> AppResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = meta.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        return Err(AppError::InvalidKeyPath(format!(
+            "{}: permissions {mode:04o} are too open; run `chmod 600` on it",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_key_permissions(_path: &Path, _meta: &std::fs::Metadata) -> AppResult<()> {
+    Ok(())
+}
+
+async fn key_path_for_profile<C>(db: &C, profile_id: u32) -> AppResult<Option<String>>
+where
+    C: ConnectionTrait,
+{
+    let row = entity::local_auth::Entity::find()
+        .filter(entity::local_auth::Column::ProfileId.eq(profile_id))
+        .one(db)
+        .await?;
+    Ok(row.and_then(|r| r.key_path_local))
+}
+
+/// 写入 / 清除 local_auth：`None` 表示删掉这条 profile 的 key 记录
+pub async fn replace_key_for_profile<C>(
+    db: &C,
+    profile_id: u32,
+    key_path: Option<String>,
+) -> AppResult<()>
+where
+    C: ConnectionTrait,
+{
+    entity::local_auth::Entity::delete_many()
+        .filter(entity::local_auth::Column::ProfileId.eq(profile_id))
+        .exec(db)
+        .await?;
+
+    if let Some(path) = key_path {
+        entity::local_auth::ActiveModel {
+            profile_id: Set(profile_id),
+            key_path_local: Set(Some(path)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn replace_jumps_for_profile<C>(
+    db: &C,
+    profile_id: u32,
+    jumps: &[String],
+) -> AppResult<()>
 where
     C: ConnectionTrait,
 {
@@ -72,13 +167,127 @@ where
         active.insert(db).await?;
     }
 
+    // 新链可能和别的 profile 的链连成环；在同一个事务里展开校验一遍，失败则整体回滚
+    route::resolve_jump_chain(db, profile_id).await?;
+
     Ok(())
 }
 
+/// 这些由 profile 自己的字段 / jump 链决定，不允许用 `--opt` 覆盖
+const MANAGED_SSH_OPTIONS: &[(&str, &str)] = &[
+    ("HostName", "--host"),
+    ("User", "--user"),
+    ("Port", "--port"),
+    ("ProxyJump", "--jump"),
+];
+
+fn validate_option_key(key: &str) -> AppResult<()> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AppError::InvalidSshOption(format!(
+            "{key:?}: option names are letters and digits only, e.g. ServerAliveInterval"
+        )));
+    }
+    if let Some((name, flag)) = MANAGED_SSH_OPTIONS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+    {
+        return Err(AppError::InvalidSshOption(format!(
+            "{name} is managed by jmssh; use {flag} instead"
+        )));
+    }
+    Ok(())
+}
+
+/// `Key=Value` -> (Key, Value)
+pub fn parse_ssh_option(raw: &str) -> AppResult<(String, String)> {
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| AppError::InvalidSshOption(format!("{raw:?}: expected Key=Value")))?;
+    let (key, value) = (key.trim(), value.trim());
+
+    validate_option_key(key)?;
+    if value.is_empty() || value.chars().any(char::is_control) {
+        return Err(AppError::InvalidSshOption(format!(
+            "{raw:?}: value must be non-empty and on a single line"
+        )));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// 先删 `unset`，再按 key（不区分大小写）覆盖写入 `set`
+pub async fn apply_option_changes<C>(
+    db: &C,
+    profile_id: u32,
+    set: &[String],
+    unset: &[String],
+) -> AppResult<()>
+where
+    C: ConnectionTrait,
+{
+    let set = set
+        .iter()
+        .map(|raw| parse_ssh_option(raw))
+        .collect::<AppResult<Vec<_>>>()?;
+
+    let existing = entity::profile_options::Entity::find()
+        .filter(entity::profile_options::Column::ProfileId.eq(profile_id))
+        .all(db)
+        .await?;
+
+    let stale = existing
+        .iter()
+        .filter(|row| {
+            unset.iter().any(|k| k.eq_ignore_ascii_case(&row.key))
+                || set.iter().any(|(k, _)| k.eq_ignore_ascii_case(&row.key))
+        })
+        .map(|row| row.id)
+        .collect_vec();
+
+    if !stale.is_empty() {
+        entity::profile_options::Entity::delete_many()
+            .filter(entity::profile_options::Column::Id.is_in(stale))
+            .exec(db)
+            .await?;
+    }
+
+    for (key, value) in set {
+        entity::profile_options::ActiveModel {
+            profile_id: Set(profile_id),
+            key: Set(key),
+            value: Set(value),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// 批量查 ssh 选项，按 profile 分组（组内按写入顺序）
+pub async fn options_for_profiles<C>(
+    db: &C,
+    profile_ids: &[u32],
+) -> AppResult<HashMap<u32, Vec<(String, String)>>>
+where
+    C: ConnectionTrait,
+{
+    Ok(entity::profile_options::Entity::find()
+        .filter(entity::profile_options::Column::ProfileId.is_in(profile_ids.to_vec()))
+        .order_by_asc(entity::profile_options::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|row| (row.profile_id, (row.key, row.value)))
+        .into_group_map())
+}
+
 pub async fn add_profile(ctx: &AppContext, input: EditProfileInput) -> AppResult<ProfileView> {
-    let host = input.host.unwrap_or_else(|| "127.0.0.1".to_string());
-    let user = input.user.unwrap_or_else(|| "root".to_string());
-    let port = input.port.unwrap_or(22);
+    // 没给的字段取 config.toml 里的 [defaults]
+    let defaults = &ctx.config.defaults;
+    let host = input.host.unwrap_or_else(|| defaults.host.clone());
+    let user = input.user.unwrap_or_else(|| defaults.user.clone());
+    let port = input.port.unwrap_or(defaults.port);
 
     let txn = ctx.db.begin().await?;
 
@@ -91,7 +300,18 @@ pub async fn add_profile(ctx: &AppContext, input: EditProfileInput) -> AppResult
         return Err(AppError::ProfileAlreadyExists(input.label.clone()));
     }
 
-    let auth_mode = AuthMode::from_str(input.mode.as_deref())?;
+    let key_path = input
+        .key_path
+        .as_deref()
+        .map(validate_key_path)
+        .transpose()?;
+    let tag_changes = TagChanges::parse(input.tags.as_deref(), &input.tag_changes)?;
+
+    // 只给了 --key 没给 --mode 时，默认就是想用这把 key
+    let auth_mode = match (input.mode.as_deref(), &key_path) {
+        (None, Some(_)) => AuthMode::Key,
+        (mode, _) => AuthMode::from_str(mode)?,
+    };
 
     let active = profiles::ActiveModel {
         label: Set(Some(input.label.clone())),
@@ -99,7 +319,6 @@ pub async fn add_profile(ctx: &AppContext, input: EditProfileInput) -> AppResult
         username: Set(user.clone()),
         port: Set(Some(port)),
         auth_mode: Set(auth_mode),
-        tags: Set(input.tags.clone()),
         note: Set(input.notes.clone()),
         ..Default::default()
     };
@@ -110,13 +329,26 @@ pub async fn add_profile(ctx: &AppContext, input: EditProfileInput) -> AppResult
         replace_jumps_for_profile(&txn, model.id, &input.jumps).await?;
     }
 
+    if key_path.is_some() {
+        replace_key_for_profile(&txn, model.id, key_path.clone()).await?;
+    }
+
+    apply_option_changes(&txn, model.id, &input.options, &[]).await?;
+    tag::apply_tag_changes(&txn, model.id, &tag_changes).await?;
+    let mut options = options_for_profiles(&txn, &[model.id]).await?;
+    let mut tags = tag::tags_for_profiles(&txn, &[model.id]).await?;
+
     txn.commit().await?;
 
-    Ok(to_view(model))
+    let mut view = to_view(model, key_path);
+    view.options = options.remove(&view.id).unwrap_or_default();
+    view.tags = tags.remove(&view.id).unwrap_or_default();
+    Ok(view)
 }
 
 pub async fn set_profile(ctx: &AppContext, input: EditProfileInput) -> AppResult<ProfileView> {
     let label = input.label.clone();
+    let tag_changes = TagChanges::parse(input.tags.as_deref(), &input.tag_changes)?;
 
     let txn = ctx.db.begin().await?;
 
@@ -137,9 +369,6 @@ pub async fn set_profile(ctx: &AppContext, input: EditProfileInput) -> AppResult
     if let Some(port) = input.port {
         active.port = Set(Some(port)); // 你 schema 里是 Option<u16> 的话正好
     }
-    if let Some(tags) = input.tags {
-        active.tags = Set(Some(tags));
-    }
     if let Some(note) = input.notes {
         active.note = Set(Some(note));
     }
@@ -153,9 +382,25 @@ pub async fn set_profile(ctx: &AppContext, input: EditProfileInput) -> AppResult
         replace_jumps_for_profile(&txn, model.id, &input.jumps).await?;
     }
 
+    if let Some(raw) = input.key_path.as_deref() {
+        replace_key_for_profile(&txn, model.id, Some(validate_key_path(raw)?)).await?;
+    } else if input.clear_key {
+        replace_key_for_profile(&txn, model.id, None).await?;
+    }
+
+    apply_option_changes(&txn, model.id, &input.options, &input.unset_options).await?;
+    tag::apply_tag_changes(&txn, model.id, &tag_changes).await?;
+
+    let key_path = key_path_for_profile(&txn, model.id).await?;
+    let mut options = options_for_profiles(&txn, &[model.id]).await?;
+    let mut tags = tag::tags_for_profiles(&txn, &[model.id]).await?;
+
     txn.commit().await?;
 
-    Ok(to_view(model))
+    let mut view = to_view(model, key_path);
+    view.options = options.remove(&view.id).unwrap_or_default();
+    view.tags = tags.remove(&view.id).unwrap_or_default();
+    Ok(view)
 }
 
 pub async fn list_profiles(ctx: &AppContext) -> AppResult<Vec<ProfileView>> {
@@ -164,9 +409,75 @@ pub async fn list_profiles(ctx: &AppContext) -> AppResult<Vec<ProfileView>> {
         .all(&ctx.db)
         .await?;
 
-    Ok(rows.into_iter().map(to_view).collect_vec())
+    let mut key_map = entity::local_auth::Entity::find()
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .filter_map(|r| r.key_path_local.map(|k| (r.profile_id, k)))
+        .collect::<HashMap<_, _>>();
+
+    let ids = rows.iter().map(|m| m.id).collect_vec();
+    let mut tag_map = tag::tags_for_profiles(&ctx.db, &ids).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|m| {
+            let key = key_map.remove(&m.id);
+            let tags = tag_map.remove(&m.id).unwrap_or_default();
+            ProfileView {
+                tags,
+                ..to_view(m, key)
+            }
+        })
+        .collect_vec())
+}
+/// 原地排序；`Name` 就是 list_profiles 本来的顺序
+pub async fn sort_profiles(
+    ctx: &AppContext,
+    profiles: &mut [ProfileView],
+    order: SortOrder,
+) -> AppResult<()> {
+    let by_recent =
+        |a: &ProfileView, b: &ProfileView| b.last_connected_at.cmp(&a.last_connected_at);
+
+    match order {
+        SortOrder::Name => profiles.sort_by(|a, b| a.label.cmp(&b.label)),
+        SortOrder::Recent => {
+            profiles.sort_by(|a, b| by_recent(a, b).then_with(|| a.label.cmp(&b.label)))
+        }
+        SortOrder::Frecency => {
+            let scores = history::frecency_by_profile(&ctx.db).await?;
+            let score = |p: &ProfileView| scores.get(&p.id).copied().unwrap_or(0.0);
+            profiles.sort_by(|a, b| {
+                score(b)
+                    .total_cmp(&score(a))
+                    .then_with(|| by_recent(a, b))
+                    .then_with(|| a.label.cmp(&b.label))
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 按查询语言过滤（见 `usecase::query`），顺序同 `list_profiles`
+pub async fn find_profiles(ctx: &AppContext, query: &Query) -> AppResult<Vec<ProfileView>> {
+    Ok(list_profiles(ctx)
+        .await?
+        .into_iter()
+        .filter(|p| query.matches(p))
+        .collect_vec())
+}
+
+pub async fn get_profile_by_id(ctx: &AppContext, id: u32) -> AppResult<ProfileView> {
+    let model = profiles::Entity::find_by_id(id)
+        .one(&ctx.db)
+        .await?
+        .ok_or(AppError::ProfileNotFound(format!("#{id}")))?;
+
+    let key_path = key_path_for_profile(&ctx.db, model.id).await?;
+    Ok(to_view(model, key_path))
 }
-#[allow(dead_code)]
+
 pub async fn get_profile_by_label(ctx: &AppContext, label: String) -> AppResult<ProfileView> {
     let model = profiles::Entity::find()
         .filter(profiles::Column::Label.eq(label.clone()))
@@ -174,7 +485,8 @@ pub async fn get_profile_by_label(ctx: &AppContext, label: String) -> AppResult<
         .await?
         .ok_or(AppError::ProfileNotFound(label))?;
 
-    Ok(to_view(model))
+    let key_path = key_path_for_profile(&ctx.db, model.id).await?;
+    Ok(to_view(model, key_path))
 }
 
 pub async fn get_profile_detail_by_label(
@@ -205,7 +517,7 @@ pub async fn get_profile_detail_by_label(
 
     let id2view = via_models
         .into_iter()
-        .map(|m| (m.id, to_view(m)))
+        .map(|m| (m.id, to_view(m, None)))
         .collect::<HashMap<_, _>>();
 
     let jumps = routes
@@ -213,27 +525,149 @@ pub async fn get_profile_detail_by_label(
         .filter_map(|r| id2view.get(&r.via_profile_id).cloned())
         .collect_vec();
 
-    Ok((to_view(model), jumps))
+    let key_path = key_path_for_profile(&ctx.db, model.id).await?;
+    let mut options = options_for_profiles(&ctx.db, &[model.id]).await?;
+    let mut tags = tag::tags_for_profiles(&ctx.db, &[model.id]).await?;
+
+    let mut view = to_view(model, key_path);
+    view.options = options.remove(&view.id).unwrap_or_default();
+    view.tags = tags.remove(&view.id).unwrap_or_default();
+    Ok((view, jumps))
 }
 
-pub async fn delete_profile_by_label(ctx: &AppContext, label: String) -> AppResult<()> {
+/// 删除 profile，连同它的 routes / local_auth / 密码库条目。
+///
+/// 被别的 profile 当作跳板时按 `mode` 处理：默认拒绝并列出依赖方，
+/// `Force` 摘掉指向它的 jump，`Cascade` 把（递归的）依赖方一起删掉。
+pub async fn delete_profile_by_label(
+    ctx: &AppContext,
+    label: String,
+    mode: DeleteMode,
+) -> AppResult<DeleteProfileOutcome> {
     let txn = ctx.db.begin().await?;
 
     let model = profiles::Entity::find()
         .filter(profiles::Column::Label.eq(label.clone()))
         .one(&txn)
         .await?
-        .ok_or(AppError::ProfileNotFound(label))?;
+        .ok_or(AppError::ProfileNotFound(label.clone()))?;
+
+    let dependents = find_dependents(&txn, model.id).await?;
+    let labels = profiles::Entity::find()
+        .filter(profiles::Column::Id.is_in(dependents.clone()))
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p.label.unwrap_or_else(|| format!("#{}", p.id))))
+        .collect::<HashMap<_, _>>();
+    let label_of = |id: &u32| labels.get(id).cloned().unwrap_or_else(|| format!("#{id}"));
+
+    let mut outcome = DeleteProfileOutcome {
+        removed: vec![label.clone()],
+        ..Default::default()
+    };
+
+    let to_delete = match mode {
+        _ if dependents.is_empty() => vec![model.id],
+        DeleteMode::Refuse => {
+            return Err(AppError::ProfileInUse(
+                label,
+                dependents.iter().map(label_of).join(", "),
+            ));
+        }
+        DeleteMode::Force => {
+            // 只有直接引用它的 profile 需要摘 jump；间接依赖的链会自然变短
+            let direct = entity::routes::Entity::find()
+                .filter(entity::routes::Column::ViaProfileId.eq(model.id))
+                .all(&txn)
+                .await?
+                .into_iter()
+                .map(|r| r.profile_id)
+                .unique()
+                .collect_vec();
+            outcome.detached = direct.iter().map(label_of).collect();
+            vec![model.id]
+        }
+        DeleteMode::Cascade => {
+            outcome.removed.extend(dependents.iter().map(label_of));
+            [vec![model.id], dependents].concat()
+        }
+    };
+
+    delete_profile_rows(&txn, &to_delete).await?;
+    txn.commit().await?;
+
+    // 密码库不在事务里：库里的删除提交之后再清，清不掉（没有可用后端、vault 锁着）只提示，
+    // 留下的孤儿条目不会再被任何 profile 读到
+    for id in &to_delete {
+        if let Err(e) = ctx.password_store.set_profile_password(*id, None) {
+            let name = match *id == model.id {
+                true => label.clone(),
+                false => label_of(id),
+            };
+            outcome.secret_warnings.push(format!("{name}: {e}"));
+        }
+    }
+
+    Ok(outcome)
+}
 
-    // 先删 routes，再删 profile（如果没有外键约束，这两步顺序也无所谓）
+/// 删掉 profile 和挂在它上面的所有行（密码库除外，由调用方处理）
+pub async fn delete_profile_rows<C>(db: &C, ids: &[u32]) -> AppResult<()>
+where
+    C: ConnectionTrait,
+{
+    // routes 两个方向都要清：自己的 jump，以及别人经过它的 jump
     entity::routes::Entity::delete_many()
-        .filter(entity::routes::Column::ProfileId.eq(model.id))
-        .exec(&txn)
+        .filter(
+            Condition::any()
+                .add(entity::routes::Column::ProfileId.is_in(ids.to_vec()))
+                .add(entity::routes::Column::ViaProfileId.is_in(ids.to_vec())),
+        )
+        .exec(db)
         .await?;
 
-    profiles::Entity::delete_by_id(model.id).exec(&txn).await?;
+    entity::local_auth::Entity::delete_many()
+        .filter(entity::local_auth::Column::ProfileId.is_in(ids.to_vec()))
+        .exec(db)
+        .await?;
 
-    txn.commit().await?;
+    entity::profile_options::Entity::delete_many()
+        .filter(entity::profile_options::Column::ProfileId.is_in(ids.to_vec()))
+        .exec(db)
+        .await?;
+
+    entity::profile_tags::Entity::delete_many()
+        .filter(entity::profile_tags::Column::ProfileId.is_in(ids.to_vec()))
+        .exec(db)
+        .await?;
+    tag::prune_orphan_tags(db).await?;
+    history::detach_profiles(db, ids).await?;
 
+    profiles::Entity::delete_many()
+        .filter(profiles::Column::Id.is_in(ids.to_vec()))
+        .exec(db)
+        .await?;
     Ok(())
 }
+
+/// 递归找出所有（直接或间接）经过 `profile_id` 跳转的 profile
+async fn find_dependents<C>(db: &C, profile_id: u32) -> AppResult<Vec<u32>>
+where
+    C: ConnectionTrait,
+{
+    let route_rows = entity::routes::Entity::find().all(db).await?;
+
+    let mut found = Vec::new();
+    let mut queue = vec![profile_id];
+    while let Some(via) = queue.pop() {
+        for r in route_rows.iter().filter(|r| r.via_profile_id == via) {
+            if r.profile_id != profile_id && !found.contains(&r.profile_id) {
+                found.push(r.profile_id);
+                queue.push(r.profile_id);
+            }
+        }
+    }
+
+    Ok(found)
+}
@@ -0,0 +1,335 @@
+//! 选 profile 用的小查询语言，picker 和 `profile list/find` 共用。
+//!
+//! 空白分隔的若干项，全部满足才算匹配：
+//! - `web` / `"web 1"`：在 label、user、host、port、mode、tags、note 里找（不区分大小写）
+//! - `host:` `user:` `label:` `note:`：只在对应字段里找子串
+//! - `mode:`：认证方式（`auto` 等同于 `agent`）；`tag:`：标签完全一致
+//! - `port:22` / `port:2200-2299`：端口或闭区间
+//! - 前面加 `-` 或 `!` 取反，比如 `-tag:legacy`
+
+use crate::error::{AppError, AppResult};
+use crate::usecase::ProfileView;
+use crate::usecase::tag::normalize_tag;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    negated: bool,
+    matcher: Matcher,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Text(String),
+    Label(String),
+    Host(String),
+    User(String),
+    Note(String),
+    Mode(String),
+    Tag(String),
+    Port(RangeInclusive<u16>),
+}
+
+impl Query {
+    pub fn parse(input: &str) -> AppResult<Self> {
+        let terms = tokenize(input)
+            .into_iter()
+            .filter_map(|tok| parse_term(tok).transpose())
+            .collect::<AppResult<Vec<_>>>()?;
+        Ok(Self { terms })
+    }
+
+    /// `profile list --tag prod --tag !legacy`：每个参数都是一个（可取反的）标签项
+    pub fn from_tags(raw: &[String]) -> AppResult<Self> {
+        let terms = raw
+            .iter()
+            .map(|r| {
+                let (negated, name) = match r.strip_prefix('!') {
+                    Some(name) => (true, name),
+                    None => (false, r.as_str()),
+                };
+                Ok(Term {
+                    negated,
+                    matcher: Matcher::Tag(normalize_tag(name)?),
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        Ok(Self { terms })
+    }
+
+    pub fn matches(&self, p: &ProfileView) -> bool {
+        self.terms.iter().all(|t| t.matcher.matches(p) != t.negated)
+    }
+
+    /// 除了正向的普通词以外的条件；picker 把普通词交给模糊匹配打分
+    pub fn matches_structured(&self, p: &ProfileView) -> bool {
+        self.terms
+            .iter()
+            .filter(|t| !t.is_plain_text())
+            .all(|t| t.matcher.matches(p) != t.negated)
+    }
+
+    /// 正向的普通词（不带字段前缀、不取反）
+    pub fn text_terms(&self) -> impl Iterator<Item = &str> {
+        self.terms.iter().filter_map(|t| match &t.matcher {
+            Matcher::Text(s) if !t.negated => Some(s.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// 普通词在所有字段里的子串匹配（和 `profile list/find` 一样）；
+/// picker 里 label / dest 都模糊不中时用它兜底，tags、note、mode 也能搜到
+pub fn text_matches(term: &str, p: &ProfileView) -> bool {
+    Matcher::Text(term.to_lowercase()).matches(p)
+}
+
+impl Term {
+    fn is_plain_text(&self) -> bool {
+        !self.negated && matches!(self.matcher, Matcher::Text(_))
+    }
+}
+
+impl Matcher {
+    fn matches(&self, p: &ProfileView) -> bool {
+        let contains = |hay: &str, needle: &str| hay.to_lowercase().contains(needle);
+        match self {
+            Matcher::Text(s) => {
+                contains(&p.label, s)
+                    || contains(&p.user, s)
+                    || contains(&p.host, s)
+                    || p.port.to_string().contains(s.as_str())
+                    || contains(&p.mode, s)
+                    || p.tags.iter().any(|t| t.contains(s.as_str()))
+                    || p.note.as_deref().is_some_and(|n| contains(n, s))
+            }
+            Matcher::Label(s) => contains(&p.label, s),
+            Matcher::Host(s) => contains(&p.host, s),
+            Matcher::User(s) => contains(&p.user, s),
+            Matcher::Note(s) => p.note.as_deref().is_some_and(|n| contains(n, s)),
+            Matcher::Mode(s) => p.mode.starts_with(s.as_str()),
+            Matcher::Tag(s) => p.tags.iter().any(|t| t == s),
+            Matcher::Port(range) => range.contains(&p.port),
+        }
+    }
+}
+
+/// 一个原始词：是否取反、字段前缀、值
+#[derive(Debug)]
+struct Token {
+    negated: bool,
+    field: Option<String>,
+    value: String,
+}
+
+/// 按空白切词；双引号里的空白不切。引号没闭合就当作到结尾（picker 里边打边搜）
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let negated = chars.next_if(|c| *c == '-' || *c == '!').is_some();
+        let mut field = None;
+        let mut value = String::new();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    for q in chars.by_ref() {
+                        if q == '"' {
+                            break;
+                        }
+                        value.push(q);
+                    }
+                }
+                ':' if field.is_none() && is_field(&value) => {
+                    field = Some(std::mem::take(&mut value).to_lowercase());
+                }
+                c if c.is_whitespace() => break,
+                c => value.push(c),
+            }
+        }
+
+        tokens.push(Token {
+            negated,
+            field,
+            value,
+        });
+    }
+
+    tokens
+}
+
+const FIELDS: &[&str] = &["label", "host", "user", "port", "mode", "tag", "note"];
+
+fn is_field(s: &str) -> bool {
+    FIELDS.iter().any(|f| f.eq_ignore_ascii_case(s))
+}
+
+/// 空值（比如刚敲完 `host:`）不过滤任何东西
+fn parse_term(tok: Token) -> AppResult<Option<Term>> {
+    let value = tok.value.trim().to_lowercase();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let matcher = match tok.field.as_deref() {
+        None => Matcher::Text(value),
+        Some("label") => Matcher::Label(value),
+        Some("host") => Matcher::Host(value),
+        Some("user") => Matcher::User(value),
+        Some("note") => Matcher::Note(value),
+        Some("mode") => Matcher::Mode(match value.as_str() {
+            "auto" => "agent".to_string(),
+            _ => value,
+        }),
+        // 和存进去的标签按同一规则比较，也和 `--tag` 一样拒绝不合法的标签
+        Some("tag") => Matcher::Tag(normalize_tag(&value)?),
+        Some("port") => Matcher::Port(parse_port_range(&value)?),
+        Some(other) => unreachable!("unknown field {other}"),
+    };
+
+    Ok(Some(Term {
+        negated: tok.negated,
+        matcher,
+    }))
+}
+
+fn parse_port_range(raw: &str) -> AppResult<RangeInclusive<u16>> {
+    let port = |s: &str| {
+        s.trim()
+            .parse::<u16>()
+            .map_err(|_| AppError::InvalidQuery(format!("port:{raw}: expected 22 or 2200-2299")))
+    };
+
+    match raw.split_once('-') {
+        Some((lo, hi)) => {
+            let (lo, hi) = (port(lo)?, port(hi)?);
+            if lo > hi {
+                return Err(AppError::InvalidQuery(format!(
+                    "port:{raw}: range start is greater than its end"
+                )));
+            }
+            Ok(lo..=hi)
+        }
+        None => {
+            let p = port(raw)?;
+            Ok(p..=p)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(query: &str, p: &ProfileView) -> bool {
+        Query::parse(query).unwrap().matches(p)
+    }
+
+    #[test]
+    fn tokenize_keeps_quoted_phrases_together() {
+        let toks = tokenize(r#"web "primary db" note:"a b""#);
+        let values = toks.iter().map(|t| t.value.as_str()).collect::<Vec<_>>();
+        assert_eq!(values, ["web", "primary db", "a b"]);
+        assert_eq!(toks[2].field.as_deref(), Some("note"));
+    }
+
+    #[test]
+    fn tokenize_runs_an_unclosed_quote_to_the_end() {
+        let toks = tokenize(r#"-host:"db 1"#);
+        assert_eq!(toks.len(), 1);
+        assert!(toks[0].negated);
+        assert_eq!(toks[0].field.as_deref(), Some("host"));
+        assert_eq!(toks[0].value, "db 1");
+    }
+
+    #[test]
+    fn negation_works_with_dash_and_bang() {
+        let legacy = ProfileView::fixture("old", "agent", &["legacy"]);
+        let fresh = ProfileView::fixture("new", "agent", &["prod"]);
+        for q in ["-tag:legacy", "!tag:legacy"] {
+            assert!(!matches(q, &legacy), "{q}");
+            assert!(matches(q, &fresh), "{q}");
+        }
+        assert!(!matches("!old", &legacy));
+    }
+
+    #[test]
+    fn field_prefixes_only_look_at_their_field() {
+        let p = ProfileView {
+            note: Some("Primary DB".to_string()),
+            ..ProfileView::fixture("web", "key", &["prod"])
+        };
+        assert!(matches("host:web.example", &p));
+        assert!(!matches("user:web", &p));
+        assert!(matches("note:primary", &p));
+        assert!(matches("label:WE", &p));
+        // 刚敲完前缀、还没有值：不过滤
+        assert!(Query::parse("host:").unwrap().terms.is_empty());
+        assert!(matches("host:", &p));
+    }
+
+    #[test]
+    fn tag_values_are_normalized() {
+        let p = ProfileView::fixture("web", "agent", &["prod"]);
+        assert!(matches("tag:PROD", &p));
+        assert!(matches(r#"tag:" prod ""#, &p));
+        assert!(!matches("tag:pro", &p));
+        assert!(matches!(
+            Query::parse("tag:-prod"),
+            Err(AppError::InvalidTag(_))
+        ));
+    }
+
+    #[test]
+    fn mode_auto_means_agent() {
+        assert!(matches(
+            "mode:auto",
+            &ProfileView::fixture("a", "agent", &[])
+        ));
+        assert!(!matches(
+            "mode:auto",
+            &ProfileView::fixture("b", "password", &[])
+        ));
+    }
+
+    #[test]
+    fn port_matches_single_ports_and_ranges() {
+        let ssh = ProfileView::fixture("a", "agent", &[]);
+        let alt = ProfileView {
+            port: 2222,
+            ..ProfileView::fixture("b", "agent", &[])
+        };
+        assert!(matches("port:22", &ssh));
+        assert!(!matches("port:22", &alt));
+        assert!(matches("port:2200-2299", &alt));
+        assert!(!matches("port:2200-2299", &ssh));
+    }
+
+    #[test]
+    fn bad_port_ranges_are_errors() {
+        for q in [
+            "port:2299-2200",
+            "port:ssh",
+            "port:70000",
+            "port:22-",
+            "port:-22",
+        ] {
+            assert!(
+                matches!(Query::parse(q), Err(AppError::InvalidQuery(_))),
+                "{q}"
+            );
+        }
+    }
+}
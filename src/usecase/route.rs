@@ -0,0 +1,160 @@
+use crate::entity::{profiles, routes};
+use crate::error::{AppError, AppResult};
+use itertools::Itertools;
+use sea_orm::{ConnectionTrait, EntityTrait, QueryOrder};
+use std::collections::HashMap;
+
+/// 跳板嵌套的最大层数（A 经 B，B 又经 C ... 算一层）
+pub const MAX_JUMP_DEPTH: usize = 8;
+
+/// 递归展开 profile 的跳板链，返回按连接顺序排好的 via profile id（不含自己）。
+///
+/// 每个 via profile 会被替换成「它自己的展开链 + 它本身」；链上已经经过的 profile 不再重复
+/// （菱形：两个跳板共用同一个 bastion 时只走一次 bastion）。
+/// 出现环或嵌套超过 [`MAX_JUMP_DEPTH`] 时返回 [`AppError::InvalidJumpChain`]。
+pub async fn resolve_jump_chain<C>(db: &C, profile_id: u32) -> AppResult<Vec<u32>>
+where
+    C: ConnectionTrait,
+{
+    // routes 表很小，一次性读进来在内存里 DFS，比逐层查库简单
+    let route_map = routes::Entity::find()
+        .order_by_asc(routes::Column::ProfileId)
+        .order_by_asc(routes::Column::Seq)
+        .all(db)
+        .await?
+        .into_iter()
+        .into_group_map_by(|r| r.profile_id);
+
+    let mut stack = Vec::new();
+    let mut chain = Vec::new();
+
+    if let Err(path) = expand(profile_id, &route_map, &mut stack, &mut chain) {
+        let labels = profiles::Entity::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|p| (p.id, p.label.unwrap_or_else(|| format!("#{}", p.id))))
+            .collect::<HashMap<_, _>>();
+
+        let describe = |ids: &[u32]| {
+            ids.iter()
+                .map(|id| labels.get(id).cloned().unwrap_or_else(|| format!("#{id}")))
+                .join(" -> ")
+        };
+
+        return Err(AppError::InvalidJumpChain(match path {
+            ChainError::Cycle(ids) => format!("cycle detected: {}", describe(&ids)),
+            ChainError::TooDeep(ids) => format!(
+                "nested deeper than {MAX_JUMP_DEPTH} levels: {}",
+                describe(&ids)
+            ),
+        }));
+    }
+
+    Ok(chain)
+}
+
+enum ChainError {
+    Cycle(Vec<u32>),
+    TooDeep(Vec<u32>),
+}
+
+fn expand(
+    id: u32,
+    route_map: &HashMap<u32, Vec<routes::Model>>,
+    stack: &mut Vec<u32>,
+    chain: &mut Vec<u32>,
+) -> Result<(), ChainError> {
+    if let Some(pos) = stack.iter().position(|s| *s == id) {
+        let cycle = stack[pos..].iter().copied().chain([id]).collect_vec();
+        return Err(ChainError::Cycle(cycle));
+    }
+
+    if stack.len() > MAX_JUMP_DEPTH {
+        return Err(ChainError::TooDeep(stack.clone()));
+    }
+
+    stack.push(id);
+    for route in route_map.get(&id).into_iter().flatten() {
+        // 已经在链上的跳板连同它自己的跳板都走过了
+        if chain.contains(&route.via_profile_id) {
+            continue;
+        }
+        expand(route.via_profile_id, route_map, stack, chain)?;
+        chain.push(route.via_profile_id);
+    }
+    stack.pop();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (profile, [via...])
+    fn routes(edges: &[(u32, &[u32])]) -> HashMap<u32, Vec<routes::Model>> {
+        edges
+            .iter()
+            .flat_map(|(profile_id, vias)| {
+                vias.iter().enumerate().map(|(seq, via)| routes::Model {
+                    id: 0,
+                    profile_id: *profile_id,
+                    seq: seq as u32,
+                    via_profile_id: *via,
+                })
+            })
+            .into_group_map_by(|r| r.profile_id)
+    }
+
+    fn chain_of(id: u32, edges: &[(u32, &[u32])]) -> Result<Vec<u32>, ChainError> {
+        let mut chain = Vec::new();
+        expand(id, &routes(edges), &mut Vec::new(), &mut chain)?;
+        Ok(chain)
+    }
+
+    #[test]
+    fn nested_jumps_expand_in_connect_order() {
+        // 1 经 2，2 经 3
+        assert_eq!(chain_of(1, &[(1, &[2]), (2, &[3])]).ok(), Some(vec![3, 2]));
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let Err(ChainError::Cycle(ids)) = chain_of(1, &[(1, &[2]), (2, &[1])]) else {
+            panic!("expected a cycle");
+        };
+        assert_eq!(ids, [1, 2, 1]);
+
+        let Err(ChainError::Cycle(ids)) = chain_of(1, &[(1, &[1])]) else {
+            panic!("expected a self-jump cycle");
+        };
+        assert_eq!(ids, [1, 1]);
+    }
+
+    #[test]
+    fn shared_bastion_is_visited_once() {
+        // 1 经 [2, 3]，2 和 3 都经 4
+        let chain = chain_of(1, &[(1, &[2, 3]), (2, &[4]), (3, &[4])]).ok();
+        assert_eq!(chain, Some(vec![4, 2, 3]));
+
+        // 直接把 bastion 也列出来
+        let chain = chain_of(1, &[(1, &[4, 3]), (3, &[4])]).ok();
+        assert_eq!(chain, Some(vec![4, 3]));
+    }
+
+    #[test]
+    fn chains_deeper_than_the_limit_are_rejected() {
+        let edges = (1..=MAX_JUMP_DEPTH as u32 + 2)
+            .map(|id| (id, vec![id + 1]))
+            .collect_vec();
+        let edges = edges
+            .iter()
+            .map(|(id, via)| (*id, via.as_slice()))
+            .collect_vec();
+        assert!(matches!(chain_of(1, &edges), Err(ChainError::TooDeep(_))));
+
+        let ok = &edges[edges.len() - MAX_JUMP_DEPTH..];
+        assert!(chain_of(ok[0].0, ok).is_ok());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::app::AppContext;
+use crate::entity;
+use crate::error::{AppError, AppResult};
+use itertools::Itertools;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct TagCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// 标签统一小写；首字符必须是字母或数字，这样 `+x` / `-x` / `!x` 的前缀不会有歧义
+pub fn normalize_tag(raw: &str) -> AppResult<String> {
+    let tag = raw.trim().to_lowercase();
+
+    let valid_first = tag.chars().next().is_some_and(char::is_alphanumeric);
+    let valid_rest = tag
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+    if !valid_first || !valid_rest {
+        return Err(AppError::InvalidTag(format!(
+            "{raw:?}: use letters, digits and - _ . / (starting with a letter or digit)"
+        )));
+    }
+
+    Ok(tag)
+}
+
+/// 一次编辑里的标签变化：`--tags a,b` 整体替换，`--tag +x` / `--tag x` 加，`--tag -x` 减
+#[derive(Debug, Clone, Default)]
+pub struct TagChanges {
+    pub replace: Option<Vec<String>>,
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+impl TagChanges {
+    pub fn parse(replace: Option<&str>, changes: &[String]) -> AppResult<Self> {
+        let replace = replace
+            .map(|raw| {
+                raw.split(',')
+                    .filter(|t| !t.trim().is_empty())
+                    .map(normalize_tag)
+                    .collect::<AppResult<Vec<_>>>()
+            })
+            .transpose()?;
+
+        let mut add = Vec::new();
+        let mut remove = Vec::new();
+        for c in changes {
+            if let Some(name) = c.strip_prefix('-') {
+                remove.push(normalize_tag(name)?);
+            } else {
+                add.push(normalize_tag(c.strip_prefix('+').unwrap_or(c))?);
+            }
+        }
+
+        Ok(Self {
+            replace,
+            add,
+            remove,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replace.is_none() && self.add.is_empty() && self.remove.is_empty()
+    }
+}
+
+pub async fn apply_tag_changes<C>(db: &C, profile_id: u32, changes: &TagChanges) -> AppResult<()>
+where
+    C: ConnectionTrait,
+{
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let current = tags_for_profiles(db, &[profile_id])
+        .await?
+        .remove(&profile_id)
+        .unwrap_or_default();
+
+    let wanted = changes
+        .replace
+        .clone()
+        .unwrap_or(current)
+        .into_iter()
+        .chain(changes.add.iter().cloned())
+        .filter(|t| !changes.remove.contains(t))
+        .unique()
+        .collect_vec();
+
+    entity::profile_tags::Entity::delete_many()
+        .filter(entity::profile_tags::Column::ProfileId.eq(profile_id))
+        .exec(db)
+        .await?;
+
+    for name in &wanted {
+        let tag_id = ensure_tag(db, name).await?;
+        entity::profile_tags::ActiveModel {
+            profile_id: Set(profile_id),
+            tag_id: Set(tag_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    prune_orphan_tags(db).await
+}
+
+async fn ensure_tag<C>(db: &C, name: &str) -> AppResult<u32>
+where
+    C: ConnectionTrait,
+{
+    if let Some(tag) = entity::tags::Entity::find()
+        .filter(entity::tags::Column::Name.eq(name))
+        .one(db)
+        .await?
+    {
+        return Ok(tag.id);
+    }
+
+    let tag = entity::tags::ActiveModel {
+        name: Set(name.to_string()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(tag.id)
+}
+
+/// 没有任何 profile 用的标签直接删掉，`jmssh tags` 里就不会出现 0
+pub async fn prune_orphan_tags<C>(db: &C) -> AppResult<()>
+where
+    C: ConnectionTrait,
+{
+    let used = entity::profile_tags::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|r| r.tag_id)
+        .unique()
+        .collect_vec();
+
+    entity::tags::Entity::delete_many()
+        .filter(entity::tags::Column::Id.is_not_in(used))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// 批量查标签，按 profile 分组（组内按名字排序）
+pub async fn tags_for_profiles<C>(
+    db: &C,
+    profile_ids: &[u32],
+) -> AppResult<HashMap<u32, Vec<String>>>
+where
+    C: ConnectionTrait,
+{
+    let links = entity::profile_tags::Entity::find()
+        .filter(entity::profile_tags::Column::ProfileId.is_in(profile_ids.to_vec()))
+        .all(db)
+        .await?;
+
+    let names = entity::tags::Entity::find()
+        .filter(entity::tags::Column::Id.is_in(links.iter().map(|l| l.tag_id).unique()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|t| (t.id, t.name))
+        .collect::<HashMap<_, _>>();
+
+    let mut grouped = links
+        .into_iter()
+        .filter_map(|l| names.get(&l.tag_id).map(|n| (l.profile_id, n.clone())))
+        .into_group_map();
+    grouped.values_mut().for_each(|tags| tags.sort());
+    Ok(grouped)
+}
+
+pub async fn list_tags(ctx: &AppContext) -> AppResult<Vec<TagCount>> {
+    let counts = entity::profile_tags::Entity::find()
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .counts_by(|l| l.tag_id);
+
+    Ok(entity::tags::Entity::find()
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .map(|t| TagCount {
+            count: counts.get(&t.id).copied().unwrap_or(0),
+            name: t.name,
+        })
+        .sorted_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)))
+        .collect())
+}
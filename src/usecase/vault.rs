@@ -0,0 +1,43 @@
+use crate::app;
+use crate::error::{AppError, AppResult};
+use crate::infra::vault_store::VaultPasswordStore;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct VaultStatus {
+    pub path: PathBuf,
+    pub exists: bool,
+    pub unlocked: bool,
+    /// 有没有会话目录缓存解锁后的 key；没有时每条命令现场问口令
+    pub caches_key: bool,
+}
+
+fn open() -> AppResult<VaultPasswordStore> {
+    app::open_vault().map_err(|e| AppError::VaultError(e.to_string()))
+}
+
+pub fn vault_status() -> AppResult<VaultStatus> {
+    let store = open()?;
+    Ok(VaultStatus {
+        path: crate::db::vault_path().map_err(|e| AppError::VaultError(e.to_string()))?,
+        exists: store.exists(),
+        unlocked: store.is_unlocked()?,
+        caches_key: store.caches_key(),
+    })
+}
+
+pub fn create_vault(passphrase: &str) -> AppResult<()> {
+    open()?.create(passphrase)
+}
+
+pub fn unlock_vault(passphrase: &str) -> AppResult<()> {
+    open()?.unlock(passphrase)
+}
+
+pub fn lock_vault() -> AppResult<()> {
+    open()?.lock()
+}
+
+pub fn rekey_vault(old_passphrase: &str, new_passphrase: &str) -> AppResult<()> {
+    open()?.rekey(old_passphrase, new_passphrase)
+}